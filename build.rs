@@ -0,0 +1,54 @@
+// Embeds the git hash and the target triple, shown by `--version --build-info`.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    let git_hash = git(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".to_owned());
+
+    println!("cargo:rustc-env=MOVIE_ALERT_GIT_HASH={}", git_hash);
+    println!("cargo:rustc-env=MOVIE_ALERT_TARGET={}", env::var("TARGET").unwrap_or_default());
+    println!("cargo:rustc-env=MOVIE_ALERT_PROFILE={}", profile());
+
+    // HEAD only changes on a checkout, a commit moves the branch it points
+    // to, whose ref is a file of its own until it is packed
+    let git_dir = git(&["rev-parse", "--git-dir"]).map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(".git"));
+    let mut watched = vec![git_dir.join("HEAD"), git_dir.join("packed-refs")];
+    if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        watched.push(git_dir.join(head_ref));
+    }
+
+    // cargo reruns the script on every build for a path that does not exist
+    for path in watched.iter().filter(|path| path.exists()) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
+
+// the trimmed output of a git command, None without git or outside a repo
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|output| output.trim().to_owned())
+}
+
+// PROFILE is only "debug" or "release", so `--profile dist` would show up as
+// release.  OUT_DIR is target/[<triple>/]<profile>/build/<package>-<hash>/out,
+// with the name of the profile's directory in it.
+fn profile() -> String {
+    env::var("OUT_DIR")
+        .ok()
+        .and_then(|out_dir| {
+            Path::new(&out_dir)
+                .ancestors()
+                .nth(3)
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| env::var("PROFILE").unwrap_or_default())
+}
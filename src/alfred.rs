@@ -0,0 +1,27 @@
+// Script filter output for Alfred, which Raycast also understands: one json
+// document with an item per movie, its url as the argument to open.
+// https://www.alfredapp.com/help/workflows/inputs/script-filter/json/
+
+#[derive(Debug, Serialize)]
+pub struct AlfredIcon {
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlfredItem {
+    pub uid: String,
+    pub title: String,
+    pub subtitle: String,
+    pub arg: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<AlfredIcon>,
+}
+
+#[derive(Debug, Serialize)]
+struct ScriptFilter<'a> {
+    items: &'a [AlfredItem],
+}
+
+pub fn render(items: &[AlfredItem]) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&ScriptFilter { items })
+}
@@ -0,0 +1,355 @@
+// Poster thumbnails of `list --posters` kept in the cache directory, e.g.
+// ~/.cache/movie_alert/posters, so listing again does not download them
+// again.  The cache is kept under a size, the least recently used posters
+// going first, and a file's modification time is when it was last used.
+// Several runs may share the cache: posters are written to a file of their
+// own and renamed into place, and a poster another run evicted first is
+// simply gone.
+//
+// The details and translations of a movie are kept next to them, as json
+// by the id of the movie, e.g. ~/.cache/movie_alert/details/123.json, and
+// taken from there until they are older than a number of days: a movie
+// stays upcoming for months, but its details hardly change.
+
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::clock::{Clock, SystemClock};
+use crate::state::project_dirs;
+use crate::AppError;
+
+/// The size the cache is kept under unless [cache] max_mb says otherwise.
+pub const DEFAULT_MAX_MB: u64 = 50;
+
+/// How long the details and translations of a movie are kept unless
+/// [cache] details_days says otherwise.
+pub const DEFAULT_DETAILS_DAYS: u64 = 7;
+
+const POSTERS_DIR_NAME: &str = "posters";
+const DETAILS_DIR_NAME: &str = "details";
+const TRANSLATIONS_DIR_NAME: &str = "translations";
+
+// posters being written, by the id of the process writing them
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// What is in the cache.
+#[derive(Debug, Default, PartialEq)]
+pub struct CacheStats {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+pub struct PosterCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    clock: Arc<dyn Clock>,
+}
+
+// "/abc.jpg" -> "abc.jpg", nothing that could leave the directory
+fn file_name(poster_path: &str) -> Option<String> {
+    let name = poster_path.trim_start_matches('/');
+    let safe = !name.is_empty() && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+
+    Some(name.to_owned()).filter(|_| safe)
+}
+
+// writes the file as last modified at `time`, which may be a simulated one
+fn write_at(path: &Path, contents: &[u8], time: SystemTime) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+
+    file.set_modified(time)
+}
+
+// leaves NotFound alone, the file went away in the meantime
+fn ignore_gone<T>(result: std::io::Result<T>) -> std::io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl PosterCache {
+    /// The cache in the cache directory, kept under `max_mb` megabytes.
+    pub fn open(max_mb: u64) -> Result<PosterCache, AppError> {
+        PosterCache::in_dir(project_dirs()?.cache_dir().join(POSTERS_DIR_NAME),
+                            max_mb * 1024 * 1024)
+    }
+
+    /// The cache in `dir`, which is created if needed.
+    pub fn in_dir(dir: PathBuf, max_bytes: u64) -> Result<PosterCache, AppError> {
+        fs::create_dir_all(&dir)?;
+
+        Ok(PosterCache { dir, max_bytes, clock: Arc::new(SystemClock) })
+    }
+
+    /// The same cache telling the last use of a poster by `clock`.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        PosterCache { clock, ..self }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The cached thumbnail of the poster, which now counts as just used.
+    pub fn get(&self, poster_path: &str) -> Option<Vec<u8>> {
+        let path = self.dir.join(file_name(poster_path)?);
+        let image = fs::read(&path).ok()?;
+
+        if let Err(e) = File::options().write(true).open(&path)
+            .and_then(|file| file.set_modified(self.clock.now())) {
+            debug!("Cannot mark {} as used: {}", path.display(), e);
+        }
+
+        Some(image)
+    }
+
+    /// Keeps the thumbnail of the poster, evicting the least recently used
+    /// ones beyond the size.
+    pub fn put(&self, poster_path: &str, image: &[u8]) -> Result<(), AppError> {
+        let name = match file_name(poster_path) {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        let partial = self.dir.join(format!("{}.{}{}", name, std::process::id(),
+                                            PARTIAL_SUFFIX));
+        write_at(&partial, image, self.clock.now())?;
+        fs::rename(&partial, self.dir.join(&name))?;
+
+        self.evict()?;
+
+        Ok(())
+    }
+
+    // the cached files with their size and last use, oldest first
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>, AppError> {
+        let mut entries = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().ends_with(PARTIAL_SUFFIX) {
+                continue;
+            }
+
+            if let Some(metadata) = ignore_gone(entry.metadata())? {
+                if metadata.is_file() {
+                    entries.push((entry.path(), metadata.len(), metadata.modified()?));
+                }
+            }
+        }
+        entries.sort_by_key(|&(_, _, used)| used);
+
+        Ok(entries)
+    }
+
+    /// Removes the least recently used posters until the cache is under its
+    /// size, returns how many.
+    pub fn evict(&self) -> Result<usize, AppError> {
+        let entries = self.entries()?;
+        let mut bytes: u64 = entries.iter().map(|&(_, size, _)| size).sum();
+        let mut evicted = 0;
+
+        for (path, size, _) in entries.iter() {
+            if bytes <= self.max_bytes {
+                break;
+            }
+
+            debug!("Evicting {} from the poster cache", path.display());
+            ignore_gone(fs::remove_file(path))?;
+            bytes -= size;
+            evicted += 1;
+        }
+
+        Ok(evicted)
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, AppError> {
+        let entries = self.entries()?;
+
+        Ok(CacheStats {
+            files: entries.len(),
+            bytes: entries.iter().map(|&(_, size, _)| size).sum(),
+        })
+    }
+
+    /// Removes every poster, returns how many.
+    pub fn clear(&self) -> Result<usize, AppError> {
+        let entries = self.entries()?;
+
+        for (path, _, _) in entries.iter() {
+            ignore_gone(fs::remove_file(path))?;
+        }
+
+        Ok(entries.len())
+    }
+}
+
+/// Api responses about a movie by its id, the details or the translations.
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl ResponseCache {
+    /// The details of movies in the cache directory, kept for `ttl`.
+    pub fn details(ttl: Duration) -> Result<ResponseCache, AppError> {
+        ResponseCache::in_dir(project_dirs()?.cache_dir().join(DETAILS_DIR_NAME), ttl)
+    }
+
+    /// The translations of movies in the cache directory, kept for `ttl`.
+    pub fn translations(ttl: Duration) -> Result<ResponseCache, AppError> {
+        ResponseCache::in_dir(project_dirs()?.cache_dir().join(TRANSLATIONS_DIR_NAME), ttl)
+    }
+
+    /// The cache in `dir`, which is created if needed.
+    pub fn in_dir(dir: PathBuf, ttl: Duration) -> Result<ResponseCache, AppError> {
+        fs::create_dir_all(&dir)?;
+
+        Ok(ResponseCache { dir, ttl, clock: Arc::new(SystemClock) })
+    }
+
+    /// The same cache telling the age of a response by `clock`.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        ResponseCache { clock, ..self }
+    }
+
+    fn path(&self, id: u32) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// The response about the movie, unless it is older than the ttl or
+    /// cannot be read as a `T` any more.
+    pub fn get<T: DeserializeOwned>(&self, id: u32) -> Option<T> {
+        let path = self.path(id);
+        let written = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+
+        // one written in the future is from a clock that was off, and stale
+        if self.clock.now().duration_since(written).map_or(true, |age| age >= self.ttl) {
+            return None;
+        }
+
+        serde_json::from_slice(&fs::read(&path).ok()?).ok()
+    }
+
+    /// Keeps the response about the movie, starting its ttl.
+    pub fn put<T: Serialize>(&self, id: u32, response: &T) -> Result<(), AppError> {
+        let json = serde_json::to_vec(response).map_err(AppError::SerdeJsonSerializeError)?;
+        let path = self.path(id);
+
+        let partial = self.dir.join(format!("{}.{}.json{}", id, std::process::id(),
+                                            PARTIAL_SUFFIX));
+        write_at(&partial, &json, self.clock.now())?;
+        fs::rename(&partial, path)?;
+
+        Ok(())
+    }
+
+    /// How many movies there are responses about.
+    pub fn count(&self) -> Result<usize, AppError> {
+        Ok(self.files()?.len())
+    }
+
+    /// Removes every response, returns how many.
+    pub fn clear(&self) -> Result<usize, AppError> {
+        let files = self.files()?;
+
+        for path in files.iter() {
+            ignore_gone(fs::remove_file(path))?;
+        }
+
+        Ok(files.len())
+    }
+
+    fn files(&self) -> Result<Vec<PathBuf>, AppError> {
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                files.push(path);
+            }
+        }
+
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+
+    fn test_cache(name: &str, max_bytes: u64) -> PosterCache {
+        let dir = std::env::temp_dir()
+            .join(format!("movie_alert_cache_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        PosterCache::in_dir(dir, max_bytes).unwrap()
+    }
+
+    #[test]
+    fn file_name_stays_in_the_directory() {
+        assert_eq!(file_name("/abc.jpg").as_deref(), Some("abc.jpg"));
+        assert_eq!(file_name("/../x"), None);
+        assert_eq!(file_name("/a/b.jpg"), None);
+        assert_eq!(file_name("/"), None);
+    }
+
+    #[test]
+    fn put_evicts_the_least_recently_used_posters() {
+        let cache = test_cache("evict", 10);
+        let used = |name: &str, secs: u64| {
+            File::options().write(true).open(cache.dir().join(name)).unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+        };
+
+        cache.put("/a.jpg", b"aaaa").unwrap();
+        used("a.jpg", 100);
+        cache.put("/b.jpg", b"bbbb").unwrap();
+        used("b.jpg", 200);
+        used("a.jpg", 300);
+        cache.put("/c.jpg", b"cccc").unwrap();
+
+        assert_eq!(cache.get("/a.jpg").as_deref(), Some(&b"aaaa"[..]));
+        assert_eq!(cache.get("/b.jpg"), None);
+        assert_eq!(cache.stats().unwrap(), CacheStats { files: 2, bytes: 8 });
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn response_cache_keeps_a_response_for_its_ttl() {
+        let dir = std::env::temp_dir()
+            .join(format!("movie_alert_responses_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let clock = Arc::new(SimulatedClock::at_unix_secs(1_750_000_000));
+        let cache = ResponseCache::in_dir(dir.clone(), Duration::from_secs(3600)).unwrap()
+            .with_clock(clock.clone());
+
+        assert_eq!(cache.get::<Vec<u32>>(1), None);
+        cache.put(1, &vec![1, 2]).unwrap();
+        clock.advance(Duration::from_secs(3599));
+        assert_eq!(cache.get::<Vec<u32>>(1), Some(vec![1, 2]));
+        assert_eq!(cache.get::<String>(1), None);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(cache.get::<Vec<u32>>(1), None);
+
+        assert_eq!(cache.count().unwrap(), 1);
+        assert_eq!(cache.clear().unwrap(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+// Renders a month grid in the terminal, with movie titles placed on their
+// release dates.  Dates are plain "YYYY-MM-DD" strings as returned by the
+// TMD api, so there is no need for a full date library here.
+
+use crate::clock::Clock;
+
+const CELL_WIDTH: usize = 14;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+const WEEKDAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const MONTH_NAMES: [&str; 12] = ["January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December"];
+
+// parse "2025-07" into (2025, 7)
+pub fn parse_month(s: &str) -> Option<(i32, u32)> {
+    let mut parts = s.trim().splitn(2, '-');
+
+    let year = parts.next().and_then(|y| y.parse::<i32>().ok());
+    let month = parts.next().and_then(|m| m.parse::<u32>().ok());
+
+    match (year, month) {
+        (Some(y), Some(m)) if y > 0 && (1..=12).contains(&m) => Some((y, m)),
+        _ => None,
+    }
+}
+
+// "2025-7-4" becomes "2025-07-04", the way the TMD api wants dates, None if
+// there is no such day
+pub fn parse_date(s: &str) -> Option<String> {
+    let mut parts = s.trim().splitn(3, '-');
+
+    let y = parts.next().and_then(|y| y.parse::<i32>().ok())?;
+    let m = parts.next().and_then(|m| m.parse::<u32>().ok())?;
+    let d = parts.next().and_then(|d| d.parse::<u32>().ok())?;
+
+    if y > 0 && (1..=12).contains(&m) && d >= 1 && d <= days_in_month(y, m) {
+        Some(format!("{:04}-{:02}-{:02}", y, m, d))
+    } else {
+        None
+    }
+}
+
+// Without a known date, the TMD entries carry an empty release date or a
+// stand-in such as the first or last day of the year.
+pub fn is_date_tbd(release_date: &str) -> bool {
+    let mut parts = release_date.splitn(3, '-');
+
+    let y = parts.next().and_then(|y| y.parse::<i32>().ok());
+    let m = parts.next().and_then(|m| m.parse::<u32>().ok());
+    let d = parts.next().and_then(|d| d.parse::<u32>().ok());
+
+    match (y, m, d) {
+        (Some(_), Some(1), Some(1)) | (Some(_), Some(12), Some(31)) => true,
+        (Some(_), Some(_), Some(_)) => false,
+        _ => true,
+    }
+}
+
+// release dates look like "2025-07-18", returns the day if the date
+// falls in the given month.
+pub fn day_in_month(release_date: &str, year: i32, month: u32) -> Option<u32> {
+    let mut parts = release_date.splitn(3, '-');
+
+    let y = parts.next().and_then(|y| y.parse::<i32>().ok());
+    let m = parts.next().and_then(|m| m.parse::<u32>().ok());
+    let d = parts.next().and_then(|d| d.parse::<u32>().ok());
+
+    match (y, m, d) {
+        (Some(y), Some(m), Some(d)) if y == year && m == month
+            && d >= 1 && d <= days_in_month(year, month) => Some(d),
+        _ => None,
+    }
+}
+
+/// `months` after `date` ("2025-07-31"), the day is kept within the month,
+/// so 1 month after 2025-01-31 is 2025-02-28.
+pub fn add_months(date: &str, months: u32) -> Option<String> {
+    let mut parts = date.splitn(3, '-');
+
+    let y = parts.next().and_then(|y| y.parse::<i32>().ok())?;
+    let m = parts.next().and_then(|m| m.parse::<u32>().ok()).filter(|m| (1..=12).contains(m))?;
+    let d = parts.next().and_then(|d| d.parse::<u32>().ok())?;
+
+    let months_since_january = m - 1 + months;
+    let year = y + (months_since_january / 12) as i32;
+    let month = months_since_january % 12 + 1;
+
+    Some(format!("{:04}-{:02}-{:02}", year, month, d.min(days_in_month(year, month))))
+}
+
+/// The date `days` from today by `clock`, in UTC, e.g. "2025-07-18".
+pub fn date_in_days(clock: &dyn Clock, days: i64) -> String {
+    let (year, month, day) =
+        civil_from_days(clock.unix_secs().div_euclid(SECS_PER_DAY) + days);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The year it is now by `clock`, in UTC.
+pub fn this_year(clock: &dyn Clock) -> i32 {
+    civil_from_days(clock.unix_secs().div_euclid(SECS_PER_DAY)).0
+}
+
+// year, month and day of `days` after 1970-01-01, Howard Hinnant's
+// civil_from_days
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+
+    (year, month, day)
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ => if is_leap_year(year) { 29 } else { 28 },
+    }
+}
+
+// 0 is Sunday (Sakamoto's method)
+fn day_of_week(year: i32, month: u32, day: u32) -> usize {
+    const OFFSETS: [i32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+
+    let y = if month < 3 { year - 1 } else { year };
+
+    ((y + y / 4 - y / 100 + y / 400 + OFFSETS[(month - 1) as usize] + day as i32) % 7) as usize
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_owned()
+    } else {
+        let mut result: String = s.chars().take(width - 1).collect();
+        result.push('~');
+        result
+    }
+}
+
+fn rule() -> String {
+    let mut line = String::from("+");
+
+    for _ in 0..7 {
+        line.push_str(&"-".repeat(CELL_WIDTH + 2));
+        line.push('+');
+    }
+
+    line.push('\n');
+    line
+}
+
+fn row(cells: &[String]) -> String {
+    let mut line = String::from("|");
+
+    for cell in cells.iter() {
+        line.push_str(&format!(" {:<width$} |", cell, width = CELL_WIDTH));
+    }
+
+    line.push('\n');
+    line
+}
+
+/// Render the month as a grid, `titles` holds `(day, title)` pairs.
+pub fn render_month(year: i32, month: u32, titles: &[(u32, &str)]) -> String {
+    let days = days_in_month(year, month);
+    let first_weekday = day_of_week(year, month, 1);
+
+    let mut titles_by_day: Vec<Vec<&str>> = vec![Vec::new(); days as usize + 1];
+    for &(day, title) in titles.iter() {
+        if day >= 1 && day <= days {
+            titles_by_day[day as usize].push(title);
+        }
+    }
+
+    let mut out = String::new();
+
+    let heading = format!("{} {}", MONTH_NAMES[(month - 1) as usize], year);
+    out.push_str(&format!("{:^width$}\n", heading, width = (CELL_WIDTH + 3) * 7 + 1));
+
+    out.push_str(&rule());
+    out.push_str(&row(&WEEKDAY_NAMES.iter().map(|d| d.to_string()).collect::<Vec<_>>()));
+    out.push_str(&rule());
+
+    let weeks = (first_weekday + days as usize).div_ceil(7);
+
+    for week in 0..weeks {
+        // day number of each cell in this week, None for padding cells
+        let week_days: Vec<Option<u32>> = (0..7)
+            .map(|col| {
+                let index = week * 7 + col;
+                if index >= first_weekday && index < first_weekday + days as usize {
+                    Some((index - first_weekday + 1) as u32)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        out.push_str(&row(&week_days
+            .iter()
+            .map(|d| d.map(|d| d.to_string()).unwrap_or_default())
+            .collect::<Vec<_>>()));
+
+        let lines = week_days
+            .iter()
+            .filter_map(|d| d.map(|d| titles_by_day[d as usize].len()))
+            .max()
+            .unwrap_or(0);
+
+        for line in 0..lines {
+            out.push_str(&row(&week_days
+                .iter()
+                .map(|d| {
+                    d.and_then(|d| titles_by_day[d as usize].get(line))
+                        .map(|t| truncate(t, CELL_WIDTH))
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<_>>()));
+        }
+
+        out.push_str(&rule());
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+
+    #[test]
+    fn add_months_keeps_the_day_within_the_month() {
+        assert_eq!(add_months("2025-01-31", 1).as_deref(), Some("2025-02-28"));
+        assert_eq!(add_months("2024-01-31", 1).as_deref(), Some("2024-02-29"));
+        assert_eq!(add_months("2025-03-31", 1).as_deref(), Some("2025-04-30"));
+    }
+
+    #[test]
+    fn add_months_rolls_over_the_year() {
+        assert_eq!(add_months("2025-11-15", 3).as_deref(), Some("2026-02-15"));
+        assert_eq!(add_months("2025-12-31", 12).as_deref(), Some("2026-12-31"));
+        assert_eq!(add_months("2025-07-18", 0).as_deref(), Some("2025-07-18"));
+    }
+
+    #[test]
+    fn add_months_rejects_bad_dates() {
+        assert_eq!(add_months("2025-13-01", 1), None);
+        assert_eq!(add_months("2025-07", 1), None);
+        assert_eq!(add_months("", 1), None);
+    }
+
+    #[test]
+    fn civil_from_days_counts_from_the_epoch() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_287), (2025, 7, 18));
+        assert_eq!(civil_from_days(20_453), (2025, 12, 31));
+    }
+
+    #[test]
+    fn date_in_days_counts_from_the_clock() {
+        let clock = SimulatedClock::at_unix_secs(20_453 * SECS_PER_DAY as u64 + 3600);
+
+        assert_eq!(date_in_days(&clock, 0), "2025-12-31");
+        assert_eq!(date_in_days(&clock, 1), "2026-01-01");
+        assert_eq!(this_year(&clock), 2025);
+    }
+
+    #[test]
+    fn day_of_week_starts_on_sunday() {
+        assert_eq!(day_of_week(2000, 1, 1), 6);
+        assert_eq!(day_of_week(2024, 1, 1), 1);
+        assert_eq!(day_of_week(2024, 2, 29), 4);
+        assert_eq!(day_of_week(2025, 7, 4), 5);
+        assert_eq!(day_of_week(2026, 2, 1), 0);
+    }
+
+    #[test]
+    fn is_date_tbd_spots_empty_and_stand_in_dates() {
+        assert!(is_date_tbd(""));
+        assert!(is_date_tbd("2026"));
+        assert!(is_date_tbd("TBA"));
+        assert!(is_date_tbd("2026-01-01"));
+        assert!(is_date_tbd("2026-12-31"));
+        assert!(!is_date_tbd("2026-07-18"));
+        assert!(!is_date_tbd("2026-01-02"));
+    }
+
+    #[test]
+    fn render_month_puts_titles_on_their_days() {
+        // February 2026 starts on a Sunday and fills exactly four weeks
+        let grid = render_month(2026, 2, &[(1, "Alpha"), (1, "Beta"), (30, "Nope"),
+                                           (28, "A very long movie title")]);
+        let lines: Vec<&str> = grid.lines().collect();
+
+        assert_eq!(lines[0].trim(), "February 2026");
+        assert_eq!(lines[2], row(&WEEKDAY_NAMES.map(str::to_owned)).trim_end());
+        assert!(lines[4].starts_with("| 1 "));
+        assert!(lines[4].ends_with("| 7              |"));
+        assert!(lines[5].starts_with("| Alpha "));
+        assert!(lines[6].starts_with("| Beta "));
+        assert!(grid.contains("| A very long m~ |"));
+        assert!(!grid.contains("Nope"));
+
+        // heading, three lines of weekday names, and the weeks of 2, 2, 2
+        // and 3 lines, the first two with a line of titles
+        assert_eq!(lines.len(), 4 + 4 + 2 + 2 + 3);
+    }
+
+    #[test]
+    fn render_month_pads_the_first_and_last_week() {
+        // August 2026 starts on a Saturday and runs into a sixth week
+        let grid = render_month(2026, 8, &[]);
+
+        assert_eq!(grid.matches("+\n").count(), 2 + 6);
+        assert!(grid.contains(&row(&["", "", "", "", "", "", "1"].map(str::to_owned))));
+        assert!(grid.contains(&row(&["30", "31", "", "", "", "", ""].map(str::to_owned))));
+    }
+}
@@ -0,0 +1,479 @@
+// Command line interface.  Without a subcommand, `check` is run, which is
+// what movie_alert has always done.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::calendar;
+use crate::config::Alert;
+use crate::filter::GenreMatch;
+use crate::overview::OverviewLength;
+use crate::watchdog::RunLimits;
+
+#[derive(Debug, Parser)]
+#[command(name = "movie_alert", about = "Alerts about upcoming movies from themoviedb.org",
+          disable_version_flag = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Print version and exit
+    #[arg(short = 'V', long)]
+    pub version: bool,
+
+    /// With --version, also print git hash, target and build profile
+    #[arg(long, requires = "version")]
+    pub build_info: bool,
+
+    /// How much of the overview to show: chars=N, full or none
+    #[arg(long, global = true, default_value = "none", value_parser = parse_overview)]
+    pub overview: OverviewLength,
+
+    /// Work from the data of the last online run only, never touch the
+    /// network
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Output format for movies
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Cards)]
+    pub output: OutputFormat,
+
+    /// Genre to watch, instead of Animation (repeatable), "any" for every
+    /// genre
+    #[arg(long = "genre", global = true, value_name = "NAME")]
+    pub genres: Vec<String>,
+
+    /// Whether a movie needs any or all of the --genre genres to match
+    #[arg(long, global = true, value_enum, default_value_t = GenreMatch::Any)]
+    pub genre_match: GenreMatch,
+
+    /// How to alert about new movies, repeatable or comma separated to
+    /// alert in several ways [default: browser]
+    #[arg(long = "alert", global = true, value_enum, value_delimiter = ',')]
+    pub alerts: Vec<Alert>,
+
+    /// Never launch a browser, e.g. on a server or from cron.  New movies
+    /// are still printed, notified about and remembered as opened
+    #[arg(long, global = true)]
+    pub no_open: bool,
+
+    /// Choose which of the new movies to alert on, instead of all of them,
+    /// and what to do with the others
+    #[arg(long, global = true)]
+    pub pick: bool,
+
+    /// Language of titles, overviews and genre names, e.g. de-DE, instead of
+    /// the configured one
+    #[arg(long, global = true, value_name = "CODE", value_parser = parse_language)]
+    pub language: Option<String>,
+
+    /// Region to get upcoming movies for, e.g. US or CA, instead of the
+    /// configured one (repeatable, results are merged)
+    #[arg(long = "region", global = true, value_name = "CODE", value_parser = parse_region)]
+    pub regions: Vec<String>,
+
+    /// Also take in movies releasing up to this many months after the end
+    /// of the upcoming window, found with the discover api for the region
+    #[arg(long, global = true, value_name = "N")]
+    pub extend_months: Option<u32>,
+
+    /// Also alert on upcoming movies of this production company, whatever
+    /// their genres: a TMD company id or name, e.g. "Studio Ghibli"
+    /// (repeatable)
+    #[arg(long = "watch-company", global = true, value_name = "NAME")]
+    pub companies: Vec<String>,
+
+    /// Never alert on movies of this TMD company id, whatever else matches
+    /// (repeatable).  Looks up the details of each movie, one api call per
+    /// movie
+    #[arg(long = "exclude-company", global = true, value_name = "ID")]
+    pub excluded_companies: Vec<u32>,
+
+    /// Never alert on movies with this TMD keyword id (repeatable)
+    #[arg(long = "exclude-keyword", global = true, value_name = "ID")]
+    pub excluded_keywords: Vec<u32>,
+
+    /// Never alert on movies with this TMD person id in the cast or crew
+    /// (repeatable)
+    #[arg(long = "exclude-person", global = true, value_name = "ID")]
+    pub excluded_people: Vec<u32>,
+
+    /// Only show and alert on movies in this production status, e.g. "Post
+    /// Production" (repeatable).  Looks up the details of each movie, one
+    /// api call per movie
+    #[arg(long = "status", global = true, value_name = "STATUS")]
+    pub statuses: Vec<String>,
+
+    /// Also alert on movies added to this public TMD list (repeatable)
+    #[arg(long = "watch-list", alias = "list", global = true, value_name = "LIST_ID")]
+    pub lists: Vec<u32>,
+
+    /// Only show and alert on movies first listed in the results within
+    /// this long, e.g. 7d or 12h, leaving out the ones lingering there
+    #[arg(long, global = true, value_name = "AGE", value_parser = parse_interval)]
+    pub new_within: Option<Duration>,
+
+    /// Look up whether each movie has a translation into the language, one
+    /// api call per movie, and note it or keep only the translated ones
+    #[arg(long, global = true, value_enum)]
+    pub translation: Option<TranslationCheck>,
+
+    /// Only report what would be alerted on, without opening, notifying,
+    /// emailing or changing any state
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Abort the run after this many api calls
+    #[arg(long, global = true, value_name = "N")]
+    pub max_api_calls: Option<u32>,
+
+    /// Abort the run after opening this many movies
+    #[arg(long, global = true, value_name = "N")]
+    pub max_notifications: Option<u32>,
+
+    /// Abort the run after this many seconds
+    #[arg(long, global = true, value_name = "N")]
+    pub max_run_secs: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Open new upcoming movies of the watched genres in the browser (the
+    /// default)
+    Check,
+    /// Alert on movies of the watched genres once they are in theaters,
+    /// whether or not they were alerted on as upcoming
+    NowPlaying,
+    /// Alert on movies of the watched genres announced for the years after
+    /// this one.  They are alerted on again once they are upcoming
+    Announced {
+        /// How many years ahead to look
+        #[arg(long, default_value_t = 2, value_name = "N",
+              value_parser = clap::value_parser!(u32).range(1..=10))]
+        years: u32,
+    },
+    /// Alert on movies of the watched genres that the discover api finds
+    /// with the filters below, e.g. one studio's movies releasing in a year,
+    /// instead of the upcoming ones
+    Discover {
+        /// TMD company id, e.g. 10342 for Studio Ghibli (repeatable, movies
+        /// from any of them)
+        #[arg(long = "company", value_name = "ID")]
+        companies: Vec<u32>,
+        /// TMD keyword id (repeatable, movies with any of them)
+        #[arg(long = "keyword", value_name = "ID")]
+        keywords: Vec<u32>,
+        /// First release on or after this date, e.g. 2025-03-01
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        from: Option<String>,
+        /// First release on or before this date
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        to: Option<String>,
+        /// First release in this year
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        year: Option<i32>,
+        /// Average rating of at least this, from 0 to 10
+        #[arg(long, value_name = "N", value_parser = parse_vote)]
+        min_vote: Option<f32>,
+        /// Original language, e.g. ja
+        #[arg(long, value_name = "CODE", value_parser = parse_original_language)]
+        original_language: Option<String>,
+        /// Certification in the region, e.g. PG-13 in the US
+        #[arg(long, value_name = "RATING")]
+        certification: Option<String>,
+    },
+    /// List upcoming movies of the watched genres without opening anything
+    List {
+        /// Also show every alert delivered about each movie
+        #[arg(long)]
+        verbose: bool,
+        /// Show a poster thumbnail with every movie, in iTerm2 and WezTerm
+        #[arg(long)]
+        posters: bool,
+    },
+    /// Open a movie's TMD page and remember it as opened
+    Open {
+        /// TMD movie id
+        id: u32,
+    },
+    /// Show the settings in effect and where they come from
+    Config,
+    /// Inspect or change the record of opened movies
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+    /// Put back what the last `state forget`, `state reset` or `group
+    /// remove` took away
+    Undo,
+    /// Inspect or empty the cache of poster thumbnails and movie details
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Take the titles of a TMDb account export (CSV or JSON) as seen, so
+    /// they are not alerted on, and put them in a group
+    Import {
+        /// The export, e.g. a watchlist or ratings CSV
+        file: PathBuf,
+        /// Group the titles are put in
+        #[arg(long, default_value = "TMDb import")]
+        group: String,
+    },
+    /// Put movies into named groups, e.g. for a marathon
+    Group {
+        #[command(subcommand)]
+        action: GroupAction,
+    },
+    /// Browse upcoming movies of the watched genres full screen, filter
+    /// them, open them or mark them as seen
+    Tui,
+    /// Show upcoming movies of the watched genres on a month grid
+    Calendar {
+        /// Month to show, e.g. 2025-07 (defaults to the first month of the
+        /// upcoming window)
+        #[arg(long, value_parser = parse_month)]
+        month: Option<(i32, u32)>,
+    },
+    /// Show everything known to be releasing in the next 12 months, by quarter
+    Outlook {
+        /// How to render the report
+        #[arg(long, value_enum, default_value_t = OutlookFormat::Terminal)]
+        format: OutlookFormat,
+    },
+    /// Report why a movie was or was not opened
+    Explain {
+        /// TMD movie id
+        id: u32,
+    },
+    /// Remember that you watched a movie, e.g. in theaters, for `recap`
+    Watched {
+        /// TMD movie id
+        id: u32,
+        /// Day it was watched, e.g. 2025-07-18 (defaults to today)
+        #[arg(long, value_parser = parse_date)]
+        date: Option<String>,
+        /// How good it was, from 1 to 10
+        #[arg(long, value_parser = parse_rating)]
+        rating: Option<u8>,
+    },
+    /// Sum up the movies watched in a year, and how many of them were
+    /// alerted on before
+    Recap {
+        /// e.g. 2025
+        year: i32,
+    },
+    /// Show how much an alert in each genre is worth, learned from the
+    /// alerted movies opened with `open` or from the tui
+    Weights,
+    /// Suggest changes to the watched genres, from which of the alerted
+    /// movies were opened with `open` or from the tui
+    Suggest,
+    /// Keep running and check again on an interval, instead of from cron
+    Watch {
+        /// Time between checks, e.g. 6h, 30m or 1d
+        #[arg(long, default_value = "6h", value_parser = parse_interval)]
+        every: Duration,
+    },
+    /// Answer line delimited JSON-RPC requests on stdin (check, list,
+    /// markSeen) until it is closed
+    Rpc,
+    /// Run the whole pipeline on one page, without opening anything and
+    /// with a throwaway data directory, reporting time taken per stage
+    Smoke,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StateAction {
+    /// List the ids of opened movies
+    Show,
+    /// Forget that a movie was opened, so it is opened again next time
+    Forget {
+        /// TMD movie id
+        id: u32,
+    },
+    /// Forget all opened movies
+    Reset,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheAction {
+    /// Show how many posters are cached and their size, and how many movies'
+    /// details and translations
+    Stats,
+    /// Remove every cached poster, details and translations
+    Clear,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GroupAction {
+    /// Add movies to a group, which is created if needed
+    Add {
+        /// Group name, e.g. "Halloween marathon"
+        name: String,
+        /// TMD movie ids
+        #[arg(required = true)]
+        ids: Vec<u32>,
+    },
+    /// Take movies out of a group, which is gone once it is empty
+    Remove {
+        /// Group name
+        name: String,
+        /// TMD movie ids
+        #[arg(required = true)]
+        ids: Vec<u32>,
+    },
+    /// List the groups and the ids of their movies
+    Show,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// A few lines per movie
+    Cards,
+    /// One line per movie, for piping into less, status bars and such
+    Compact,
+    /// Script filter json for Alfred and Raycast
+    Alfred,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum TranslationCheck {
+    /// Say for every movie whether it is translated
+    Note,
+    /// Also leave out the movies that are not
+    Only,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutlookFormat {
+    Terminal,
+    Html,
+    Json,
+}
+
+impl Cli {
+    pub fn limits(&self) -> RunLimits {
+        RunLimits {
+            max_api_calls: self.max_api_calls,
+            max_notifications: self.max_notifications,
+            max_run_time: self.max_run_secs.map(Duration::from_secs),
+        }
+    }
+}
+
+fn parse_overview(s: &str) -> Result<OverviewLength, String> {
+    OverviewLength::parse(s)
+        .ok_or(format!("should be chars=N, full or none, got: {}", s))
+}
+
+fn parse_month(s: &str) -> Result<(i32, u32), String> {
+    calendar::parse_month(s)
+        .ok_or(format!("should look like 2025-07, got: {}", s))
+}
+
+fn parse_date(s: &str) -> Result<String, String> {
+    calendar::parse_date(s)
+        .ok_or(format!("should be a date like 2025-07-18, got: {}", s))
+}
+
+fn parse_vote(s: &str) -> Result<f32, String> {
+    s.parse::<f32>()
+        .ok()
+        .filter(|vote| (0.0..=10.0).contains(vote))
+        .ok_or(format!("should be a number from 0 to 10, got: {}", s))
+}
+
+fn parse_rating(s: &str) -> Result<u8, String> {
+    s.parse::<u8>()
+        .ok()
+        .filter(|rating| (1..=10).contains(rating))
+        .ok_or(format!("should be a whole number from 1 to 10, got: {}", s))
+}
+
+// ISO 639-1 language code alone, "JA" is taken as "ja"
+fn parse_original_language(s: &str) -> Result<String, String> {
+    if s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(s.to_ascii_lowercase())
+    } else {
+        Err(format!("should be a two letter language code, e.g. ja, got: {}", s))
+    }
+}
+
+// ISO 639-1 language code, optionally with a country: "de" or "de-DE"
+fn parse_language(s: &str) -> Result<String, String> {
+    let mut parts = s.splitn(2, '-');
+
+    let language = parts.next().unwrap_or("");
+    let country = parts.next();
+
+    let is_language = language.len() == 2 && language.chars().all(|c| c.is_ascii_lowercase());
+    let is_country = country
+        .map(|c| c.len() == 2 && c.chars().all(|c| c.is_ascii_uppercase()))
+        .unwrap_or(true);
+
+    if is_language && is_country {
+        Ok(s.to_owned())
+    } else {
+        Err(format!("should look like de or de-DE, got: {}", s))
+    }
+}
+
+// ISO 3166-1 country code, "us" is taken as "US"
+fn parse_region(s: &str) -> Result<String, String> {
+    if s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(s.to_ascii_uppercase())
+    } else {
+        Err(format!("should be a two letter country code, e.g. US, got: {}", s))
+    }
+}
+
+// "90s", "30m", "6h", "1d"
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let error = format!("should be a number followed by s, m, h or d, e.g. 6h, got: {}", s);
+
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let number = number.parse::<u64>().map_err(|_| error.clone())?;
+
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(error),
+    };
+    let secs = number.checked_mul(unit_secs).ok_or(error)?;
+
+    if secs < 60 {
+        return Err(format!("should be at least 1m, got: {}", s));
+    }
+
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_interval_takes_every_unit() {
+        assert_eq!(parse_interval("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_interval("6h"), Ok(Duration::from_secs(6 * 60 * 60)));
+        assert_eq!(parse_interval("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+    }
+
+    #[test]
+    fn parse_interval_wants_at_least_a_minute() {
+        assert_eq!(parse_interval("59s"), Err("should be at least 1m, got: 59s".to_owned()));
+        assert!(parse_interval("0m").is_err());
+    }
+
+    #[test]
+    fn parse_interval_rejects_bad_input() {
+        for s in ["", "6", "h", "6w", "-1h", "1.5h", "6 h", "18446744073709551615d"] {
+            assert!(parse_interval(s).is_err(), "{:?} should be rejected", s);
+        }
+    }
+}
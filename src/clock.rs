@@ -0,0 +1,70 @@
+// The time as movie_alert reads it: when a movie was first seen, until when
+// it is snoozed, the days of the upcoming window, the age of a cached
+// response.  All of them ask a `Clock`, the system clock in a run and a
+// `SimulatedClock` that is set and moved on by hand in tests, so a snooze
+// running out or a movie listed for a week can be tested without waiting.
+// Timers that only measure how long something takes, like the watchdog and
+// the pacing of requests, use `Instant` and no clock.
+
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where the time comes from.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+
+    /// Seconds since 1970-01-01 UTC, 0 for a time before.
+    fn unix_secs(&self) -> i64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+}
+
+/// The clock of the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock standing still at a time until it is moved on.
+pub struct SimulatedClock {
+    now: Mutex<SystemTime>,
+}
+
+impl SimulatedClock {
+    /// The clock at `secs` seconds after 1970-01-01 UTC.
+    pub fn at_unix_secs(secs: u64) -> SimulatedClock {
+        SimulatedClock { now: Mutex::new(UNIX_EPOCH + Duration::from_secs(secs)) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += by;
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulated_clock_moves_only_when_advanced() {
+        let clock = SimulatedClock::at_unix_secs(1_750_000_000);
+        assert_eq!(clock.unix_secs(), 1_750_000_000);
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(clock.unix_secs(), 1_750_000_090);
+        assert_eq!(SimulatedClock::at_unix_secs(0).unix_secs(), 0);
+    }
+}
@@ -0,0 +1,571 @@
+// Settings read from config.toml in the config directory, e.g.
+// ~/.config/movie_alert/config.toml, so the same setup can be copied between
+// machines.  Every value is optional and a command
+// line flag always wins over the config file.
+//
+//     genres = ["Animation", "Family"]
+//     companies = ["Studio Ghibli", "Pixar"]
+//     excluded_companies = [3]
+//     excluded_keywords = [9715]
+//     excluded_people = [488]
+//     language = "en-US"
+//     region = "US"
+//     api_key_file = "/home/me/.tmd_api_key"
+//     api_base_urls = ["https://tmdb-cache.local/3", "https://api.themoviedb.org/3"]
+//     browser = "firefox"
+//     extend_months = 3
+//
+//     [notifications]
+//     alert = ["desktop", "ntfy"]
+//     max_per_run = 10
+//     concurrency = 4
+//     messages_per_minute = { discord = 20 }
+//     languages = { email = "fr-FR" }
+//
+//     [http]
+//     concurrency = 6
+//     timeout_secs = 30
+//
+//     [enrichment]
+//     concurrency = 4
+//
+//     [state]
+//     git_commit = true
+//     git_push = true
+//
+//     [cache]
+//     max_mb = 50
+//     details_days = 7
+//
+//     [account]
+//     session_id_file = "/home/me/.tmd_session_id"
+//
+//     [email]
+//     smtp_server = "smtp.example.com"
+//     username = "me@example.com"
+//     password_file = "/home/me/.smtp_password"
+//     from = "movie_alert <me@example.com>"
+//     to = "me@example.com"
+//
+//     [slack]
+//     webhook_url = "https://hooks.slack.com/services/..."
+//
+//     [discord]
+//     webhook_url = "https://discord.com/api/webhooks/..."
+//
+//     [webhook]
+//     url = "https://ha.example.com/api/webhook/movies"
+//     token_file = "/home/me/.webhook_token"
+//     headers = { "X-Source" = "movie_alert" }
+//
+//     [ntfy]
+//     server = "https://ntfy.example.com"
+//     topic = "movies"
+//
+//     [gotify]
+//     server = "https://gotify.example.com"
+//     token_file = "/home/me/.gotify_token"
+//
+//     [instapaper]
+//     username = "me@example.com"
+//     password_file = "/home/me/.instapaper_password"
+//
+//     [wallabag]
+//     server = "https://wallabag.example.com"
+//     client_id = "1_abc"
+//     client_secret_file = "/home/me/.wallabag_secret"
+//     username = "me"
+//     password_file = "/home/me/.wallabag_password"
+//
+//     [mqtt]
+//     broker = "homeassistant.local:1883"
+//     username = "movie_alert"
+//     password_file = "/home/me/.mqtt_password"
+//
+// A `${VAR}` in any string value is replaced by the environment variable
+// when the file is loaded, e.g. `password_file = "${HOME}/.smtp_password"`
+// or `headers = { "X-Api-Key" = "${HA_API_KEY}" }`, so secrets can stay out
+// of the file.  A variable that is not set is an error, not an empty value.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Deserializer};
+
+// only for loading the file, which takes the toml parser of the cli feature
+#[cfg(feature = "cli")]
+use std::{fs::File, io::{ErrorKind, Read}};
+#[cfg(feature = "cli")]
+use crate::AppError;
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+// what the concurrency and timeout settings may be, beyond them a run
+// would either crawl or flood the api
+#[cfg(feature = "cli")]
+const CONCURRENCY_RANGE: std::ops::RangeInclusive<usize> = 1..=32;
+#[cfg(feature = "cli")]
+const TIMEOUT_SECS_RANGE: std::ops::RangeInclusive<u64> = 1..=600;
+
+// where older versions looked for it, whatever the platform
+const LEGACY_CONFIG_DIR_PATH: &str = ".config/movie_alert";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub genres: Vec<String>,
+    // same as --watch-company
+    pub companies: Vec<String>,
+    // same as --exclude-company, --exclude-keyword and --exclude-person
+    pub excluded_companies: Vec<u32>,
+    pub excluded_keywords: Vec<u32>,
+    pub excluded_people: Vec<u32>,
+    pub language: Option<String>,
+    pub region: Option<String>,
+    // file holding nothing but the TMD api key, used when TMD_API_V3 is not
+    // set
+    pub api_key_file: Option<PathBuf>,
+    // proxies, caching gateways or mirrors tried in order instead of the
+    // public api host, used when TMD_API_BASE_URL is not set
+    pub api_base_urls: Vec<String>,
+    // program the movie URL is handed to, instead of the system default
+    pub browser: Option<String>,
+    // same as --extend-months
+    pub extend_months: Option<u32>,
+    pub notifications: NotificationConfig,
+    pub http: HttpConfig,
+    pub enrichment: EnrichmentConfig,
+    pub state: StateConfig,
+    pub cache: CacheConfig,
+    // the TMD account whose watchlist check leaves alone
+    pub account: Option<AccountConfig>,
+    // where --alert email sends the digest to
+    pub email: Option<EmailConfig>,
+    // where --alert webhook posts the new movies to
+    pub webhook: Option<WebhookConfig>,
+    // where --alert slack posts the new movies to, when SLACK_WEBHOOK_URL is
+    // not set
+    pub slack: Option<SlackConfig>,
+    // where --alert discord posts the new movies to, when
+    // DISCORD_WEBHOOK_URL is not set
+    pub discord: Option<DiscordConfig>,
+    // where --alert ntfy pushes every new movie to
+    pub ntfy: Option<NtfyConfig>,
+    // where --alert gotify pushes every new movie to
+    pub gotify: Option<GotifyConfig>,
+    // where --alert instapaper saves every new movie to
+    pub instapaper: Option<InstapaperConfig>,
+    // where --alert wallabag saves every new movie to
+    pub wallabag: Option<WallabagConfig>,
+    // where --alert mqtt publishes every new movie and the movies releasing
+    // this week to
+    pub mqtt: Option<MqttConfig>,
+}
+
+/// How new movies are alerted about, see --alert.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Alert {
+    /// Print a line per new movie, e.g. for cron to mail
+    Stdout,
+    /// Open the movie page in a browser
+    Browser,
+    /// Show a desktop notification
+    Desktop,
+    /// Open the movie page and show a notification
+    Both,
+    /// Send one email with all new movies of the run, see [email] in the
+    /// config file
+    Email,
+    /// Post all new movies of the run as json to a url, see [webhook] in the
+    /// config file
+    Webhook,
+    /// Post all new movies of the run to a Slack channel, see [slack] in the
+    /// config file or set SLACK_WEBHOOK_URL
+    Slack,
+    /// Post all new movies of the run to a Discord channel, see [discord] in
+    /// the config file or set DISCORD_WEBHOOK_URL
+    Discord,
+    /// Push every new movie to an ntfy topic, see [ntfy] in the config file
+    Ntfy,
+    /// Push every new movie to a Gotify server, see [gotify] in the config
+    /// file
+    Gotify,
+    /// Save every new movie page to Instapaper, see [instapaper] in the
+    /// config file
+    Instapaper,
+    /// Save every new movie page to a Wallabag server, see [wallabag] in the
+    /// config file
+    Wallabag,
+    /// Publish every new movie and the movies releasing this week to Home
+    /// Assistant over MQTT, see [mqtt] in the config file
+    Mqtt,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    // same as --alert, one or a list of them
+    #[serde(deserialize_with = "one_or_many")]
+    pub alert: Vec<Alert>,
+    // same as --max-notifications
+    pub max_per_run: Option<u32>,
+    // notifiers sent to at a time, 4 by default
+    pub concurrency: Option<usize>,
+    // the most messages a minute by channel, e.g. { discord = 20 }, instead
+    // of what the channel takes by itself
+    pub messages_per_minute: BTreeMap<String, u32>,
+    // the language of the titles and numbers by channel, e.g.
+    // { email = "fr-FR" }, instead of the api language
+    pub languages: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    // pages of upcoming and discover results fetched at a time, 6 by default
+    pub concurrency: Option<usize>,
+    // a request, the api's and the notifiers' alike, is given up on after
+    // this, 30 by default
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnrichmentConfig {
+    // movies whose details or translations are asked for at a time, 4 by
+    // default
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    // file holding nothing but a session id of the account, see the README
+    pub session_id_file: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    // the poster cache of list --posters is kept under it, 50 by default
+    pub max_mb: Option<u64>,
+    // the details and translations of a movie are asked for again once
+    // they are this old, 7 by default and never with 0
+    pub details_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateConfig {
+    // commit the data directory to git after every run that changes it,
+    // see git.rs
+    pub git_commit: bool,
+    // and push the commits
+    pub git_push: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub smtp_server: String,
+    // defaults to the submission port, 587
+    pub smtp_port: Option<u16>,
+    pub username: Option<String>,
+    // file holding nothing but the smtp password
+    pub password_file: Option<PathBuf>,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    // file holding nothing but the bearer token sent along
+    pub token_file: Option<PathBuf>,
+    // sent with every post, e.g. an api key the receiving end wants
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlackConfig {
+    // incoming webhook of the channel
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    // webhook of the channel, from its integrations settings
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NtfyConfig {
+    // defaults to https://ntfy.sh
+    pub server: Option<String>,
+    pub topic: String,
+    // file holding nothing but an access token, for topics that need one
+    pub token_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GotifyConfig {
+    pub server: String,
+    // file holding nothing but the token of the Gotify application
+    pub token_file: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstapaperConfig {
+    // email address or username of the account
+    pub username: String,
+    // file holding nothing but the password, for accounts that have one
+    pub password_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WallabagConfig {
+    pub server: String,
+    // of an api client created under "API clients management"
+    pub client_id: String,
+    // file holding nothing but the secret of that client
+    pub client_secret_file: PathBuf,
+    pub username: String,
+    // file holding nothing but the password of the account
+    pub password_file: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    // "host" or "host:port", the port defaults to 1883
+    pub broker: String,
+    // the topics are under it, defaults to movie_alert
+    pub topic: Option<String>,
+    pub username: Option<String>,
+    // file holding nothing but the password, for brokers that want one
+    pub password_file: Option<PathBuf>,
+    // of the discovery messages, defaults to homeassistant
+    pub discovery_prefix: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+// `alert = "desktop"` of older config files, or a list
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Alert>, D::Error> {
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(alert) => vec![alert],
+        OneOrMany::Many(alerts) => alerts,
+    })
+}
+
+/// The config file in `config_dir`, or the one at the old place in `home`
+/// while there is none in `config_dir` yet.
+pub fn config_path(config_dir: &Path, home: &Path) -> PathBuf {
+    let path = config_dir.join(CONFIG_FILE_NAME);
+    let legacy_path = home.join(LEGACY_CONFIG_DIR_PATH).join(CONFIG_FILE_NAME);
+
+    if !path.exists() && legacy_path.is_file() {
+        debug!("Using config file at the old place: {:?}", legacy_path);
+        return legacy_path;
+    }
+
+    path
+}
+
+/// Loads the config file at `path`.  A missing file is the same as an
+/// empty one.
+#[cfg(feature = "cli")]
+pub fn load(path: &Path) -> Result<Config, AppError> {
+    let mut content = String::new();
+
+    match File::open(path) {
+        Ok(mut file) => {
+            file.read_to_string(&mut content)?;
+        },
+        Err(ref e) if e.kind() == ErrorKind::NotFound => {
+            debug!("No config file at: {:?}", path);
+            return Ok(Config::default());
+        },
+        Err(e) => return Err(AppError::IOError(e)),
+    }
+
+    debug!("Loading config file: {:?}", path);
+
+    // checked as it is first, errors in the file itself come with the line
+    // they are on
+    toml::from_str::<Config>(&content).map_err(|e| AppError::ConfigError(path.to_owned(), e))?;
+
+    let mut value: toml::Value = toml::from_str(&content)
+        .map_err(|e| AppError::ConfigError(path.to_owned(), e))?;
+    interpolate_value(&mut value, "")
+        .map_err(|msg| AppError::ConfigVariableError(path.to_owned(), msg))?;
+
+    let config: Config = value.try_into()
+        .map_err(|e| AppError::ConfigError(path.to_owned(), e))?;
+    validate(&config).map_err(|msg| AppError::ConfigValueError(path.to_owned(), msg))?;
+
+    Ok(config)
+}
+
+// the values the types of the fields let through but a run cannot use
+#[cfg(feature = "cli")]
+fn validate(config: &Config) -> Result<(), String> {
+    let concurrencies = [("notifications.concurrency", config.notifications.concurrency),
+                         ("http.concurrency", config.http.concurrency),
+                         ("enrichment.concurrency", config.enrichment.concurrency)];
+
+    for (key, value) in concurrencies.iter() {
+        if let Some(value) = value.filter(|value| !CONCURRENCY_RANGE.contains(value)) {
+            return Err(format!("{} is {}, it must be from {} to {}", key, value,
+                               CONCURRENCY_RANGE.start(), CONCURRENCY_RANGE.end()));
+        }
+    }
+
+    for (channel, rate) in config.notifications.messages_per_minute.iter() {
+        if *rate == 0 {
+            return Err(format!("notifications.messages_per_minute.{} is 0, it must be at \
+                                least 1", channel));
+        }
+    }
+
+    if let Some(secs) = config.http.timeout_secs.filter(|secs| !TIMEOUT_SECS_RANGE.contains(secs)) {
+        return Err(format!("http.timeout_secs is {}, it must be from {} to {}", secs,
+                           TIMEOUT_SECS_RANGE.start(), TIMEOUT_SECS_RANGE.end()));
+    }
+
+    Ok(())
+}
+
+// replaces the variables in every string in `value`, `key` is where it is
+// in the file, e.g. "email.password_file", for the error
+#[cfg(feature = "cli")]
+fn interpolate_value(value: &mut toml::Value, key: &str) -> Result<(), String> {
+    match value {
+        toml::Value::String(s) => {
+            *s = interpolate(s, |name| std::env::var(name).ok())
+                .map_err(|msg| format!("{}: {}", key, msg))?;
+        },
+        toml::Value::Array(values) => {
+            for (index, value) in values.iter_mut().enumerate() {
+                interpolate_value(value, &format!("{}[{}]", key, index))?;
+            }
+        },
+        toml::Value::Table(table) => {
+            for (name, value) in table.iter_mut() {
+                let key = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                interpolate_value(value, &key)?;
+            }
+        },
+        _ => {},
+    }
+
+    Ok(())
+}
+
+// `s` with every `${NAME}` replaced by `lookup(NAME)`, a `$` without a `{`
+// after it is left as it is
+#[cfg(feature = "cli")]
+fn interpolate(s: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("${{ without a closing }} in {:?}", s))?;
+        let name = &rest[start + 2..start + end];
+
+        if name.is_empty() {
+            return Err(format!("${{}} without a variable name in {:?}", s));
+        }
+
+        let value = lookup(name)
+            .ok_or_else(|| format!("environment variable {} is not set", name))?;
+        out.push_str(&value);
+
+        rest = &rest[start + end + 1..];
+    }
+
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".to_owned()),
+            "TOKEN" => Some("s3cret".to_owned()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn validate_keeps_concurrency_and_timeout_in_range() {
+        let mut config = Config::default();
+        assert_eq!(validate(&config), Ok(()));
+
+        config.http.concurrency = Some(0);
+        assert_eq!(validate(&config),
+                   Err("http.concurrency is 0, it must be from 1 to 32".to_owned()));
+
+        config.http.concurrency = Some(32);
+        config.http.timeout_secs = Some(601);
+        assert_eq!(validate(&config),
+                   Err("http.timeout_secs is 601, it must be from 1 to 600".to_owned()));
+    }
+
+    #[test]
+    fn interpolate_replaces_every_variable() {
+        assert_eq!(interpolate("${HOME}/.token", lookup), Ok("/home/me/.token".to_owned()));
+        assert_eq!(interpolate("Bearer ${TOKEN} ${TOKEN}", lookup),
+                   Ok("Bearer s3cret s3cret".to_owned()));
+        assert_eq!(interpolate("no variables", lookup), Ok("no variables".to_owned()));
+    }
+
+    #[test]
+    fn interpolate_leaves_a_lone_dollar_alone() {
+        assert_eq!(interpolate("$5 and $HOME", lookup), Ok("$5 and $HOME".to_owned()));
+    }
+
+    #[test]
+    fn interpolate_names_the_missing_variable() {
+        assert_eq!(interpolate("${NOPE}", lookup),
+                   Err("environment variable NOPE is not set".to_owned()));
+    }
+
+    #[test]
+    fn interpolate_rejects_unclosed_and_empty_variables() {
+        assert!(interpolate("${HOME", lookup).is_err());
+        assert!(interpolate("${}", lookup).is_err());
+    }
+
+    #[test]
+    fn interpolate_value_says_where_the_variable_is() {
+        let mut value: toml::Value = toml::from_str("[email]\nto = \"${MOVIE_ALERT_NOT_SET}\"")
+            .unwrap();
+
+        assert_eq!(interpolate_value(&mut value, ""),
+                   Err("email.to: environment variable MOVIE_ALERT_NOT_SET is not set"
+                       .to_owned()));
+    }
+}
@@ -0,0 +1,102 @@
+// A new movie as sent anywhere but the browser and the desktop: in the
+// digest of a run, by email or posted to a webhook, Slack or Discord, or on
+// its own to ntfy or Gotify.  A digest too long for one message goes out in
+// parts, which share the id of the digest.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::filter::get_genre_name_from_ids;
+use crate::tmdb::Movie;
+use crate::{calendar, links, locale};
+
+/// The most movies in one message of a digest, unless the notifier takes
+/// fewer.
+pub const DIGEST_PART_SIZE: usize = 25;
+
+#[derive(Clone, Serialize)]
+pub struct DigestMovie {
+    pub id: u32,
+    pub title: String,
+    pub genres: String,
+    pub release_date: String,
+    pub poster_url: Option<String>,
+    pub url: String,
+    // names of the groups the movie is in
+    pub groups: Vec<String>,
+    // "7.5 of 1,234 votes" and "1h 42m", in the numbers of the api language,
+    // see locale
+    pub rating: Option<String>,
+    pub runtime: Option<String>,
+    // the same movie for notifiers in another language, by the language
+    #[serde(skip)]
+    pub localized: BTreeMap<String, DigestMovie>,
+}
+
+/// "2025-07-18", or "date TBD (2026-01-01)" for a stand-in date.
+pub fn release_date_label(release_date: &str) -> String {
+    if !calendar::is_date_tbd(release_date) {
+        release_date.to_owned()
+    } else if release_date.is_empty() {
+        "date TBD".to_owned()
+    } else {
+        format!("date TBD ({})", release_date)
+    }
+}
+
+impl DigestMovie {
+    /// The movie as alerted about, with the numbers in those of `language`.
+    pub fn new(movie: &Movie, genre_map: &HashMap<u32, String>, groups: Vec<String>,
+               url: String, language: &str) -> DigestMovie {
+        DigestMovie {
+            id: movie.id,
+            title: movie.title.clone(),
+            genres: get_genre_name_from_ids(&movie.genre_ids, genre_map),
+            release_date: release_date_label(&movie.release_date),
+            poster_url: movie.poster_path.as_ref().map(|p| links::tmd_poster_url(p)),
+            url,
+            groups,
+            rating: locale::format_rating(movie.vote_average, movie.vote_count, language),
+            runtime: movie.runtime
+                .filter(|&minutes| minutes > 0)
+                .map(|minutes| locale::format_runtime(minutes, language)),
+            localized: BTreeMap::new(),
+        }
+    }
+
+    /// The movie for a notifier in `language`, as it is when there is no
+    /// language or the movie is not localized into it.
+    pub fn in_language(&self, language: Option<&str>) -> &DigestMovie {
+        language.and_then(|language| self.localized.get(language)).unwrap_or(self)
+    }
+
+    /// "Rating: 7.5 of 1,234 votes, Runtime: 1h 42m", nothing when neither
+    /// is known.
+    pub fn facts(&self) -> Option<String> {
+        let facts: Vec<String> = [("Rating", &self.rating), ("Runtime", &self.runtime)]
+            .iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| format!("{}: {}", name, value)))
+            .collect();
+
+        Some(facts.join(", ")).filter(|facts| !facts.is_empty())
+    }
+}
+
+/// Which part of the digest of a run a message is.
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestPart {
+    pub id: String,
+    // from 1
+    pub part: usize,
+    pub parts: usize,
+}
+
+impl DigestPart {
+    /// " (part 2 of 3)", nothing for a digest in one part.
+    pub fn suffix(&self) -> String {
+        if self.parts > 1 {
+            format!(" (part {} of {})", self.part, self.parts)
+        } else {
+            String::new()
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Posts all new movies of a run to a Discord webhook, an embed per movie
+// with its poster, release date and genres.  Discord takes at most 10 embeds
+// in a message, so the digest of a long run comes in parts of
+// MOVIES_PER_MESSAGE.  A message Discord turns away with 429 Too Many
+// Requests is posted again after the `retry_after` of the response.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::digest::{DigestMovie, DigestPart};
+use crate::http;
+use crate::AppError;
+
+/// Embeds Discord takes in a message.
+pub const MOVIES_PER_MESSAGE: usize = 10;
+
+/// Messages a minute a webhook takes.
+pub const MESSAGES_PER_MINUTE: u32 = 30;
+
+// a message turned away is posted again this many times
+const RATE_LIMITED_RETRIES: u32 = 3;
+// asked to wait longer than this, the message fails instead
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+// the body of a 429 response, the seconds to wait
+#[derive(Debug, Deserialize)]
+struct RateLimited {
+    retry_after: f64,
+}
+
+fn movie_embed(movie: &DigestMovie) -> Value {
+    let mut embed = json!({
+        "title": movie.title,
+        "url": movie.url,
+        "fields": [
+            { "name": "Release date", "value": movie.release_date, "inline": true },
+            { "name": "Genres", "value": movie.genres, "inline": true },
+        ],
+    });
+
+    for (name, value) in [("Rating", &movie.rating), ("Runtime", &movie.runtime)] {
+        if let (Some(value), Some(fields)) = (value, embed["fields"].as_array_mut()) {
+            fields.push(json!({ "name": name, "value": value, "inline": true }));
+        }
+    }
+
+    if let Some(ref poster_url) = movie.poster_url {
+        embed["image"] = json!({ "url": poster_url });
+    }
+
+    embed
+}
+
+/// Posts the movies in one message, at most MOVIES_PER_MESSAGE of them.
+pub async fn post_movies(webhook_url: &str, movies: &[DigestMovie], part: Option<&DigestPart>)
+                         -> Result<(), AppError> {
+    let message = json!({
+        "content": format!("{} new upcoming movies{}", movies.len(),
+                           part.map(DigestPart::suffix).unwrap_or_default()),
+        "embeds": movies.iter().map(movie_embed).collect::<Vec<Value>>(),
+    });
+
+    debug!("Posting {} movies to Discord", movies.len());
+
+    let client = http::client();
+    let mut retries = 0;
+
+    loop {
+        let sent = client.post(webhook_url).json(&message).send().await;
+
+        let response = match sent {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS
+                && retries < RATE_LIMITED_RETRIES => response,
+            sent => {
+                http::check_post(sent, "Error: cannot post new movies to Discord").await?;
+                return Ok(());
+            },
+        };
+
+        let wait = response.json::<RateLimited>().await.ok()
+            .and_then(|limited| Duration::try_from_secs_f64(limited.retry_after).ok())
+            .filter(|wait| *wait <= MAX_RETRY_AFTER)
+            .ok_or_else(|| AppError::NotificationError(
+                "Discord turned away the new movies and asked to wait too long".to_owned()))?;
+
+        debug!("Discord is rate limiting the webhook, posting again in {} ms",
+               wait.as_millis());
+        tokio::time::sleep(wait).await;
+        retries += 1;
+    }
+}
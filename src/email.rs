@@ -0,0 +1,121 @@
+// Sends all new movies of a run in one email, for when a browser tab or a
+// notification per movie is too much.
+
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::fs;
+
+use crate::config::EmailConfig;
+use crate::digest::{DigestMovie, DigestPart};
+use crate::AppError;
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_movie(out: &mut String, movie: &DigestMovie) {
+    out.push_str("<p>\n");
+
+    if let Some(ref poster_url) = movie.poster_url {
+        out.push_str(&format!("<img src=\"{}\" alt=\"\" width=\"92\"><br>\n",
+                              escape_html(poster_url)));
+    }
+
+    out.push_str(&format!("<a href=\"{}\"><b>{}</b></a><br>\n", escape_html(&movie.url),
+                          escape_html(&movie.title)));
+    out.push_str(&format!("{}<br>\nRelease date: {}\n", escape_html(&movie.genres),
+                          escape_html(&movie.release_date)));
+    if let Some(facts) = movie.facts() {
+        out.push_str(&format!("<br>{}\n", escape_html(&facts)));
+    }
+    out.push_str("</p>\n");
+}
+
+// movies in a group are listed under its name, a movie in more than one
+// group under each of them, the rest come last
+fn render_digest(movies: &[DigestMovie], part: Option<&DigestPart>) -> String {
+    let suffix = part.map(DigestPart::suffix).unwrap_or_default();
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<body>\n");
+    out.push_str(&format!("<h1>{} new upcoming movies{}</h1>\n", movies.len(), suffix));
+
+    let mut group_names: Vec<&str> = movies
+        .iter()
+        .flat_map(|movie| movie.groups.iter().map(|g| g.as_str()))
+        .collect();
+    group_names.sort();
+    group_names.dedup();
+
+    for name in group_names.iter() {
+        out.push_str(&format!("<h2>{}</h2>\n", escape_html(name)));
+
+        for movie in movies.iter().filter(|movie| movie.groups.iter().any(|g| g == name)) {
+            render_movie(&mut out, movie);
+        }
+    }
+
+    let ungrouped: Vec<&DigestMovie> = movies.iter().filter(|m| m.groups.is_empty()).collect();
+
+    if !group_names.is_empty() && !ungrouped.is_empty() {
+        out.push_str("<h2>Other movies</h2>\n");
+    }
+
+    for movie in ungrouped.into_iter() {
+        render_movie(&mut out, movie);
+    }
+
+    if let Some(part) = part.filter(|part| part.parts > 1) {
+        out.push_str(&format!("<p><small>Digest {}, part {} of {}</small></p>\n",
+                              escape_html(&part.id), part.part, part.parts));
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Sends the digest, or the part of it, returns its Message-ID.
+pub fn send_digest(settings: &EmailConfig, movies: &[DigestMovie], part: Option<&DigestPart>)
+                   -> Result<String, AppError> {
+    let from = settings.from.parse()
+        .map_err(|e| AppError::EmailError(format!("bad from address {}: {}", settings.from, e)))?;
+    let to = settings.to.parse()
+        .map_err(|e| AppError::EmailError(format!("bad to address {}: {}", settings.to, e)))?;
+
+    let message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(format!("movie_alert: {} new upcoming movies{}", movies.len(),
+                         part.map(DigestPart::suffix).unwrap_or_default()))
+        .message_id(None)
+        .header(ContentType::TEXT_HTML)
+        .body(render_digest(movies, part))
+        .map_err(|e| AppError::EmailError(e.to_string()))?;
+    let message_id = message.headers().get_raw("Message-ID").unwrap_or("").to_owned();
+
+    let mut transport = SmtpTransport::starttls_relay(&settings.smtp_server)
+        .map_err(|e| AppError::EmailError(e.to_string()))?;
+
+    if let Some(port) = settings.smtp_port {
+        transport = transport.port(port);
+    }
+
+    if let Some(ref username) = settings.username {
+        let password = match settings.password_file {
+            Some(ref path) => fs::read_to_string(path)?.trim().to_owned(),
+            None => String::new(),
+        };
+
+        transport = transport.credentials(Credentials::new(username.to_owned(), password));
+    }
+
+    debug!("Sending digest of {} movies to {}", movies.len(), settings.to);
+
+    transport.build().send(&message).map_err(|e| AppError::EmailError(e.to_string()))?;
+
+    Ok(message_id)
+}
@@ -0,0 +1,287 @@
+// The one error type of movie_alert, and how each error is reported.  Errors
+// a run can carry on without are collected in an `ErrorReport` and reported
+// together at the end of it, the way linters do.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "cli")]
+use std::path::PathBuf;
+
+use crate::http::ErrorResponse;
+
+// errors of every step shown in full at the end of a run, the rest are only
+// counted
+const REPORTED_EXAMPLES: usize = 3;
+
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum AppError {
+    APIKeyError(std::env::VarError),
+    // with what came back when the response had an error status
+    RestClientError(String, reqwest::Error, Option<ErrorResponse>),
+    GenreIdNotFoundError(String, Vec<String>),
+    HomeDirectoryError,
+    SerdeJsonSerializeError(serde_json::Error),
+    SerdeJsonDeserializeError(serde_json::Error),
+    IOError(std::io::Error),
+    EnvLogError(log::SetLoggerError),
+    ArgumentError(String),
+    BlockedUrlError(String),
+    // the program that could not be started
+    BrowserError(String, std::io::Error),
+    RunLimitExceededError(String),
+    OfflineError(String),
+    // the export file and what is wrong with it
+    ImportError(String, String),
+    #[cfg(feature = "cli")]
+    ConfigError(PathBuf, toml::de::Error),
+    // a ${VAR} in a value whose variable is not set, or that is not closed
+    #[cfg(feature = "cli")]
+    ConfigVariableError(PathBuf, String),
+    // a value out of the range it may be in, with the key
+    #[cfg(feature = "cli")]
+    ConfigValueError(PathBuf, String),
+    NotificationError(String),
+    #[cfg(feature = "store")]
+    StoreError(rusqlite::Error),
+    EmailError(String),
+    GitError(String, String),
+    RunIncompleteError(usize),
+    FeatureError(&'static str),
+}
+
+/// Errors of steps the rest of a run does not depend on, e.g. one alert or
+/// saving the offline data, by the step they happened in.
+#[derive(Debug, Default)]
+pub struct ErrorReport {
+    errors: Vec<(String, AppError)>,
+}
+
+impl ErrorReport {
+    pub fn add(&mut self, step: &str, error: AppError) {
+        warn!("Carrying on after {} failed: {:?}", step, error);
+
+        self.errors.push((step.to_owned(), error));
+    }
+
+    /// Reports the errors collected, a few of every step in full, and fails
+    /// when there were any.
+    pub fn finish(self) -> Result<(), AppError> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+
+        let total = self.errors.len();
+
+        let mut by_step: BTreeMap<String, Vec<AppError>> = BTreeMap::new();
+        for (step, error) in self.errors.into_iter() {
+            by_step.entry(step).or_default().push(error);
+        }
+
+        for (step, errors) in by_step.into_iter() {
+            let count = errors.len();
+            error!("Failed {} time(s): {}", count, step);
+
+            for error in errors.into_iter().take(REPORTED_EXAMPLES) {
+                error.report_error();
+            }
+
+            if count > REPORTED_EXAMPLES {
+                error!("    ...and {} more", count - REPORTED_EXAMPLES);
+            }
+        }
+
+        Err(AppError::RunIncompleteError(total))
+    }
+}
+
+// one line, e.g. for a JSON-RPC error or the deliveries of a movie,
+// `report_error` tells more
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            AppError::APIKeyError(ref cause) => {
+                write!(f, "TMD API key TMD_API_V3 is not set: {}", cause)
+            },
+            AppError::RestClientError(ref msg, ref cause, _) => {
+                write!(f, "{}: {}", msg.trim_start_matches("Error: "), cause)
+            },
+            AppError::GenreIdNotFoundError(ref name, ref valid_names) => {
+                write!(f, "no genre is called {}, valid genre names are: {}", name,
+                       valid_names.join(", "))
+            },
+            AppError::HomeDirectoryError => write!(f, "home directory cannot be located"),
+            AppError::SerdeJsonSerializeError(ref cause) => {
+                write!(f, "cannot save to data file: {}", cause)
+            },
+            AppError::SerdeJsonDeserializeError(ref cause) => {
+                write!(f, "cannot load from data file: {}", cause)
+            },
+            AppError::IOError(ref cause) => write!(f, "IO error: {}", cause),
+            AppError::EnvLogError(ref cause) => {
+                write!(f, "cannot initialize env log: {}", cause)
+            },
+            AppError::ArgumentError(ref msg) => write!(f, "{}", msg),
+            AppError::BlockedUrlError(ref url) => {
+                write!(f, "refusing to open URL outside of known sites: {}", url)
+            },
+            AppError::BrowserError(ref program, ref cause) => {
+                write!(f, "cannot start {} to open the movie page: {}", program, cause)
+            },
+            AppError::RunLimitExceededError(ref msg) => {
+                write!(f, "run aborted, limit exceeded: {}", msg)
+            },
+            AppError::OfflineError(ref msg) => write!(f, "cannot run offline: {}", msg),
+            AppError::ImportError(ref file, ref msg) => {
+                write!(f, "cannot import {}: {}", file, msg)
+            },
+            #[cfg(feature = "cli")]
+            AppError::ConfigError(ref path, ref cause) => {
+                write!(f, "cannot load config file {}: {}", path.display(), cause.message())
+            },
+            #[cfg(feature = "cli")]
+            AppError::ConfigVariableError(ref path, ref msg)
+            | AppError::ConfigValueError(ref path, ref msg) => {
+                write!(f, "cannot load config file {}: {}", path.display(), msg)
+            },
+            AppError::NotificationError(ref cause) => write!(f, "cannot alert: {}", cause),
+            #[cfg(feature = "store")]
+            AppError::StoreError(ref cause) => {
+                write!(f, "cannot read or update the store of opened movies: {}", cause)
+            },
+            AppError::EmailError(ref cause) => write!(f, "cannot send email digest: {}", cause),
+            AppError::GitError(ref command, ref cause) => {
+                write!(f, "git {} failed in the data directory: {}", command, cause)
+            },
+            AppError::RunIncompleteError(count) => {
+                write!(f, "the run finished with {} error(s)", count)
+            },
+            AppError::FeatureError(feature) => {
+                write!(f, "movie_alert was built without the {} feature", feature)
+            },
+        }
+    }
+}
+
+impl std::convert::From<std::io::Error> for AppError {
+    fn from(s: std::io::Error) -> Self {
+        AppError::IOError(s)
+    }
+}
+
+#[cfg(feature = "pick")]
+impl std::convert::From<dialoguer::Error> for AppError {
+    fn from(s: dialoguer::Error) -> Self {
+        match s {
+            dialoguer::Error::IO(e) => AppError::IOError(e),
+        }
+    }
+}
+
+#[cfg(feature = "store")]
+impl std::convert::From<rusqlite::Error> for AppError {
+    fn from(s: rusqlite::Error) -> Self {
+        AppError::StoreError(s)
+    }
+}
+
+impl AppError {
+    pub fn report_error(self) {
+        match self {
+            AppError::APIKeyError(cause) => {
+                error!("Error: TMD API key TMD_API_V3 is not set in env!");
+                error!("    TMD API key can be obtained at https://developers.themoviedb.org/3/getting-started");
+                error!("    {}", cause);
+            },
+            AppError::RestClientError(msg, cause, response) => {
+                error!("{}", msg);
+                error!("    {}", cause);
+
+                if let Some(response) = response {
+                    for (name, value) in response.headers.iter() {
+                        error!("    {}: {}", name, value);
+                    }
+                    if !response.body.is_empty() {
+                        error!("    {}", response.body);
+                    }
+                }
+            },
+            AppError::GenreIdNotFoundError(name, valid_names) => {
+                error!("Error: id cannot be found for genre name: {}", name);
+                error!("    Valid genre names are: {}", valid_names.join(", "));
+            },
+            AppError::HomeDirectoryError => {
+                error!("Error: home directory cannot be located.")
+            },
+            AppError::SerdeJsonSerializeError(cause) => {
+                error!("Error: cannot save to data file");
+                error!("    {}", cause);
+            },
+            AppError::SerdeJsonDeserializeError(cause) => {
+                error!("Error: cannot load from data file");
+                error!("    {}", cause);
+            }
+            AppError::IOError(cause) => {
+                error!("Error: IO error");
+                error!("    {}", cause);
+            },
+            AppError::EnvLogError(cause) => {
+                error!("Error: cannot initialize env log:");
+                error!("    {}", cause);
+            },
+            AppError::ArgumentError(msg) => {
+                error!("Error: {}", msg);
+            },
+            AppError::BlockedUrlError(url) => {
+                error!("Error: refusing to open URL outside of known sites: {}", url);
+            },
+            AppError::BrowserError(program, cause) => {
+                error!("Error: cannot start {} to open the movie page:", program);
+                error!("    {}", cause);
+            },
+            AppError::RunLimitExceededError(msg) => {
+                error!("Error: run aborted, limit exceeded: {}", msg);
+            },
+            AppError::OfflineError(msg) => {
+                error!("Error: cannot run offline: {}", msg);
+            },
+            AppError::ImportError(file, msg) => {
+                error!("Error: cannot import {}:", file);
+                error!("    {}", msg);
+            },
+            #[cfg(feature = "cli")]
+            AppError::ConfigError(path, cause) => {
+                error!("Error: cannot load config file: {}", path.display());
+                error!("    {}", cause);
+            },
+            #[cfg(feature = "cli")]
+            AppError::ConfigVariableError(path, msg) | AppError::ConfigValueError(path, msg) => {
+                error!("Error: cannot load config file: {}", path.display());
+                error!("    {}", msg);
+            },
+            AppError::NotificationError(cause) => {
+                error!("Error: cannot alert:");
+                error!("    {}", cause);
+            },
+            #[cfg(feature = "store")]
+            AppError::StoreError(cause) => {
+                error!("Error: cannot read or update the store of opened movies:");
+                error!("    {}", cause);
+            },
+            AppError::EmailError(cause) => {
+                error!("Error: cannot send email digest:");
+                error!("    {}", cause);
+            },
+            AppError::GitError(command, cause) => {
+                error!("Error: git {} failed in the data directory:", command);
+                error!("    {}", cause);
+            },
+            AppError::RunIncompleteError(count) => {
+                error!("Error: the run finished with {} error(s), see above", count);
+            },
+            AppError::FeatureError(feature) => {
+                error!("Error: movie_alert was built without the {} feature, see Cargo.toml",
+                       feature);
+            },
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// Picking the movies of the wanted genres out of the upcoming ones, and
+// turning genre names into ids and back.
+
+use std::collections::HashMap;
+
+use crate::genres::KnownGenre;
+use crate::tmdb::Movie;
+use crate::AppError;
+
+/// The --genre that watches every genre, leaving the picking to the other
+/// filters.
+pub const ANY_GENRE: &str = "any";
+
+/// Whether a movie needs to be in any or all of the wanted genres, see
+/// --genre-match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum GenreMatch {
+    /// Movies in at least one of the genres
+    Any,
+    /// Movies in every one of the genres
+    All,
+}
+
+/// No `genre_ids` match every movie, see ANY_GENRE.
+pub fn is_genre_match(movie: &Movie, genre_ids: &[u32], genre_match: GenreMatch) -> bool {
+    if genre_ids.is_empty() {
+        return true;
+    }
+
+    match genre_match {
+        GenreMatch::Any => genre_ids.iter().any(|id| movie.genre_ids.contains(id)),
+        GenreMatch::All => genre_ids.iter().all(|id| movie.genre_ids.contains(id)),
+    }
+}
+
+/// "Horror or Thriller", "Animation and Family", "all" for every genre.
+pub fn describe_genres(genre_ids: &[u32], genre_match: GenreMatch, genre_map: &HashMap<u32, String>)
+                       -> String {
+    if genre_ids.is_empty() {
+        return "all".to_owned();
+    }
+
+    let separator = match genre_match {
+        GenreMatch::Any => " or ",
+        GenreMatch::All => " and ",
+    };
+
+    genre_ids
+        .iter()
+        .filter_map(|id| genre_map.get(id).map(|name| name.as_str()))
+        .collect::<Vec<&str>>()
+        .join(separator)
+}
+
+pub fn get_upcoming_movies_by_filter<'a>(genre_ids: &[u32], genre_match: GenreMatch,
+                                         movies: &'a [Movie]) -> Vec<&'a Movie> {
+    movies
+        .iter()
+        .filter(move |movie| is_genre_match(movie, genre_ids, genre_match))
+        .collect()
+}
+
+pub fn valid_genre_names(genre_map: &HashMap<u32, String>) -> Vec<String> {
+    let mut names: Vec<String> = genre_map.values().cloned().collect();
+    names.sort();
+    names
+}
+
+pub fn get_genre_id_by_name(genre_name: &str, genre_map: &HashMap<u32, String>)
+                            -> Result<u32, AppError> {
+    genre_map
+        .iter()
+        .filter(|&(_, name) | name.to_lowercase() == genre_name.to_lowercase())
+        .map(|(id, _)| *id)
+        .last()
+        .ok_or(AppError::GenreIdNotFoundError(genre_name.to_owned(), valid_genre_names(genre_map)))
+}
+
+/// Ids of the genres given with --genre, Animation when there are none and
+/// none at all for ANY_GENRE.
+pub fn resolve_genre_ids(genre_names: &[String], genre_map: &HashMap<u32, String>)
+                         -> Result<Vec<u32>, AppError> {
+    if genre_names.is_empty() {
+        return get_known_genre_id(KnownGenre::Animation, genre_map).map(|id| vec![id]);
+    }
+    if genre_names.iter().any(|name| name.eq_ignore_ascii_case(ANY_GENRE)) {
+        return Ok(Vec::new());
+    }
+
+    let mut ids: Vec<u32> = Vec::new();
+
+    for name in genre_names.iter() {
+        let id = match name.parse::<KnownGenre>() {
+            Ok(genre) => get_known_genre_id(genre, genre_map),
+            Err(_) => get_genre_id_by_name(name, genre_map),
+        }?;
+
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    Ok(ids)
+}
+
+// prefer the live genre list, but fall back on the well known id in case
+// the name is spelled differently there
+fn get_known_genre_id(genre: KnownGenre, genre_map: &HashMap<u32, String>) -> Result<u32, AppError> {
+    get_genre_id_by_name(genre.name(), genre_map)
+        .or_else(|e| {
+            if genre_map.contains_key(&genre.id()) {
+                Ok(genre.id())
+            } else {
+                Err(e)
+            }
+        })
+}
+
+pub fn get_genre_name_from_ids(ids: &[u32], genre_map: &HashMap<u32, String>) -> String {
+    ids.iter()
+        .map(|i| genre_map.get(i) )
+        .fold((String::new(), true), |(mut result, is_first), r| {
+            if let Some(s) = r {
+                if !is_first {
+                    result.push_str(", ");
+                }
+
+                result.push_str(s);
+            };
+
+            (result, false)
+        })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn genre_map() -> HashMap<u32, String> {
+        vec![(16, "Animation".to_owned()), (27, "Horror".to_owned())].into_iter().collect()
+    }
+
+    #[test]
+    fn any_genre_resolves_to_no_ids() {
+        let names = vec!["Horror".to_owned(), "Any".to_owned()];
+
+        assert_eq!(resolve_genre_ids(&names, &genre_map()).unwrap(), Vec::<u32>::new());
+        assert_eq!(resolve_genre_ids(&[], &genre_map()).unwrap(), vec![16]);
+        assert_eq!(describe_genres(&[], GenreMatch::Any, &genre_map()), "all");
+    }
+
+    #[test]
+    fn no_genre_ids_match_every_movie() {
+        let movie = Movie {
+            poster_path: None,
+            adult: false,
+            overview: String::new(),
+            release_date: "2025-01-01".to_owned(),
+            genre_ids: vec![80],
+            id: 1,
+            title: "Heat".to_owned(),
+            vote_average: 0.0,
+            vote_count: 0,
+            region_release_dates: Default::default(),
+            translated: None,
+            excluded: None,
+            status: None,
+            runtime: None,
+        };
+
+        assert!(is_genre_match(&movie, &[], GenreMatch::All));
+        assert!(!is_genre_match(&movie, &[16], GenreMatch::Any));
+    }
+}
@@ -0,0 +1,142 @@
+// The standard TMD movie genres, so common genres can be named in code
+// without a string lookup.  The live genre list is still what ids are
+// resolved against, this only covers the well known names, and stands in
+// for it when it cannot be fetched.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownGenre {
+    Action,
+    Adventure,
+    Animation,
+    Comedy,
+    Crime,
+    Documentary,
+    Drama,
+    Family,
+    Fantasy,
+    History,
+    Horror,
+    Music,
+    Mystery,
+    Romance,
+    ScienceFiction,
+    TvMovie,
+    Thriller,
+    War,
+    Western,
+}
+
+pub const ALL_KNOWN_GENRES: [KnownGenre; 19] = [
+    KnownGenre::Action,
+    KnownGenre::Adventure,
+    KnownGenre::Animation,
+    KnownGenre::Comedy,
+    KnownGenre::Crime,
+    KnownGenre::Documentary,
+    KnownGenre::Drama,
+    KnownGenre::Family,
+    KnownGenre::Fantasy,
+    KnownGenre::History,
+    KnownGenre::Horror,
+    KnownGenre::Music,
+    KnownGenre::Mystery,
+    KnownGenre::Romance,
+    KnownGenre::ScienceFiction,
+    KnownGenre::TvMovie,
+    KnownGenre::Thriller,
+    KnownGenre::War,
+    KnownGenre::Western,
+];
+
+impl KnownGenre {
+    /// Genre name as used by the TMD api (en-US).
+    pub fn name(&self) -> &'static str {
+        match *self {
+            KnownGenre::Action => "Action",
+            KnownGenre::Adventure => "Adventure",
+            KnownGenre::Animation => "Animation",
+            KnownGenre::Comedy => "Comedy",
+            KnownGenre::Crime => "Crime",
+            KnownGenre::Documentary => "Documentary",
+            KnownGenre::Drama => "Drama",
+            KnownGenre::Family => "Family",
+            KnownGenre::Fantasy => "Fantasy",
+            KnownGenre::History => "History",
+            KnownGenre::Horror => "Horror",
+            KnownGenre::Music => "Music",
+            KnownGenre::Mystery => "Mystery",
+            KnownGenre::Romance => "Romance",
+            KnownGenre::ScienceFiction => "Science Fiction",
+            KnownGenre::TvMovie => "TV Movie",
+            KnownGenre::Thriller => "Thriller",
+            KnownGenre::War => "War",
+            KnownGenre::Western => "Western",
+        }
+    }
+
+    /// Genre id as used by the TMD api.
+    pub fn id(&self) -> u32 {
+        match *self {
+            KnownGenre::Action => 28,
+            KnownGenre::Adventure => 12,
+            KnownGenre::Animation => 16,
+            KnownGenre::Comedy => 35,
+            KnownGenre::Crime => 80,
+            KnownGenre::Documentary => 99,
+            KnownGenre::Drama => 18,
+            KnownGenre::Family => 10751,
+            KnownGenre::Fantasy => 14,
+            KnownGenre::History => 36,
+            KnownGenre::Horror => 27,
+            KnownGenre::Music => 10402,
+            KnownGenre::Mystery => 9648,
+            KnownGenre::Romance => 10749,
+            KnownGenre::ScienceFiction => 878,
+            KnownGenre::TvMovie => 10770,
+            KnownGenre::Thriller => 53,
+            KnownGenre::War => 10752,
+            KnownGenre::Western => 37,
+        }
+    }
+}
+
+/// Genre id to name of all the known genres, in the shape of the live genre
+/// list (en-US).
+pub fn known_genre_map() -> HashMap<u32, String> {
+    ALL_KNOWN_GENRES
+        .iter()
+        .map(|g| (g.id(), g.name().to_owned()))
+        .collect()
+}
+
+impl fmt::Display for KnownGenre {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// "Science Fiction", "science-fiction" and "sciencefiction" all compare equal
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+impl FromStr for KnownGenre {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = normalize(s);
+
+        ALL_KNOWN_GENRES
+            .iter()
+            .find(|g| normalize(g.name()) == wanted)
+            .cloned()
+            .ok_or(format!("unknown genre: {}", s))
+    }
+}
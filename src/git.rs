@@ -0,0 +1,46 @@
+// Optional history of the data directory in git.  With `git_commit` in the
+// [state] section of the config file, the data directory is made a git repo
+// if it is not one yet and every change to it is committed after a run,
+// with `git_push` it is pushed to the upstream of the branch too.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use crate::AppError;
+
+fn git(dir: &Path, args: &[&str]) -> Result<Output, AppError> {
+    debug!("Running git {} in {:?}", args.join(" "), dir);
+
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        return Err(AppError::GitError(args.join(" "), stderr));
+    }
+
+    Ok(output)
+}
+
+/// Commits every change in `dir` with `message`, returns whether there was
+/// anything to commit.
+pub fn commit_all(dir: &Path, message: &str) -> Result<bool, AppError> {
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "--quiet"])?;
+        info!("Made a git repo for the data directory: {:?}", dir);
+    }
+
+    git(dir, &["add", "--all"])?;
+
+    if git(dir, &["status", "--porcelain"])?.stdout.is_empty() {
+        debug!("Nothing changed in: {:?}", dir);
+        return Ok(false);
+    }
+
+    git(dir, &["commit", "--quiet", "--message", message])?;
+
+    Ok(true)
+}
+
+pub fn push(dir: &Path) -> Result<(), AppError> {
+    git(dir, &["push", "--quiet"]).map(|_| ())
+}
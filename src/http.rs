@@ -0,0 +1,253 @@
+// A request that fails is reported with what came back: the headers and the
+// start of the body of a response with an error status, which usually say
+// what was wrong with the request.  Api keys and tokens in the query are
+// blanked out of all of it, the url in the error included.  Urls that are
+// secrets as a whole, like Slack and Discord webhooks, are left out of the
+// error altogether.  Requests give up after a while, so that a host that
+// does not answer cannot hold up a run.
+
+use reqwest::{Client, Response, Url};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::AppError;
+
+// of the body kept, an html error page can go on for a while
+const MAX_BODY_CHARS: usize = 1000;
+
+// query params and headers whose values are secrets
+const SECRET_QUERY_PARAMS: [&str; 4] = ["api_key", "token", "access_token", "session_id"];
+const SECRET_HEADERS: [&str; 5] = ["authorization", "proxy-authorization", "cookie", "set-cookie",
+                                   "x-gotify-key"];
+
+const REDACTED: &str = "REDACTED";
+
+// a host that cannot be reached within this is given up on, and the next api
+// host is tried
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a whole request, the body included, may take unless [http]
+/// timeout_secs says otherwise.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+// of the config, set once before the first client
+static REQUEST_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Makes the requests of every client made from now on time out after
+/// `timeout`, instead of DEFAULT_TIMEOUT_SECS.  Only the first call counts.
+pub fn set_timeout(timeout: Duration) {
+    if REQUEST_TIMEOUT.set(timeout).is_err() {
+        debug!("The request timeout is set already");
+    }
+}
+
+/// A client whose requests time out, for the api and the notifiers alike.
+///
+/// Proxies are taken from HTTP_PROXY, HTTPS_PROXY and NO_PROXY.
+pub fn client() -> Client {
+    let timeout = *REQUEST_TIMEOUT.get_or_init(|| Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT.min(timeout))
+        .timeout(timeout)
+        .build()
+        // the same as `Client::new`, which panics too
+        .expect("the TLS backend cannot be initialized")
+}
+
+/// The headers and the start of the body of a response with an error
+/// status, secrets blanked out.
+#[derive(Debug)]
+pub struct ErrorResponse {
+    pub headers: Vec<(String, String)>,
+    // whitespace squeezed, so it fits on one line
+    pub body: String,
+}
+
+/// The response to the request sent, unless it has an error status or
+/// could not be sent, which is reported as `message`.
+pub async fn check_response(sent: Result<Response, reqwest::Error>, message: &str)
+                            -> Result<Response, AppError> {
+    let response = sent
+        .map_err(|e| AppError::RestClientError(message.to_owned(), redact_url(e), None))?;
+
+    let error = match response.error_for_status_ref() {
+        Ok(_) => return Ok(response),
+        Err(e) => redact_url(e),
+    };
+
+    let secrets = secrets_in(response.url());
+
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_owned()
+            } else {
+                redact(&String::from_utf8_lossy(value.as_bytes()), &secrets)
+            };
+
+            (name.to_string(), value)
+        })
+        .collect();
+
+    // only there for the report, a body that cannot be read is no reason for
+    // another error
+    let body = response.text().await.unwrap_or_default();
+    let body = truncate(&redact(&body, &secrets));
+
+    Err(AppError::RestClientError(message.to_owned(), error,
+                                  Some(ErrorResponse { headers, body })))
+}
+
+/// Same as `check_response`, for posting to a url that holds a secret in
+/// its path, e.g. a webhook: the url is left out of the error.
+pub async fn check_post(sent: Result<Response, reqwest::Error>, message: &str)
+                        -> Result<Response, AppError> {
+    check_response(sent, message).await.map_err(hide_url)
+}
+
+// the error of a request without its url, see `check_post`
+fn hide_url(error: AppError) -> AppError {
+    match error {
+        AppError::RestClientError(msg, cause, response) => {
+            AppError::RestClientError(msg, cause.without_url(), response)
+        },
+        e => e,
+    }
+}
+
+/// The error with the secrets in the query of its url, if it has one,
+/// blanked out.
+pub fn redact_url(mut error: reqwest::Error) -> reqwest::Error {
+    if let Some(url) = error.url_mut() {
+        redact_query(url);
+    }
+
+    error
+}
+
+fn redact_query(url: &mut Url) {
+    if secrets_in(url).is_empty() {
+        return;
+    }
+
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if SECRET_QUERY_PARAMS.contains(&name.as_ref()) {
+                REDACTED.to_owned()
+            } else {
+                value.into_owned()
+            };
+
+            (name.into_owned(), value)
+        })
+        .collect();
+
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+}
+
+// values of the secret query params, which a proxy or mirror may well echo
+// back
+fn secrets_in(url: &Url) -> Vec<String> {
+    url.query_pairs()
+        .filter(|(name, value)| SECRET_QUERY_PARAMS.contains(&name.as_ref()) && !value.is_empty())
+        .map(|(_, value)| value.into_owned())
+        .collect()
+}
+
+fn redact(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_owned(), |text, secret| text.replace(secret.as_str(), REDACTED))
+}
+
+fn truncate(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if body.chars().count() <= MAX_BODY_CHARS {
+        body
+    } else {
+        let mut truncated: String = body.chars().take(MAX_BODY_CHARS).collect();
+        truncated.push_str("...");
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_blanks_out_every_secret() {
+        let secrets = vec!["abc123".to_owned(), "xyz".to_owned()];
+
+        assert_eq!(redact("key abc123, again abc123 and xyz", &secrets),
+                   "key REDACTED, again REDACTED and REDACTED");
+        assert_eq!(redact("nothing here", &[]), "nothing here");
+    }
+
+    #[test]
+    fn redact_query_blanks_out_secret_params_only() {
+        let mut url = Url::parse("https://api.example.com/3/movie/upcoming?api_key=abc123&\
+                                  language=en-US&token=xyz").unwrap();
+        redact_query(&mut url);
+
+        assert_eq!(url.query(), Some("api_key=REDACTED&language=en-US&token=REDACTED"));
+    }
+
+    #[test]
+    fn redact_query_leaves_urls_without_secrets_alone() {
+        let mut url = Url::parse("https://api.example.com/3/list/10?page=1&q=a%20b").unwrap();
+        redact_query(&mut url);
+
+        assert_eq!(url.as_str(), "https://api.example.com/3/list/10?page=1&q=a%20b");
+    }
+
+    #[test]
+    fn secrets_in_skips_empty_values() {
+        let url = Url::parse("https://example.com/?api_key=&access_token=t0k&page=2").unwrap();
+
+        assert_eq!(secrets_in(&url), vec!["t0k".to_owned()]);
+    }
+
+    #[test]
+    fn truncate_squeezes_whitespace() {
+        assert_eq!(truncate("<html>\n  <body>  Not   found\n</body>"),
+                   "<html> <body> Not found </body>");
+    }
+
+    #[test]
+    fn truncate_cuts_long_bodies_on_char_boundaries() {
+        let body = "é".repeat(MAX_BODY_CHARS + 10);
+        let truncated = truncate(&body);
+
+        assert_eq!(truncated.chars().count(), MAX_BODY_CHARS + 3);
+        assert!(truncated.ends_with("é..."));
+    }
+
+    // nothing listens on the discard port, so sending fails right away with
+    // the url in the error
+    async fn connect_error(url: &str) -> reqwest::Error {
+        reqwest::Client::new().get(url).send().await.unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn redact_url_keeps_the_api_key_out_of_the_error() {
+        let error = redact_url(connect_error("http://127.0.0.1:9/3/genre?api_key=abc123").await);
+
+        assert!(!error.to_string().contains("abc123"));
+        assert!(!format!("{:?}", error).contains("abc123"));
+        assert_eq!(error.url().and_then(|url| url.query()), Some("api_key=REDACTED"));
+    }
+
+    #[tokio::test]
+    async fn hide_url_leaves_the_url_out() {
+        let cause = connect_error("http://127.0.0.1:9/services/T0/B0/s3cret").await;
+        let error = hide_url(AppError::RestClientError("Error: cannot post".to_owned(), cause,
+                                                       None));
+
+        assert!(!error.to_string().contains("s3cret"));
+        assert!(!format!("{:?}", error).contains("s3cret"));
+    }
+}
@@ -0,0 +1,196 @@
+// Titles out of the CSV or JSON export of a TMDb account (watchlist,
+// ratings, favorites), or any list shaped like it, to seed the state with.
+// Columns are found by their names, ignoring case, spaces and underscores:
+// the TMDb id ("TMDb ID", "id"), else the title ("Title", "Name") and the
+// year ("Year", or the first four digits of "Release Date").  A JSON export
+// is an array of objects with the same keys, or an object holding one as
+// "results".
+
+use serde_json::Value;
+
+// column names and keys, normalized
+const ID_KEYS: &[&str] = &["tmdbid", "id"];
+const TITLE_KEYS: &[&str] = &["title", "name", "originaltitle"];
+const YEAR_KEYS: &[&str] = &["year", "releasedate", "date"];
+
+/// One title of an export, by id when it has one.
+#[derive(Debug, PartialEq)]
+pub struct ExportedTitle {
+    pub id: Option<u32>,
+    pub title: String,
+    pub year: Option<i32>,
+}
+
+// "TMDb ID" -> "tmdbid"
+fn normalize(key: &str) -> String {
+    key.chars()
+        .filter(|c| !c.is_whitespace() && *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// "2025", "2025-07-18"
+fn parse_year(value: &str) -> Option<i32> {
+    value.trim().get(..4).and_then(|year| year.parse().ok())
+}
+
+fn title_of(id: Option<String>, title: Option<String>, year: Option<String>)
+            -> Option<ExportedTitle> {
+    let id = id.and_then(|id| id.trim().parse().ok());
+    let title = title.unwrap_or_default().trim().to_owned();
+    let year = year.and_then(|year| parse_year(&year));
+
+    if id.is_none() && title.is_empty() {
+        return None;
+    }
+
+    Some(ExportedTitle { id, title, year })
+}
+
+/// The titles of the export, CSV or JSON, or what is wrong with it.  Rows
+/// with neither an id nor a title are left out.
+pub fn parse_export(text: &str) -> Result<Vec<ExportedTitle>, String> {
+    let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        parse_json(trimmed)
+    } else {
+        parse_csv(trimmed)
+    }
+}
+
+fn parse_json(text: &str) -> Result<Vec<ExportedTitle>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(mut object) => match object.remove("results") {
+            Some(Value::Array(items)) => items,
+            _ => return Err("expected an array of titles or an object with \"results\"".into()),
+        },
+        _ => return Err("expected an array of titles".to_owned()),
+    };
+
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let object = item.as_object()?;
+            let field = |keys: &[&str]| {
+                object.iter()
+                    .find(|(key, _)| keys.contains(&normalize(key).as_str()))
+                    .and_then(|(_, value)| match value {
+                        Value::String(s) => Some(s.clone()),
+                        Value::Number(n) => Some(n.to_string()),
+                        _ => None,
+                    })
+            };
+
+            title_of(field(ID_KEYS), field(TITLE_KEYS), field(YEAR_KEYS))
+        })
+        .collect())
+}
+
+fn parse_csv(text: &str) -> Result<Vec<ExportedTitle>, String> {
+    let mut rows = csv_rows(text)?.into_iter();
+
+    let header: Vec<String> = rows
+        .next()
+        .ok_or("the export is empty")?
+        .iter()
+        .map(|name| normalize(name))
+        .collect();
+
+    let column = |keys: &[&str]| keys.iter().find_map(|key| header.iter().position(|n| n == key));
+    let (id, title, year) = (column(ID_KEYS), column(TITLE_KEYS), column(YEAR_KEYS));
+
+    if id.is_none() && title.is_none() {
+        return Err("no TMDb ID or Title column in the header".to_owned());
+    }
+
+    Ok(rows
+        .filter_map(|row| {
+            let field = |index: Option<usize>| index.and_then(|index| row.get(index).cloned());
+
+            title_of(field(id), field(title), field(year))
+        })
+        .collect())
+}
+
+// the fields of every line, quoted ones may hold commas, quotes doubled and
+// line breaks
+fn csv_rows(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {},
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            },
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(format!("a quote is not closed on line {}", rows.len() + 1));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title(id: Option<u32>, title: &str, year: Option<i32>) -> ExportedTitle {
+        ExportedTitle { id, title: title.to_owned(), year }
+    }
+
+    #[test]
+    fn parse_export_reads_csv_by_column_name() {
+        let csv = "Title,TMDb ID,Release Date,Your Rating\r\n\
+                   \"Spirited Away\",129,2001-07-20,10\r\n\
+                   \"Heat, the remake\",,2027-01-01,\r\n\
+                   \"Say \"\"hi\"\"\",,,\r\n";
+
+        assert_eq!(parse_export(csv).unwrap(), vec![
+            title(Some(129), "Spirited Away", Some(2001)),
+            title(None, "Heat, the remake", Some(2027)),
+            title(None, "Say \"hi\"", None),
+        ]);
+    }
+
+    #[test]
+    fn parse_export_reads_json() {
+        let json = r#"{"results": [{"id": 129, "title": "Spirited Away",
+                                    "release_date": "2001-07-20"},
+                                   {"name": "Heat", "year": 1995}, {"rating": 9}]}"#;
+
+        assert_eq!(parse_export(json).unwrap(), vec![
+            title(Some(129), "Spirited Away", Some(2001)),
+            title(None, "Heat", Some(1995)),
+        ]);
+    }
+
+    #[test]
+    fn parse_export_says_what_is_wrong() {
+        assert!(parse_export("Rating,Date\n9,2020\n").unwrap_err().contains("header"));
+        assert!(parse_export("Title\n\"Heat\n").unwrap_err().contains("not closed"));
+        assert!(parse_export("{\"page\": 1}").is_err());
+    }
+}
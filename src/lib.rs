@@ -0,0 +1,67 @@
+// The TMD plumbing and everything else movie_alert is built from, so other
+// tools can reuse it.  The binary in main.rs only wires it up to the
+// command line.  The TMD client, the filters and the digest alerts build
+// without any of the features, see Cargo.toml.
+
+#[macro_use]
+extern crate log;
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod alfred;
+#[cfg(feature = "store")]
+pub mod cache;
+pub mod calendar;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod digest;
+pub mod discord;
+#[cfg(feature = "email")]
+pub mod email;
+mod error;
+pub mod filter;
+pub mod genres;
+pub mod git;
+pub mod http;
+pub mod import;
+pub mod links;
+pub mod locale;
+pub mod matching;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(feature = "store")]
+pub mod notifier;
+#[cfg(feature = "desktop")]
+pub mod notify;
+pub mod outlook;
+pub mod overview;
+pub mod posters;
+#[cfg(feature = "push")]
+pub mod push;
+#[cfg(feature = "readlater")]
+pub mod readlater;
+#[cfg(feature = "store")]
+pub mod recap;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "store")]
+pub mod run;
+pub mod slack;
+#[cfg(feature = "store")]
+pub mod suggest;
+#[cfg(feature = "store")]
+pub mod state;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod tmdb;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod watchdog;
+pub mod webhook;
+#[cfg(feature = "store")]
+pub mod weights;
+
+pub use error::{AppError, ErrorReport};
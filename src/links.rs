@@ -0,0 +1,96 @@
+// Every URL that may end up in a browser is built here, and the browser is
+// only ever launched through `open_in_browser`, which refuses anything
+// outside of the known sites.
+
+use std::process::{Command, Stdio};
+use crate::AppError;
+
+const TMD_MOVIE_URL_BASE: &str = "https://www.themoviedb.org/movie";
+const TMD_POSTER_URL_BASE: &str = "https://image.tmdb.org/t/p/w185";
+const TMD_THUMBNAIL_URL_BASE: &str = "https://image.tmdb.org/t/p/w92";
+
+const ALLOWED_URL_PREFIXES: [&str; 5] = [
+    "https://www.themoviedb.org/",
+    "https://www.imdb.com/",
+    "https://m.imdb.com/",
+    "https://www.youtube.com/",
+    "https://youtu.be/",
+];
+
+// characters cmd.exe would act on when the url is handed to `start`
+#[cfg(target_os = "windows")]
+const UNSAFE_URL_CHARS: &str = "\"&|^<>%";
+
+#[cfg(not(target_os = "windows"))]
+const UNSAFE_URL_CHARS: &str = "\"";
+
+#[cfg(target_os = "macos")]
+fn browser_command(url: &str) -> Command {
+    let mut command = Command::new("open");
+    command.arg(url);
+    command
+}
+
+// Linux (including NAS boxes) and the BSDs
+#[cfg(all(unix, not(target_os = "macos")))]
+fn browser_command(url: &str) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(url);
+    command
+}
+
+// `start` is a cmd.exe builtin rather than a program, and its first quoted
+// argument is the window title
+#[cfg(target_os = "windows")]
+fn browser_command(url: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.args(&["/C", "start", ""]).arg(url);
+    command
+}
+
+pub fn tmd_movie_url(id: u32) -> String {
+    TMD_MOVIE_URL_BASE.to_owned() + "/" + &id.to_string()
+}
+
+// poster paths come with a leading slash, e.g. "/abc.jpg"
+pub fn tmd_poster_url(poster_path: &str) -> String {
+    TMD_POSTER_URL_BASE.to_owned() + poster_path
+}
+
+// the smallest size of the same poster
+pub fn tmd_thumbnail_url(poster_path: &str) -> String {
+    TMD_THUMBNAIL_URL_BASE.to_owned() + poster_path
+}
+
+pub fn is_allowed(url: &str) -> bool {
+    ALLOWED_URL_PREFIXES.iter().any(|prefix| url.starts_with(prefix)) &&
+        !url.chars().any(|c| c.is_whitespace() || c.is_control() || UNSAFE_URL_CHARS.contains(c))
+}
+
+/// `browser` is the program to hand the url to, the system default is used
+/// when it is not given.
+pub fn open_in_browser(url: &str, browser: Option<&str>) -> Result<(), AppError> {
+    if !is_allowed(url) {
+        return Err(AppError::BlockedUrlError(url.to_owned()));
+    }
+
+    debug!("Opening in browser: {}", url);
+
+    let mut command = match browser {
+        Some(program) => {
+            let mut command = Command::new(program);
+            command.arg(url);
+            command
+        },
+        None => browser_command(url),
+    };
+
+    // the browser is left running, whether it then loads the page is up to
+    // it, but a program that cannot be started at all is an error
+    match command.stdout(Stdio::inherit()).spawn() {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            Err(AppError::BrowserError(command.get_program().to_string_lossy().into_owned(), e))
+        },
+    }
+}
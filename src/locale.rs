@@ -0,0 +1,115 @@
+// Ratings, vote counts and runtimes written the way the api language writes
+// numbers, "7,5" and "1.234" in German, "7.5" and "1,234" in English, so
+// they read right next to the titles and overviews in that language.  Only
+// the separators and the runtime units change, the words around them stay
+// English.
+
+// languages writing a decimal comma, the others write a point
+const DECIMAL_COMMA: &[&str] = &["cs", "da", "de", "el", "es", "fi", "fr", "hu", "id", "it",
+                                 "nb", "nl", "no", "pl", "pt", "ro", "ru", "sv", "tr", "uk"];
+
+// languages grouping thousands with a point, and with a space, the others
+// with a comma
+const GROUP_POINT: &[&str] = &["da", "de", "el", "es", "id", "it", "nl", "pt", "ro", "tr"];
+const GROUP_SPACE: &[&str] = &["cs", "fi", "fr", "hu", "nb", "no", "pl", "ru", "sv", "uk"];
+
+// "de" of "de-DE"
+fn base_language(language: &str) -> &str {
+    language.split('-').next().unwrap_or(language)
+}
+
+/// `value` with one decimal, "7,5" for de-DE.
+pub fn format_decimal(value: f32, language: &str) -> String {
+    let formatted = format!("{:.1}", value);
+
+    if DECIMAL_COMMA.contains(&base_language(language)) {
+        formatted.replace('.', ",")
+    } else {
+        formatted
+    }
+}
+
+/// `count` in groups of three digits, "1.234" for de-DE, "1 234" with a
+/// no-break space for fr-FR.
+pub fn format_count(count: u32, language: &str) -> String {
+    let separator = match base_language(language) {
+        lang if GROUP_POINT.contains(&lang) => '.',
+        lang if GROUP_SPACE.contains(&lang) => '\u{a0}',
+        _ => ',',
+    };
+
+    let digits = count.to_string();
+    let mut out = String::new();
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(separator);
+        }
+        out.push(digit);
+    }
+
+    out
+}
+
+/// "1h 42m" in English, "1 h 42 min" elsewhere.
+pub fn format_runtime(minutes: u32, language: &str) -> String {
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    let (hour_unit, minute_unit) = if base_language(language) == "en" {
+        ("h", "m")
+    } else {
+        (" h", " min")
+    };
+
+    match hours {
+        0 => format!("{}{}", minutes, minute_unit),
+        _ if minutes == 0 => format!("{}{}", hours, hour_unit),
+        _ => format!("{}{} {}{}", hours, hour_unit, minutes, minute_unit),
+    }
+}
+
+/// "7,5 of 1.234 votes" for de-DE, nothing for a movie without votes, which
+/// most upcoming movies are.
+pub fn format_rating(vote_average: f32, vote_count: u32, language: &str) -> Option<String> {
+    match vote_count {
+        0 => None,
+        1 => Some(format!("{} of 1 vote", format_decimal(vote_average, language))),
+        _ => Some(format!("{} of {} votes", format_decimal(vote_average, language),
+                          format_count(vote_count, language))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_decimal_takes_the_separator_of_the_language() {
+        assert_eq!(format_decimal(7.46, "en-US"), "7.5");
+        assert_eq!(format_decimal(7.46, "de-DE"), "7,5");
+        assert_eq!(format_decimal(7.0, "pt"), "7,0");
+        assert_eq!(format_decimal(7.0, "ja-JP"), "7.0");
+    }
+
+    #[test]
+    fn format_count_groups_thousands() {
+        assert_eq!(format_count(1234567, "en-US"), "1,234,567");
+        assert_eq!(format_count(1234, "de-DE"), "1.234");
+        assert_eq!(format_count(1234, "fr-FR"), "1\u{a0}234");
+        assert_eq!(format_count(999, "de-DE"), "999");
+    }
+
+    #[test]
+    fn format_runtime_writes_hours_and_minutes() {
+        assert_eq!(format_runtime(102, "en-US"), "1h 42m");
+        assert_eq!(format_runtime(102, "fr-FR"), "1 h 42 min");
+        assert_eq!(format_runtime(45, "de-DE"), "45 min");
+        assert_eq!(format_runtime(120, "en-GB"), "2h");
+    }
+
+    #[test]
+    fn format_rating_leaves_out_movies_without_votes() {
+        assert_eq!(format_rating(7.5, 1234, "de-DE").as_deref(), Some("7,5 of 1.234 votes"));
+        assert_eq!(format_rating(8.0, 1, "en-US").as_deref(), Some("8.0 of 1 vote"));
+        assert_eq!(format_rating(0.0, 0, "en-US"), None);
+    }
+}
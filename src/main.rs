@@ -1,373 +1,2440 @@
 #[macro_use]
 extern crate log;
 
+// only the responses of the rpc command are serialized here
+#[cfg(feature = "rpc")]
 #[macro_use]
 extern crate serde_derive;
 
-extern crate roadrunner;
-extern crate env_logger;
-extern crate tokio_core;
-extern crate serde_json;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::fs;
+use futures::{stream, Stream, StreamExt};
+use std::pin::pin;
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use clap::Parser;
+#[cfg(feature = "pick")]
+use dialoguer::{MultiSelect, Select};
+use movie_alert::{alfred, calendar, config, git, http, import, links, locale, notifier, outlook,
+                  overview, posters, recap, suggest, weights};
+#[cfg(feature = "mqtt")]
+use movie_alert::mqtt;
+#[cfg(feature = "rpc")]
+use movie_alert::rpc;
+#[cfg(feature = "tui")]
+use movie_alert::tui;
+use movie_alert::{AppError, ErrorReport};
+use movie_alert::tmdb::{DEFAULT_LOOKUP_CONCURRENCY, DEFAULT_PAGE_CONCURRENCY, DiscoverFilter,
+                        Exclusions, Movie, TMD_API_DEFAULT_LANGUAGE,
+                        TmdClient, Translation, UpcomingSnapshot,
+                        has_translation, is_excluded, release_order, retrieve_company_movies,
+                        retrieve_filtered_discover_movies, retrieve_genre_and_convert_to_map,
+                        retrieve_details, retrieve_genres, retrieve_list,
+                        retrieve_now_playing_movies,
+                        retrieve_translations, retrieve_upcoming_movies_by_page,
+                        retrieve_upcoming_snapshot, retrieve_watchlist, search_company,
+                        search_movie, translated_title};
+use movie_alert::state::{DataPaths, STORE_FILE_NAME, data_paths, dry_run_data_paths,
+                         load_list_members, load_snapshot, load_state, project_dirs,
+                         save_list_members, save_snapshot};
+use movie_alert::filter::{GenreMatch, describe_genres, get_genre_name_from_ids,
+                          get_upcoming_movies_by_filter, is_genre_match, resolve_genre_ids};
+use movie_alert::store::{Delivery, MovieStore};
+use movie_alert::clock::{Clock, SystemClock};
+use movie_alert::cache::{DEFAULT_DETAILS_DAYS, DEFAULT_MAX_MB, PosterCache, ResponseCache};
+use movie_alert::digest::{DigestMovie, release_date_label};
+use movie_alert::import::ExportedTitle;
+use movie_alert::matching::{Confidence, TitleMatch};
+use movie_alert::notifier::{Dispatcher, Notifier};
+use movie_alert::overview::OverviewLength;
+use movie_alert::genres::KnownGenre;
+use movie_alert::watchdog::{capped, RunLimits, RunWatchdog};
+use movie_alert::weights::GenreWeight;
+use movie_alert::config::{Alert, Config};
+use movie_alert::cli::{CacheAction, Cli, Command, GroupAction, OutlookFormat, OutputFormat,
+                       StateAction, TranslationCheck};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+const TMD_API_V3_ENV_KEY_NAME: &str = "TMD_API_V3";
+
+static TMD_CLIENT: OnceLock<TmdClient> = OnceLock::new();
+
+// optional, comma separated api base urls tried in order, for proxies,
+// caching gateways or mirrors.  Defaults to https://api.themoviedb.org/3
+const TMD_API_BASE_URL_ENV_KEY_NAME: &str = "TMD_API_BASE_URL";
+
+// incoming webhook for --alert slack, wins over [slack] in the config file
+const SLACK_WEBHOOK_URL_ENV_KEY_NAME: &str = "SLACK_WEBHOOK_URL";
+
+// webhook for --alert discord, wins over [discord] in the config file
+const DISCORD_WEBHOOK_URL_ENV_KEY_NAME: &str = "DISCORD_WEBHOOK_URL";
+
+#[tokio::main]
+async fn main() {
+
+    ::std::process::exit(match process().await {
+        Ok(_) => 0,
+        Err(e) => {
+            e.report_error();
+            1
+        },
+    });
+}
+
+// settings from the command line and config file that the stages after
+// fetching need
+struct RunOptions<'a> {
+    genres: Vec<String>,
+    genre_match: GenreMatch,
+    output: OutputFormat,
+    overview: OverviewLength,
+    // ids or names, their upcoming movies are alerted on whatever the genres
+    companies: Vec<String>,
+    lists: Vec<u32>,
+    offline: bool,
+    // upcoming movies of all of these are merged, the configured region
+    // is used when empty
+    regions: Vec<String>,
+    // months of discovered movies added past the upcoming window
+    extend_months: u32,
+    browser: Option<&'a str>,
+    // of the api, which ratings, vote counts and runtimes are written in
+    language: &'a str,
+    // every way new movies are alerted about, never empty
+    alerts: Vec<Alert>,
+    // the browser is never launched, whatever `alert` says
+    no_open: bool,
+    // new movies to alert on are chosen at the terminal
+    pick: bool,
+    // for rpc, where stdout is only for responses
+    quiet: bool,
+    // nothing is alerted on or written, new movies are only reported
+    dry_run: bool,
+    // movies first seen longer ago are left out
+    new_within: Option<Duration>,
+    // whether movies are looked up for a translation into the api language
+    translation: Option<TranslationCheck>,
+    // movies of these are never alerted on, empty offline
+    exclusions: Exclusions,
+    // production statuses of the movies shown and alerted on, any when empty
+    statuses: Vec<String>,
+    // how long the details and translations of a movie are cached
+    details_ttl: Duration,
+    // movies whose details or translations are asked for at a time
+    lookup_concurrency: usize,
+}
+
+fn config_file_path() -> Result<PathBuf, AppError> {
+    let home = env::home_dir().ok_or(AppError::HomeDirectoryError)?;
+
+    Ok(config::config_path(project_dirs()?.config_dir(), &home))
+}
+
+async fn process() -> Result<(), AppError> {
+
+    // make it possible to see logs by:
+    //          RUST_LOG="movie_alert=debug" cargo run
+    //          RUST_LOG="movie_alert" cargo run
+    env_logger::init().map_err(AppError::EnvLogError)?;
+
+    let cli = Cli::parse();
+
+    if cli.version {
+        print_version(cli.build_info);
+        return Ok(());
+    }
+
+    let config_path = config_file_path()?;
+    let mut config = config::load(&config_path)?;
+
+    http::set_timeout(Duration::from_secs(
+        config.http.timeout_secs.unwrap_or(http::DEFAULT_TIMEOUT_SECS)));
+
+    if cli.language.is_some() {
+        config.language = cli.language.clone();
+    }
+
+    let mut limits = cli.limits();
+    if limits.max_notifications.is_none() {
+        limits.max_notifications = config.notifications.max_per_run;
+    }
+
+    let mut watchdog = RunWatchdog::start(limits);
+
+    let Cli { command, genres, genre_match, output, overview, companies, lists, offline, alerts,
+              no_open, pick, regions, extend_months, dry_run, new_within, translation,
+              excluded_companies, excluded_keywords, excluded_people, statuses, .. } = cli;
+    let command = command.unwrap_or(Command::Check);
+
+    let options = RunOptions {
+        genres: if genres.is_empty() { config.genres.clone() } else { genres },
+        genre_match,
+        output,
+        overview,
+        companies: if companies.is_empty() { config.companies.clone() } else { companies },
+        lists,
+        offline,
+        regions,
+        extend_months: extend_months.or(config.extend_months).unwrap_or(0),
+        browser: config.browser.as_deref(),
+        language: config.language.as_deref().unwrap_or(TMD_API_DEFAULT_LANGUAGE),
+        alerts: if !alerts.is_empty() {
+            alerts
+        } else if !config.notifications.alert.is_empty() {
+            config.notifications.alert.clone()
+        } else {
+            vec![Alert::Browser]
+        },
+        no_open,
+        pick,
+        quiet: matches!(command, Command::Rpc),
+        dry_run,
+        new_within,
+        translation,
+        // offline there are no details to look at, and nothing is alerted on
+        exclusions: if offline {
+            Exclusions::default()
+        } else {
+            Exclusions {
+                company_ids: or_config(excluded_companies, &config.excluded_companies),
+                keyword_ids: or_config(excluded_keywords, &config.excluded_keywords),
+                person_ids: or_config(excluded_people, &config.excluded_people),
+            }
+        },
+        statuses,
+        details_ttl: Duration::from_secs(
+            config.cache.details_days.unwrap_or(DEFAULT_DETAILS_DAYS) * 24 * 60 * 60),
+        lookup_concurrency: config.enrichment.concurrency.unwrap_or(DEFAULT_LOOKUP_CONCURRENCY),
+    };
+
+    if let Some(feature) = missing_feature(&command, &options) {
+        return Err(AppError::FeatureError(feature));
+    }
+    if let Some(needs) = options.alerts.iter().find_map(|&alert| missing_section(alert, &config)) {
+        return Err(AppError::ArgumentError(
+            format!("alerting by {} in {}", needs, config_path.display())));
+    }
+
+    match command {
+        Command::Config => return show_config(&config_path, &config),
+        Command::State { action } => return change_state(action, &options, &config),
+        Command::Group { .. } | Command::Watched { .. } | Command::Open { .. }
+        | Command::Undo if options.dry_run => {
+            return Err(AppError::ArgumentError(
+                "--dry-run does not apply to group, watched, open and undo, which only change \
+                 state".to_owned()));
+        },
+        Command::Undo => return undo(&config),
+        Command::Cache { action } => return change_cache(action, &config),
+        Command::Import { file, group } => {
+            return capped(limits, import_export(&file, &group, &options, &config,
+                                                &mut watchdog)).await;
+        },
+        Command::Group { action } => return change_group(action, &config),
+        Command::Weights => return show_weights(),
+        Command::Suggest => return show_suggestions(&options),
+        Command::Watched { id, date, rating } => {
+            return mark_watched(id, date.as_deref(), rating, &config);
+        },
+        Command::Recap { year } => return show_recap(year),
+        Command::Open { id } => return open_movie(id, &config, &mut watchdog),
+        Command::Smoke if offline => {
+            return Err(AppError::OfflineError("smoke is a test of the api itself".to_owned()));
+        },
+        Command::Smoke => {
+            return capped(limits, smoke_test(&options, &config, &mut watchdog)).await;
+        },
+        _ if offline && options.translation.is_some() => {
+            return Err(AppError::OfflineError(
+                "--translation looks up every movie in the api".to_owned()));
+        },
+        _ if offline && !options.statuses.is_empty() => {
+            return Err(AppError::OfflineError(
+                "--status looks up every movie in the api".to_owned()));
+        },
+        Command::Check if offline => {
+            return Err(AppError::OfflineError(
+                "check only opens movies found in fresh results".to_owned()));
+        },
+        Command::NowPlaying if offline => {
+            return Err(AppError::OfflineError(
+                "now-playing only alerts on fresh results".to_owned()));
+        },
+        Command::NowPlaying => {
+            return capped(limits, now_playing(&options, &config, &mut watchdog)).await;
+        },
+        Command::Announced { .. } if offline => {
+            return Err(AppError::OfflineError(
+                "announced only alerts on fresh results".to_owned()));
+        },
+        Command::Announced { .. } if options.regions.len() > 1 => {
+            return Err(AppError::ArgumentError(
+                "announced takes one --region, the one release dates are in".to_owned()));
+        },
+        Command::Announced { years } => {
+            return capped(limits, announced(years, &options, &config, &mut watchdog)).await;
+        },
+        Command::Discover { .. } if offline => {
+            return Err(AppError::OfflineError(
+                "discover only alerts on fresh results".to_owned()));
+        },
+        Command::Discover { .. } if options.regions.len() > 1 => {
+            return Err(AppError::ArgumentError(
+                "discover takes one --region, whose certifications --certification is in"
+                    .to_owned()));
+        },
+        Command::Discover { companies, keywords, from, to, year, min_vote, original_language,
+                            certification } => {
+            // the genres and region are filled in once there is a client
+            let filter = DiscoverFilter {
+                genre_ids: Vec::new(),
+                genre_match: options.genre_match,
+                company_ids: companies,
+                keyword_ids: keywords,
+                from,
+                to,
+                year,
+                min_vote,
+                original_language,
+                certification,
+                region: String::new(),
+            };
+
+            return capped(limits, discover(filter, &options, &config, &mut watchdog)).await;
+        },
+        Command::Watch { .. } if offline => {
+            return Err(AppError::OfflineError(
+                "watch keeps checking fresh results".to_owned()));
+        },
+        Command::Watch { .. } | Command::Rpc if options.pick => {
+            return Err(AppError::ArgumentError(
+                "--pick needs someone at the terminal, use it with check".to_owned()));
+        },
+        Command::Rpc if options.alerts.contains(&Alert::Stdout) => {
+            return Err(AppError::ArgumentError(
+                "--alert stdout would get in the way of the responses on stdout".to_owned()));
+        },
+        Command::Watch { every } => return watch(every, limits, &options, &config).await,
+        #[cfg(feature = "rpc")]
+        Command::Rpc => return serve_rpc(limits, &options, &config).await,
+        _ => {},
+    }
+
+    capped(limits, run(command, &options, &config, &mut watchdog)).await
+}
+
+// --dry-run leaves the files of older versions where they are
+fn options_data_paths(options: &RunOptions) -> Result<DataPaths, AppError> {
+    if options.dry_run {
+        dry_run_data_paths()
+    } else {
+        data_paths()
+    }
+}
+
+// the cargo feature needed by the command or the flags that this binary was
+// built without, see [features] in Cargo.toml
+fn missing_feature(command: &Command, options: &RunOptions) -> Option<&'static str> {
+    if matches!(command, Command::Tui) && !cfg!(feature = "tui") {
+        return Some("tui");
+    }
+    if matches!(command, Command::Rpc) && !cfg!(feature = "rpc") {
+        return Some("rpc");
+    }
+    if options.pick && !cfg!(feature = "pick") {
+        return Some("pick");
+    }
+
+    options.alerts.iter().find_map(|alert| match alert {
+        Alert::Email if !cfg!(feature = "email") => Some("email"),
+        Alert::Desktop | Alert::Both if !cfg!(feature = "desktop") => Some("desktop"),
+        Alert::Ntfy | Alert::Gotify if !cfg!(feature = "push") => Some("push"),
+        Alert::Instapaper | Alert::Wallabag if !cfg!(feature = "readlater") => Some("readlater"),
+        Alert::Mqtt if !cfg!(feature = "mqtt") => Some("mqtt"),
+        _ => None,
+    })
+}
+
+// a notifier for every alert in `options`, in that order
+fn notifiers<'a>(options: &RunOptions<'a>, config: &'a Config) -> Vec<Box<dyn Notifier + 'a>> {
+    let mut notifiers: Vec<Box<dyn Notifier + 'a>> = Vec::new();
+
+    for alert in options.alerts.iter() {
+        let wants_browser = matches!(alert, Alert::Browser | Alert::Both) && !options.no_open;
+
+        if wants_browser {
+            notifiers.push(Box::new(notifier::Browser { browser: options.browser }));
+        }
+        #[cfg(feature = "desktop")]
+        if matches!(alert, Alert::Desktop | Alert::Both) {
+            notifiers.push(Box::new(notifier::Desktop { browser: options.browser }));
+        }
+
+        // the sections were checked to be there before anything is fetched
+        match alert {
+            Alert::Stdout => notifiers.push(Box::new(notifier::Stdout)),
+            #[cfg(feature = "email")]
+            Alert::Email => {
+                if let Some(ref settings) = config.email {
+                    notifiers.push(Box::new(notifier::Email(settings)));
+                }
+            },
+            Alert::Webhook => {
+                if let Some(ref settings) = config.webhook {
+                    notifiers.push(Box::new(notifier::Webhook(settings)));
+                }
+            },
+            Alert::Slack => {
+                if let Some(url) = slack_webhook_url(config) {
+                    notifiers.push(Box::new(notifier::Slack(url)));
+                }
+            },
+            Alert::Discord => {
+                if let Some(url) = discord_webhook_url(config) {
+                    notifiers.push(Box::new(notifier::Discord(url)));
+                }
+            },
+            #[cfg(feature = "push")]
+            Alert::Ntfy => {
+                if let Some(ref settings) = config.ntfy {
+                    notifiers.push(Box::new(notifier::Ntfy(settings)));
+                }
+            },
+            #[cfg(feature = "push")]
+            Alert::Gotify => {
+                if let Some(ref settings) = config.gotify {
+                    notifiers.push(Box::new(notifier::Gotify(settings)));
+                }
+            },
+            #[cfg(feature = "readlater")]
+            Alert::Instapaper => {
+                if let Some(ref settings) = config.instapaper {
+                    notifiers.push(Box::new(notifier::Instapaper(settings)));
+                }
+            },
+            #[cfg(feature = "readlater")]
+            Alert::Wallabag => {
+                if let Some(ref settings) = config.wallabag {
+                    notifiers.push(Box::new(notifier::Wallabag::new(settings)));
+                }
+            },
+            #[cfg(feature = "mqtt")]
+            Alert::Mqtt => {
+                if let Some(ref settings) = config.mqtt {
+                    notifiers.push(Box::new(notifier::Mqtt(settings)));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    notifiers
+}
+
+// fresh upcoming movies, or the ones of the last online run with --offline
+async fn fetch_snapshot(paths: &DataPaths, options: &RunOptions<'_>, config: &Config,
+                        watchdog: &mut RunWatchdog, errors: &mut ErrorReport)
+                        -> Result<(Option<&'static TmdClient>, UpcomingSnapshot), AppError> {
+    if options.offline {
+        let snapshot = load_snapshot(&paths.snapshot)?;
+
+        if let Some(ref language) = config.language {
+            if *language != snapshot.language {
+                warn!("Offline data is in {}, not {}, run once without --offline to get it in {}",
+                      snapshot.language, language, language);
+            }
+        }
+
+        return Ok((None, snapshot));
+    }
+
+    let client = tmd_client_from_config(config)?;
+
+    let regions = wanted_regions(options, client);
+
+    let snapshot = retrieve_upcoming_snapshot(client, &regions, options.extend_months,
+                                              watchdog).await?;
+    // only --offline needs it, and only later
+    if !options.dry_run {
+        if let Err(e) = save_snapshot(&snapshot, &paths.snapshot) {
+            errors.add("saving offline data", e);
+        }
+    }
+
+    Ok((Some(client), snapshot))
+}
+
+// the --region regions, the configured one when there are none
+fn wanted_regions(options: &RunOptions<'_>, client: &TmdClient) -> Vec<String> {
+    if options.regions.is_empty() {
+        vec![client.region().to_owned()]
+    } else {
+        options.regions.clone()
+    }
+}
+
+// ids and names of the watched companies, the ones that cannot be found are
+// left out and reported at the end of the run
+async fn resolve_companies(names: &[String], client: &TmdClient, watchdog: &mut RunWatchdog,
+                           errors: &mut ErrorReport) -> Result<Vec<(u32, String)>, AppError> {
+    let mut companies = Vec::new();
+
+    for name in names.iter() {
+        if let Ok(id) = name.parse::<u32>() {
+            companies.push((id, name.clone()));
+            continue;
+        }
+
+        match search_company(name, client, watchdog).await {
+            Ok(Some(company)) => {
+                debug!("Watching company {} as {} ({})", name, company.name, company.id);
+                companies.push((company.id, company.name));
+            },
+            Ok(None) => {
+                errors.add("looking up a watched company", AppError::ArgumentError(
+                    format!("no TMD company is called {}", name)));
+            },
+            Err(e @ AppError::RestClientError(..)) => {
+                errors.add("looking up a watched company", e);
+            },
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(companies)
+}
+
+// everything that needs the upcoming movies, that is every command not
+// handled in `process`
+async fn run(command: Command, options: &RunOptions<'_>, config: &Config,
+             watchdog: &mut RunWatchdog) -> Result<(), AppError> {
+    let paths = options_data_paths(options)?;
+    let mut errors = ErrorReport::default();
+
+    let (client, mut snapshot) = fetch_snapshot(&paths, options, config, watchdog,
+                                                &mut errors).await?;
+
+    // --translation and --status are never given offline and there are no
+    // exclusions offline, so there is a client for them
+    if let Some(client) = client.filter(|_| options.translation.is_some()
+                                            || wants_details(options)) {
+        let wanted_genre_ids = resolve_genre_ids(&options.genres, &snapshot.genres)?;
+        let wanted: HashSet<u32> = get_upcoming_movies_by_filter(&wanted_genre_ids,
+                                                                 options.genre_match,
+                                                                 &snapshot.movies)
+            .iter()
+            .map(|movie| movie.id)
+            .collect();
+
+        if options.translation.is_some() {
+            add_translations(snapshot.movies.iter_mut().filter(|m| wanted.contains(&m.id)),
+                             options, client, watchdog, &mut errors).await?;
+        }
+        add_details(snapshot.movies.iter_mut().filter(|movie| wanted.contains(&movie.id)),
+                    options, client, watchdog, &mut errors).await?;
+    }
+
+    let genre_id_to_name = &snapshot.genres;
+    let upcoming_movies = &snapshot.movies;
+    let min_date = &snapshot.min_date;
+    let max_date = &snapshot.max_date;
+
+    let wanted_genre_ids: Vec<u32> = resolve_genre_ids(&options.genres, genre_id_to_name)?;
+    debug!("Wanted genre ids are: {:?}", wanted_genre_ids);
+
+    let wanted_genres = describe_genres(&wanted_genre_ids, options.genre_match, genre_id_to_name);
+
+    let matched_movies = get_upcoming_movies_by_filter(&wanted_genre_ids, options.genre_match,
+                                                       upcoming_movies);
+    let matched_movies = translated_only(matched_movies, options.translation);
+    let matched_movies = wanted_details(matched_movies, options);
+
+    if let Command::Calendar { month } = command {
+        let (year, month) = month
+            .or_else(|| calendar::parse_month(&min_date[..min_date.len().min(7)]))
+            .ok_or(AppError::ArgumentError(
+                format!("cannot derive month from upcoming window: {}", min_date)))?;
+
+        let (store, _) = load_state(&paths)?;
+        show_calendar(year, month, &matched_movies, &store.groups()?);
+
+        return errors.finish();
+    }
+
+    if let Command::Outlook { format } = command {
+        show_outlook(min_date, &matched_movies, genre_id_to_name, format)?;
+
+        return errors.finish();
+    }
+
+    let (store, mut opened_movie_set) = load_state(&paths)?;
+
+    if let Command::Explain { id } = command {
+        explain_movie(id, &snapshot, &wanted_genre_ids, options.genre_match, &opened_movie_set,
+                      &store.deliveries(id)?, &weights::genre_weights(&store)?);
+
+        if let Some(status) = store.status_of(id)? {
+            let previous = status.previous
+                .map(|previous| format!(", was {}", previous))
+                .unwrap_or_default();
+            println!("Stored status: {} since {}{}", status.status, status.changed, previous);
+        }
+
+        return errors.finish();
+    }
+
+    let matched_movies = listed_within(matched_movies, &store, options.new_within)?;
+
+    #[cfg(feature = "tui")]
+    if let Command::Tui = command {
+        tui::browse(&matched_movies, genre_id_to_name, &store, &mut opened_movie_set,
+                    options.browser)?;
+
+        return errors.finish();
+    }
+
+    if options.output == OutputFormat::Cards && !options.quiet {
+        println!("Upcoming {} movies (from {} to {}): {}", wanted_genres, min_date, max_date,
+                 matched_movies.len());
+    }
+
+    if options.output == OutputFormat::Alfred && !options.quiet {
+        print_alfred(&matched_movies, genre_id_to_name, &opened_movie_set)?;
+    }
+
+    if let Command::List { verbose, posters } = command {
+        // anywhere else the cards go without them, see posters.rs
+        let posters = posters && options.output == OutputFormat::Cards && !options.quiet
+            && !options.offline && posters::terminal_shows_images();
+        let cache = match posters {
+            true => Some(PosterCache::open(config.cache.max_mb.unwrap_or(DEFAULT_MAX_MB))?),
+            false => None,
+        };
+
+        // downloaded a few ahead of the card being printed, in its order
+        let mut thumbnails = pin!(stream::iter(matched_movies.iter())
+            .map(|movie| {
+                let cache = cache.as_ref();
+                async move {
+                    match (movie.poster_path.as_deref(), cache) {
+                        (Some(poster_path), Some(cache)) => {
+                            Some(poster_thumbnail(poster_path, cache).await)
+                        },
+                        _ => None,
+                    }
+                }
+            })
+            .buffered(posters::DOWNLOAD_CONCURRENCY));
+
+        for movie in matched_movies.iter() {
+            let thumbnail = thumbnails.next().await.flatten();
+            if let (Some(thumbnail), Some(cache)) = (thumbnail, &cache) {
+                if let Some(thumbnail) = keep_thumbnail(movie, thumbnail, cache, &mut errors)? {
+                    println!("{}", posters::inline_image(&thumbnail));
+                }
+            }
+
+            print_movie(movie, genre_id_to_name, !opened_movie_set.contains(&movie.id),
+                        store.listed_secs(movie.id)?, options.output, options.overview,
+                        options.language);
+
+            if verbose && options.output != OutputFormat::Alfred {
+                print_deliveries(&store.deliveries(movie.id)?, "    ");
+            }
+        }
+
+        return errors.finish();
+    }
+
+    record_statuses(upcoming_movies.iter(), &store, options)?;
+
+    // snoozed movies are left alone as if they were opened, until the snooze
+    // is over
+    opened_movie_set.extend(store.snoozed_ids()?);
+
+    // and so are the ones on the watchlist of the account, which are known
+    // about already
+    if let (Some(account), Some(client)) = (config.account.as_ref(), client) {
+        match fs::read_to_string(&account.session_id_file) {
+            Ok(session_id) => match retrieve_watchlist(session_id.trim(), client, watchdog).await {
+                Ok(watchlisted) => {
+                    debug!("Leaving alone {} movies of the watchlist", watchlisted.len());
+                    opened_movie_set.extend(watchlisted);
+                },
+                Err(e @ AppError::RestClientError(..)) => {
+                    errors.add("getting the watchlist of the account", e);
+                },
+                Err(e) => return Err(e),
+            },
+            // the check goes on as if there were no account
+            Err(e) => {
+                let path = account.session_id_file.display();
+                errors.add("reading the session id of the account", AppError::IOError(
+                    std::io::Error::new(e.kind(), format!("{}: {}", path, e))));
+            },
+        }
+    }
+
+    let opened_before = opened_movie_set.clone();
+    let mut dispatcher = Dispatcher::new(notifiers(options, config), &store)
+        .with_settings(&config.notifications);
+
+    process_found_movies(&matched_movies, genre_id_to_name, &mut opened_movie_set, &store,
+                         watchdog, options, &mut dispatcher, &mut errors).await?;
+
+    // check is never run offline, so there is always a client here
+    if let Some(client) = client {
+        let companies = resolve_companies(&options.companies, client, watchdog,
+                                          &mut errors).await?;
+
+        if !companies.is_empty() {
+            let company_ids: Vec<u32> = companies.iter().map(|&(id, _)| id).collect();
+            let regions = wanted_regions(options, client);
+
+            // the same window as the upcoming movies, extended or not
+            match retrieve_company_movies(&company_ids, min_date, max_date, &regions, client,
+                                          watchdog).await {
+                Ok(mut movies) => {
+                    // the ones of the watched genres were handled above
+                    movies.retain(|movie| !matched_movies.iter().any(|m| m.id == movie.id));
+
+                    if options.translation.is_some() {
+                        add_translations(movies.iter_mut(), options, client, watchdog,
+                                         &mut errors).await?;
+                    }
+                    add_details(movies.iter_mut(), options, client, watchdog,
+                                &mut errors).await?;
+                    record_statuses(movies.iter(), &store, options)?;
+
+                    let company_movies = translated_only(movies.iter().collect(),
+                                                         options.translation);
+                    let company_movies = wanted_details(company_movies, options);
+                    let company_movies = listed_within(company_movies, &store,
+                                                       options.new_within)?;
+
+                    if options.output == OutputFormat::Cards && !options.quiet {
+                        let names: Vec<&str> = companies
+                            .iter()
+                            .map(|(_, name)| name.as_str())
+                            .collect();
+                        println!("Upcoming from {}: {}", names.join(", "), company_movies.len());
+                    }
+
+                    process_found_movies(&company_movies, genre_id_to_name,
+                                         &mut opened_movie_set, &store, watchdog, options,
+                                         &mut dispatcher, &mut errors).await?;
+                },
+                Err(e @ AppError::RestClientError(..)) => {
+                    errors.add("getting the movies of watched companies", e);
+                },
+                Err(e) => return Err(e),
+            }
+        }
+
+        if !options.lists.is_empty() {
+            let mut list_members = load_list_members(&paths.lists)?;
+
+            for &list_id in options.lists.iter() {
+                // the list is diffed again next time
+                let (name, movies) = match retrieve_list(list_id, client, watchdog).await {
+                    Ok(list) => list,
+                    Err(e @ AppError::RestClientError(..)) => {
+                        errors.add("getting a watched list", e);
+                        continue;
+                    },
+                    Err(e) => return Err(e),
+                };
+
+                let ids: BTreeSet<u32> = movies.iter().map(|m| m.id).collect();
+
+                match list_members.insert(list_id, ids) {
+                    None => {
+                        // nothing to diff against on the first run, just remember
+                        // what is on the list now
+                        if !options.quiet {
+                            println!("Now watching list {} ({}): {} movies", list_id, name,
+                                     movies.len());
+                        }
+                    },
+                    Some(previous) => {
+                        let mut added: Vec<&Movie> = movies
+                            .iter()
+                            .filter(|m| !previous.contains(&m.id))
+                            .collect();
+                        added.sort_by(|a, b| release_order(a, b));
+
+                        if options.output == OutputFormat::Cards && !options.quiet {
+                            println!("Added to list {} ({}): {}", list_id, name, added.len());
+                        }
+
+                        process_found_movies(&added, genre_id_to_name,
+                                             &mut opened_movie_set, &store, watchdog,
+                                             options, &mut dispatcher, &mut errors)
+                            .await?;
+                    },
+                }
+            }
+
+            if !options.dry_run {
+                save_list_members(&list_members, &paths.lists)?;
+            }
+        }
+    }
+
+    // movies in the digest only count as alerted once it is sent
+    dispatcher.finish(&mut errors).await?;
+
+    #[cfg(feature = "mqtt")]
+    if let Some(ref settings) = config.mqtt {
+        if options.alerts.contains(&Alert::Mqtt) && !options.dry_run {
+            publish_releasing(settings, &matched_movies, genre_id_to_name, &store, options,
+                              &mut errors).await?;
+        }
+    }
+
+    if !options.dry_run {
+        commit_new_ids(&paths, config, "Check", &opened_before, &store.opened_ids()?,
+                       &mut errors);
+    }
+
+    errors.finish()
+}
+
+// the thumbnail of the poster out of the cache, or downloaded, and whether
+// it was downloaded
+async fn poster_thumbnail(poster_path: &str, cache: &PosterCache)
+                          -> Result<(Vec<u8>, bool), AppError> {
+    if let Some(thumbnail) = cache.get(poster_path) {
+        return Ok((thumbnail, false));
+    }
+
+    posters::retrieve_thumbnail(poster_path).await.map(|thumbnail| (thumbnail, true))
+}
+
+// puts a downloaded thumbnail into the cache, a poster that could not be
+// downloaded is left out of the list
+fn keep_thumbnail(movie: &Movie, thumbnail: Result<(Vec<u8>, bool), AppError>,
+                  cache: &PosterCache, errors: &mut ErrorReport)
+                  -> Result<Option<Vec<u8>>, AppError> {
+    match thumbnail {
+        Ok((thumbnail, downloaded)) => {
+            if let (true, Some(poster_path)) = (downloaded, movie.poster_path.as_deref()) {
+                if let Err(e) = cache.put(poster_path, &thumbnail) {
+                    errors.add("caching a poster", e);
+                }
+            }
+
+            Ok(Some(thumbnail))
+        },
+        Err(e @ AppError::RestClientError(..)) => {
+            errors.add("getting a poster", e);
+
+            Ok(None)
+        },
+        Err(e) => Err(e),
+    }
+}
+
+// sets the Home Assistant sensor to the watched movies releasing in the next
+// seven days, alerted on before or not
+#[cfg(feature = "mqtt")]
+async fn publish_releasing(settings: &config::MqttConfig, movies: &[&Movie],
+                           genre_map: &HashMap<u32, String>, store: &MovieStore,
+                           options: &RunOptions<'_>, errors: &mut ErrorReport)
+                           -> Result<(), AppError> {
+    let (today, last_day) = (calendar::date_in_days(store.clock(), 0),
+                          calendar::date_in_days(store.clock(), 6));
+
+    let releasing = movies
+        .iter()
+        .filter(|movie| movie.release_date >= today && movie.release_date <= last_day)
+        .map(|movie| digest_movie(movie, genre_map, store, links::tmd_movie_url(movie.id),
+                                  options.language))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Err(e) = mqtt::publish_releasing(settings, &releasing).await {
+        errors.add("publishing the movies releasing this week", e);
+    }
+
+    Ok(())
+}
+
+// alerts on the movies in theaters that were not alerted on as such yet
+async fn now_playing(options: &RunOptions<'_>, config: &Config, watchdog: &mut RunWatchdog)
+                     -> Result<(), AppError> {
+    let paths = options_data_paths(options)?;
+    let mut errors = ErrorReport::default();
+
+    let client = tmd_client_from_config(config)?;
+    let regions = wanted_regions(options, client);
+
+    let genre_map = retrieve_genres(client, watchdog).await?;
+    let wanted_genre_ids = resolve_genre_ids(&options.genres, &genre_map)?;
+    let wanted_genres = describe_genres(&wanted_genre_ids, options.genre_match, &genre_map);
+
+    let mut movies = retrieve_now_playing_movies(client, &regions, watchdog).await?;
+
+    let wanted: HashSet<u32> = get_upcoming_movies_by_filter(&wanted_genre_ids,
+                                                             options.genre_match, &movies)
+        .iter()
+        .map(|movie| movie.id)
+        .collect();
+
+    if options.translation.is_some() {
+        add_translations(movies.iter_mut().filter(|movie| wanted.contains(&movie.id)), options,
+                         client, watchdog, &mut errors).await?;
+    }
+    add_details(movies.iter_mut().filter(|movie| wanted.contains(&movie.id)), options,
+                client, watchdog, &mut errors).await?;
+
+    let matched_movies = get_upcoming_movies_by_filter(&wanted_genre_ids, options.genre_match,
+                                                       &movies);
+
+    let matched_movies = translated_only(matched_movies, options.translation);
+    let matched_movies = wanted_details(matched_movies, options);
+
+    let (store, _) = load_state(&paths)?;
+    record_statuses(movies.iter(), &store, options)?;
+    let matched_movies = listed_within(matched_movies, &store, options.new_within)?;
+    let playing_before = store.playing_ids()?;
+
+    let mut playing = playing_before.clone();
+    playing.extend(store.snoozed_ids()?);
+
+    if options.output == OutputFormat::Cards && !options.quiet {
+        println!("Now playing {} movies: {}", wanted_genres, matched_movies.len());
+    }
+    if options.output == OutputFormat::Alfred && !options.quiet {
+        print_alfred(&matched_movies, &genre_map, &playing)?;
+    }
+
+    let mut dispatcher = Dispatcher::now_playing(notifiers(options, config), &store)
+        .with_settings(&config.notifications);
+
+    process_found_movies(&matched_movies, &genre_map, &mut playing, &store, watchdog, options,
+                         &mut dispatcher, &mut errors).await?;
+    dispatcher.finish(&mut errors).await?;
+
+    if !options.dry_run {
+        commit_new_ids(&paths, config, "Now playing", &playing_before, &store.playing_ids()?,
+                       &mut errors);
+    }
+
+    errors.finish()
+}
+
+// alerts on the movies announced for the `years` after this one that were
+// not alerted on as announced yet, which leaves the alert about them coming
+// up to `check`
+async fn announced(years: u32, options: &RunOptions<'_>, config: &Config,
+                   watchdog: &mut RunWatchdog) -> Result<(), AppError> {
+    let paths = options_data_paths(options)?;
+    let mut errors = ErrorReport::default();
+
+    let client = tmd_client_from_config(config)?;
+
+    let genre_map = retrieve_genres(client, watchdog).await?;
+    let genre_ids = resolve_genre_ids(&options.genres, &genre_map)?;
+    let wanted_genres = describe_genres(&genre_ids, options.genre_match, &genre_map);
+
+    let this_year = calendar::this_year(&SystemClock);
+    let from = format!("{}-01-01", this_year + 1);
+    let to = format!("{}-12-31", this_year + years as i32);
+
+    let filter = DiscoverFilter {
+        genre_ids,
+        genre_match: options.genre_match,
+        company_ids: Vec::new(),
+        keyword_ids: Vec::new(),
+        from: Some(from.clone()),
+        to: Some(to.clone()),
+        year: None,
+        min_vote: None,
+        original_language: None,
+        certification: None,
+        region: options.regions.first().map(|r| r.as_str()).unwrap_or(client.region())
+            .to_owned(),
+    };
+
+    let mut movies = retrieve_filtered_discover_movies(&filter, client, watchdog).await?;
+
+    if options.translation.is_some() {
+        add_translations(movies.iter_mut(), options, client, watchdog, &mut errors).await?;
+    }
+    add_details(movies.iter_mut(), options, client, watchdog, &mut errors).await?;
+
+    let found_movies = translated_only(movies.iter().collect(), options.translation);
+    let found_movies = wanted_details(found_movies, options);
+
+    let (store, _) = load_state(&paths)?;
+    record_statuses(movies.iter(), &store, options)?;
+    let found_movies = listed_within(found_movies, &store, options.new_within)?;
+    let announced_before = store.announced_ids()?;
+
+    let mut announced = announced_before.clone();
+    announced.extend(store.snoozed_ids()?);
+
+    if options.output == OutputFormat::Cards && !options.quiet {
+        println!("Announced {} movies (from {} to {}): {}", wanted_genres, from, to,
+                 found_movies.len());
+    }
+    if options.output == OutputFormat::Alfred && !options.quiet {
+        print_alfred(&found_movies, &genre_map, &announced)?;
+    }
+
+    let mut dispatcher = Dispatcher::announced(notifiers(options, config), &store)
+        .with_settings(&config.notifications);
+
+    process_found_movies(&found_movies, &genre_map, &mut announced, &store, watchdog, options,
+                         &mut dispatcher, &mut errors).await?;
+    dispatcher.finish(&mut errors).await?;
+
+    if !options.dry_run {
+        commit_new_ids(&paths, config, "Announced", &announced_before, &store.announced_ids()?,
+                       &mut errors);
+    }
+
+    errors.finish()
+}
+
+// alerts on the movies found with the filter that were not alerted on yet,
+// the same way check does with the upcoming ones
+async fn discover(mut filter: DiscoverFilter, options: &RunOptions<'_>, config: &Config,
+                  watchdog: &mut RunWatchdog) -> Result<(), AppError> {
+    let paths = options_data_paths(options)?;
+    let mut errors = ErrorReport::default();
+
+    let client = tmd_client_from_config(config)?;
+
+    let genre_map = retrieve_genres(client, watchdog).await?;
+    filter.genre_ids = resolve_genre_ids(&options.genres, &genre_map)?;
+    filter.region = options.regions.first().map(|r| r.as_str()).unwrap_or(client.region())
+        .to_owned();
+    let wanted_genres = describe_genres(&filter.genre_ids, options.genre_match, &genre_map);
+
+    // the genres are filtered on by the api already
+    let mut movies = retrieve_filtered_discover_movies(&filter, client, watchdog).await?;
+
+    if options.translation.is_some() {
+        add_translations(movies.iter_mut(), options, client, watchdog, &mut errors).await?;
+    }
+    add_details(movies.iter_mut(), options, client, watchdog, &mut errors).await?;
+
+    let found_movies = translated_only(movies.iter().collect(), options.translation);
+    let found_movies = wanted_details(found_movies, options);
+
+    let (store, mut opened_movie_set) = load_state(&paths)?;
+    record_statuses(movies.iter(), &store, options)?;
+    let found_movies = listed_within(found_movies, &store, options.new_within)?;
+    let opened_before = store.opened_ids()?;
+    opened_movie_set.extend(store.snoozed_ids()?);
+
+    if options.output == OutputFormat::Cards && !options.quiet {
+        println!("Discovered {} movies: {}", wanted_genres, found_movies.len());
+    }
+    if options.output == OutputFormat::Alfred && !options.quiet {
+        print_alfred(&found_movies, &genre_map, &opened_movie_set)?;
+    }
+
+    let mut dispatcher = Dispatcher::new(notifiers(options, config), &store)
+        .with_settings(&config.notifications);
+
+    process_found_movies(&found_movies, &genre_map, &mut opened_movie_set, &store, watchdog,
+                         options, &mut dispatcher, &mut errors).await?;
+    dispatcher.finish(&mut errors).await?;
+
+    if !options.dry_run {
+        commit_new_ids(&paths, config, "Discover", &opened_before, &store.opened_ids()?,
+                       &mut errors);
+    }
+
+    errors.finish()
+}
+
+// what the config lacks for the alert, "email needs an [email] section"
+fn missing_section(alert: Alert, config: &Config) -> Option<String> {
+    let (missing, needs) = match alert {
+        Alert::Stdout | Alert::Browser | Alert::Desktop | Alert::Both => return None,
+        Alert::Email => (config.email.is_none(), "email needs an [email] section".to_owned()),
+        Alert::Webhook => {
+            (config.webhook.is_none(), "webhook needs a [webhook] section".to_owned())
+        },
+        Alert::Slack => (slack_webhook_url(config).is_none(),
+                         format!("slack needs {} or a [slack] section",
+                                 SLACK_WEBHOOK_URL_ENV_KEY_NAME)),
+        Alert::Discord => (discord_webhook_url(config).is_none(),
+                           format!("discord needs {} or a [discord] section",
+                                   DISCORD_WEBHOOK_URL_ENV_KEY_NAME)),
+        Alert::Ntfy => (config.ntfy.is_none(), "ntfy needs an [ntfy] section".to_owned()),
+        Alert::Gotify => (config.gotify.is_none(), "gotify needs a [gotify] section".to_owned()),
+        Alert::Instapaper => {
+            (config.instapaper.is_none(), "instapaper needs an [instapaper] section".to_owned())
+        },
+        Alert::Wallabag => {
+            (config.wallabag.is_none(), "wallabag needs a [wallabag] section".to_owned())
+        },
+        Alert::Mqtt => (config.mqtt.is_none(), "mqtt needs an [mqtt] section".to_owned()),
+    };
+
+    Some(needs).filter(|_| missing)
+}
+
+// commits the data directory when the config asks for it.  The state itself
+// is saved already, so failing to commit or push does not stop the run.
+fn commit_state(paths: &DataPaths, config: &Config, message: &str, errors: &mut ErrorReport) {
+    if !config.state.git_commit {
+        return;
+    }
+
+    let result = match git::commit_all(&paths.dir, message) {
+        Ok(true) if config.state.git_push => git::push(&paths.dir),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        errors.add("keeping the data directory in git", e);
+    }
+}
+
+// commits the state with the ids that are in `after` but not in `before`,
+// "Check: 2 new movies" and their links
+fn commit_new_ids(paths: &DataPaths, config: &Config, label: &str, before: &HashSet<u32>,
+                  after: &HashSet<u32>, errors: &mut ErrorReport) {
+    let mut new_ids: Vec<&u32> = after.difference(before).collect();
+    new_ids.sort();
+
+    let mut message = format!("{}: {} new movies\n", label, new_ids.len());
+    for id in new_ids.into_iter() {
+        message.push_str(&format!("\n{}", links::tmd_movie_url(*id)));
+    }
+
+    commit_state(paths, config, &message, errors);
+}
+
+#[cfg(feature = "rpc")]
+#[derive(Debug, Serialize)]
+struct RpcMovie {
+    id: u32,
+    title: String,
+    release_date: String,
+    genres: String,
+    url: String,
+    new: bool,
+}
+
+#[cfg(feature = "rpc")]
+async fn serve_rpc(limits: RunLimits, options: &RunOptions<'_>, config: &Config)
+                   -> Result<(), AppError> {
+    rpc::serve(async |method: &str, params: &serde_json::Value| {
+        // limits apply to every request on its own
+        let mut watchdog = RunWatchdog::start(limits);
+
+        let result = match method {
+            "list" => capped(limits, rpc_list(options, config, &mut watchdog)).await,
+            "check" => capped(limits, rpc_check(options, config, &mut watchdog)).await,
+            "markSeen" => {
+                let id = params
+                    .get("id")
+                    .and_then(|id| id.as_u64())
+                    .and_then(|id| u32::try_from(id).ok())
+                    .ok_or(rpc::RpcError::invalid_params("expected {\"id\": <TMD movie id>}"))?;
+
+                rpc_mark_seen(id, options)
+            },
+            _ => return Err(rpc::RpcError::method_not_found(method)),
+        };
+
+        result.map_err(rpc::RpcError::from)
+    }).await
+}
+
+// same movies as the list command
+#[cfg(feature = "rpc")]
+async fn rpc_list(options: &RunOptions<'_>, config: &Config, watchdog: &mut RunWatchdog)
+                  -> Result<serde_json::Value, AppError> {
+    let paths = options_data_paths(options)?;
+    let mut errors = ErrorReport::default();
+
+    let (_, snapshot) = fetch_snapshot(&paths, options, config, watchdog, &mut errors).await?;
+    let (_, opened_movie_set) = load_state(&paths)?;
+
+    let wanted_genre_ids = resolve_genre_ids(&options.genres, &snapshot.genres)?;
+
+    let movies: Vec<RpcMovie> = get_upcoming_movies_by_filter(&wanted_genre_ids,
+                                                              options.genre_match,
+                                                              &snapshot.movies)
+        .into_iter()
+        .map(|movie| {
+            RpcMovie {
+                id: movie.id,
+                title: movie.title.clone(),
+                release_date: movie.release_date.clone(),
+                genres: get_genre_name_from_ids(&movie.genre_ids, &snapshot.genres),
+                url: links::tmd_movie_url(movie.id),
+                new: !opened_movie_set.contains(&movie.id),
+            }
+        })
+        .collect();
+
+    errors.finish()?;
+
+    serde_json::to_value(movies).map_err(AppError::SerdeJsonSerializeError)
+}
+
+// runs a check, the result is the ids of the movies alerted about
+#[cfg(feature = "rpc")]
+async fn rpc_check(options: &RunOptions<'_>, config: &Config, watchdog: &mut RunWatchdog)
+                   -> Result<serde_json::Value, AppError> {
+    if options.offline {
+        return Err(AppError::OfflineError(
+            "check only opens movies found in fresh results".to_owned()));
+    }
+
+    let paths = options_data_paths(options)?;
+    let (_, before) = load_state(&paths)?;
+
+    run(Command::Check, options, config, watchdog).await?;
+
+    let (_, after) = load_state(&paths)?;
+    let mut alerted: Vec<u32> = after.difference(&before).cloned().collect();
+    alerted.sort();
+
+    serde_json::to_value(alerted).map_err(AppError::SerdeJsonSerializeError)
+}
+
+// remembers a movie as opened without opening it
+#[cfg(feature = "rpc")]
+fn rpc_mark_seen(id: u32, options: &RunOptions<'_>) -> Result<serde_json::Value, AppError> {
+    let paths = options_data_paths(options)?;
+    let (store, _) = load_state(&paths)?;
+
+    // stdout is the rpc channel, what would be marked only goes to the log
+    if options.dry_run {
+        info!("Would mark movie {} as seen", id);
+    } else {
+        store.record_alerted(id)?;
+    }
+
+    Ok(serde_json::Value::from(id))
+}
+
+// what --pick can do with the movies not chosen, in this order
+#[cfg(feature = "pick")]
+const PICK_CHOICES: [&str; 3] = [
+    "Leave them for the next run",
+    "Mark them as seen",
+    "Snooze them for a week",
+];
+
+#[cfg(feature = "pick")]
+const SNOOZE_SECS: i64 = 7 * 24 * 60 * 60;
+
+// first retry after a failed api request in watch mode, doubled on every
+// further failure up to the regular interval
+const WATCH_RETRY_SECS: u64 = 60;
+
+// up to a tenth of the interval earlier or later, so that several machines
+// started together do not keep polling the api at the same moment
+fn with_jitter(every: Duration) -> Duration {
+    let spread = every.as_secs() / 5;
+    if spread == 0 {
+        return every;
+    }
+
+    let nanos = SystemClock.now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0);
+
+    every - Duration::from_secs(spread / 2) + Duration::from_secs(nanos % (spread + 1))
+}
+
+async fn watch(every: Duration, limits: RunLimits, options: &RunOptions<'_>, config: &Config)
+               -> Result<(), AppError> {
+    let mut failures: u32 = 0;
+
+    loop {
+        // limits apply to every check on its own
+        let mut watchdog = RunWatchdog::start(limits);
+
+        let check = run(Command::Check, options, config, &mut watchdog);
+
+        let wait = match capped(limits, check).await {
+            Ok(_) => {
+                failures = 0;
+                with_jitter(every)
+            },
+            // reported already, or only this check ran over its limits, and
+            // the next check may well go better
+            Err(e @ AppError::RunIncompleteError(_))
+            | Err(e @ AppError::RunLimitExceededError(_)) => {
+                e.report_error();
+
+                failures = 0;
+                with_jitter(every)
+            },
+            Err(e @ AppError::RestClientError(..)) => {
+                e.report_error();
+
+                failures += 1;
+                let backoff = WATCH_RETRY_SECS.saturating_mul(1 << (failures - 1).min(20));
+                Duration::from_secs(backoff).min(every)
+            },
+            Err(e) => return Err(e),
+        };
+
+        info!("Next check in {} seconds", wait.as_secs());
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// the movie database API key can be obtained from
+// https://developers.themoviedb.org/3/getting-started
+fn read_api_key(config: &Config) -> Result<String, AppError> {
+    match env::var(TMD_API_V3_ENV_KEY_NAME) {
+        Ok(key) => {
+            debug!("API key is found in env.");
+            Ok(key)
+        },
+        Err(e) => match config.api_key_file {
+            Some(ref path) => {
+                debug!("Reading API key from: {:?}", path);
+                let key = fs::read_to_string(path)?;
+                Ok(key.trim().to_owned())
+            },
+            None => Err(AppError::APIKeyError(e)),
+        },
+    }
+}
+
+fn slack_webhook_url(config: &Config) -> Option<String> {
+    env::var(SLACK_WEBHOOK_URL_ENV_KEY_NAME)
+        .ok()
+        .or_else(|| config.slack.as_ref().map(|slack| slack.webhook_url.clone()))
+}
+
+fn discord_webhook_url(config: &Config) -> Option<String> {
+    env::var(DISCORD_WEBHOOK_URL_ENV_KEY_NAME)
+        .ok()
+        .or_else(|| config.discord.as_ref().map(|discord| discord.webhook_url.clone()))
+}
+
+// the client is set up once, so that every run of a watch or rpc process
+// shares its genres and its pace of requests
+fn tmd_client_from_config(config: &Config) -> Result<&'static TmdClient, AppError> {
+    if let Some(client) = TMD_CLIENT.get() {
+        return Ok(client);
+    }
+
+    let client = read_api_key(config).map(|key| new_tmd_client(key, config))?;
+
+    Ok(TMD_CLIENT.get_or_init(|| client))
+}
+
+fn show_config(config_path: &Path, config: &Config) -> Result<(), AppError> {
+    let paths = data_paths()?;
+
+    let config_state = if config_path.exists() { "loaded" } else { "not found" };
+    println!("Config file: {} ({})", config_path.display(), config_state);
+
+    let key_state = match (env::var(TMD_API_V3_ENV_KEY_NAME), &config.api_key_file) {
+        (Ok(_), _) => format!("set in {}", TMD_API_V3_ENV_KEY_NAME),
+        (Err(_), Some(path)) => format!("read from {}", path.display()),
+        (Err(_), &None) => "not set".to_owned(),
+    };
+    println!("TMD api key: {}", key_state);
+
+    let base_urls_source = match env::var(TMD_API_BASE_URL_ENV_KEY_NAME) {
+        Ok(_) => TMD_API_BASE_URL_ENV_KEY_NAME,
+        Err(_) if !config.api_base_urls.is_empty() => "config file",
+        Err(_) => "default",
+    };
+    let client = new_tmd_client(String::new(), config);
+    println!("TMD api base urls ({}): {}", base_urls_source, client.base_urls().join(", "));
+    println!("TMD api language: {}", client.language());
+    println!("TMD api region: {}", client.region());
+
+    if config.genres.is_empty() {
+        println!("Genres (default): {}", KnownGenre::Animation);
+    } else {
+        println!("Genres (config): {}", config.genres.join(", "));
+    }
+    if config.companies.is_empty() {
+        println!("Watched companies: none");
+    } else {
+        println!("Watched companies (config): {}", config.companies.join(", "));
+    }
+    let ids = |ids: &[u32]| ids.iter().map(|id| id.to_string()).collect::<Vec<String>>();
+    for (what, excluded) in [("companies", &config.excluded_companies),
+                             ("keywords", &config.excluded_keywords),
+                             ("people", &config.excluded_people)] {
+        if !excluded.is_empty() {
+            println!("Excluded {} (config): {}", what, ids(excluded).join(", "));
+        }
+    }
+
+    println!("Browser: {}",
+             config.browser.as_deref().unwrap_or("system default"));
+    if config.notifications.alert.is_empty() {
+        println!("Alert (default): {:?}", Alert::Browser);
+    } else {
+        println!("Alert (config): {:?}", config.notifications.alert);
+    }
+    println!("Upcoming window extended by: {} months", config.extend_months.unwrap_or(0));
+    println!("Git history of data directory: {}",
+             match (config.state.git_commit, config.state.git_push) {
+                 (false, _) => "off",
+                 (true, false) => "commit",
+                 (true, true) => "commit and push",
+             });
+
+    println!("Store: {}", paths.store.display());
+    println!("Watched lists file: {}", paths.lists.display());
+    println!("Offline data file: {}", paths.snapshot.display());
+
+    Ok(())
+}
+
+fn change_state(action: StateAction, options: &RunOptions<'_>, config: &Config)
+                -> Result<(), AppError> {
+    let paths = options_data_paths(options)?;
+    let (mut store, opened_movie_set) = load_state(&paths)?;
+    let mut errors = ErrorReport::default();
+
+    match action {
+        StateAction::Show => {
+            let mut ids: Vec<&u32> = opened_movie_set.iter().collect();
+            ids.sort();
+
+            println!("Opened movies: {}", ids.len());
+            for id in ids.into_iter() {
+                println!("{}  {}", id, links::tmd_movie_url(*id));
+            }
+        },
+        StateAction::Forget { id } if options.dry_run => {
+            if opened_movie_set.contains(&id) {
+                println!("Would forget movie {}", id);
+            } else {
+                println!("Movie {} was not opened", id);
+            }
+        },
+        StateAction::Forget { id } => {
+            if store.forget(id)? {
+                println!("Forgot movie {}", id);
+                commit_state(&paths, config, &format!("Forget movie {}", id), &mut errors);
+            } else {
+                println!("Movie {} was not opened", id);
+            }
+        },
+        StateAction::Reset if options.dry_run => {
+            println!("Would forget {} opened movies", opened_movie_set.len());
+        },
+        StateAction::Reset => {
+            let forgotten = store.reset()?;
+            println!("Forgot {} opened movies", forgotten);
+            if forgotten > 0 {
+                commit_state(&paths, config, &format!("Forget all {} opened movies", forgotten),
+                             &mut errors);
+            }
+        },
+    }
 
-use std::iter::Iterator;
-use std::env;
-use std::path::PathBuf;
-use std::fs::File;
-use std::io::Write;
-use std::collections::{HashMap, HashSet};
-use tokio_core::reactor::Core;
-use roadrunner::RestClient;
-use roadrunner::RestClientMethods;
-
-const TMD_API_MOVIE_GENRES_URL: &str = "https://api.themoviedb.org/3/genre/movie/list";
-const TMD_API_MOVIE_UPCOMING_URL: &str = "https://api.themoviedb.org/3/movie/upcoming";
-const TMD_MOVIE_URL_BASE: &str = "https://www.themoviedb.org/movie";
+    errors.finish()
+}
 
-const TMD_API_V3_ENV_KEY_NAME: &str = "TMD_API_V3";
-const TMD_API_KEY_QUERY_PARAM_NAME: &str = "api_key";
+fn undo(config: &Config) -> Result<(), AppError> {
+    let paths = data_paths()?;
+    let (mut store, _) = load_state(&paths)?;
+    let mut errors = ErrorReport::default();
+
+    match store.undo()? {
+        Some(command) => {
+            println!("Undid {}", command);
+            commit_state(&paths, config, &format!("Undo {}", command), &mut errors);
+        },
+        None => println!("Nothing to undo"),
+    }
+
+    errors.finish()
+}
 
-// data file will be in ~/.movie_alert
-const DATA_FILE_PATH: &str = ".movie_alert";
+// "Dune (2021)"
+fn with_year(title: &str, year: Option<i32>) -> String {
+    match year {
+        Some(year) => format!("{} ({})", title, year),
+        None => title.to_owned(),
+    }
+}
 
-#[cfg(target_os = "macos")]
-const BROWSER_OPEN_CMD: &str = "open";
+// asks on the terminal whether a match that is not sure is the title, no
+// when there is no terminal to ask on
+fn confirm_match(title: &ExportedTitle, found: &TitleMatch) -> Result<bool, AppError> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Ok(false);
+    }
 
-#[cfg(target_os = "linux")]
-const BROWSER_OPEN_CMD: &str = "xdg-open";
+    print!("Is {} the movie {}? [y/N] ", with_year(&title.title, title.year),
+           with_year(&found.title, found.year));
+    std::io::stdout().flush()?;
 
-#[cfg(target_os = "windows")]
-const BROWSER_OPEN_CMD: &str = "start";
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-struct GenreReponse {
-    genres: Vec<Genre>,
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-struct Genre {
-    id: u32,
-    name: String,
+// takes the titles of a TMDb account export as seen and puts them in the
+// group, looking up the ones without an id by title and year
+async fn import_export(file: &Path, group: &str, options: &RunOptions<'_>, config: &Config,
+                       watchdog: &mut RunWatchdog) -> Result<(), AppError> {
+    let paths = options_data_paths(options)?;
+    let mut errors = ErrorReport::default();
+
+    let text = fs::read_to_string(file)?;
+    let titles = import::parse_export(&text)
+        .map_err(|msg| AppError::ImportError(file.display().to_string(), msg))?;
+
+    let mut ids = BTreeSet::new();
+    for title in titles.iter() {
+        let found = match title.id {
+            Some(id) => Some(id),
+            None if options.offline => {
+                errors.add("looking up an exported title", AppError::OfflineError(
+                    format!("{} has no TMDb id to go by", title.title)));
+                continue;
+            },
+            None => {
+                let client = tmd_client_from_config(config)?;
+
+                match search_movie(&title.title, title.year, client, watchdog).await {
+                    Ok(Some(found)) if found.confidence == Confidence::High => Some(found.id),
+                    Ok(Some(found)) if found.confidence == Confidence::Medium => {
+                        if confirm_match(title, &found)? {
+                            Some(found.id)
+                        } else {
+                            errors.add("looking up an exported title", AppError::ImportError(
+                                file.display().to_string(),
+                                format!("{} is only maybe {}, {:.0}% alike",
+                                        with_year(&title.title, title.year),
+                                        with_year(&found.title, found.year),
+                                        found.score * 100.0)));
+                            continue;
+                        }
+                    },
+                    Ok(_) => None,
+                    Err(e @ AppError::RestClientError(..)) => {
+                        errors.add("looking up an exported title", e);
+                        continue;
+                    },
+                    Err(e) => return Err(e),
+                }
+            },
+        };
+
+        match found {
+            Some(id) => {
+                debug!("Importing {} as {}", title.title, id);
+                ids.insert(id);
+            },
+            None => {
+                errors.add("looking up an exported title", AppError::ArgumentError(
+                    format!("no TMD movie is called {}", title.title)));
+            },
+        }
+    }
+
+    let (mut store, opened_before) = load_state(&paths)?;
+    let new = ids.iter().filter(|id| !opened_before.contains(id)).count();
+
+    if options.dry_run {
+        println!("Would import {} of {} titles into {}, {} of them not seen before", ids.len(),
+                 titles.len(), group, new);
+    } else {
+        store.import_opened(&ids.iter().cloned().collect())?;
+        store.add_to_group(group, &ids.iter().cloned().collect::<Vec<u32>>())?;
+
+        println!("Imported {} of {} titles into {}, {} of them not seen before", ids.len(),
+                 titles.len(), group, new);
+    }
+
+    if !options.dry_run && !ids.is_empty() {
+        commit_state(&paths, config, &format!("Import {} titles from {}", ids.len(),
+                                              file.display()), &mut errors);
+    }
+
+    errors.finish()
+}
+
+fn change_cache(action: CacheAction, config: &Config) -> Result<(), AppError> {
+    let cache = PosterCache::open(config.cache.max_mb.unwrap_or(DEFAULT_MAX_MB))?;
+    // the ttl does not matter to counting and removing
+    let details = ResponseCache::details(Duration::ZERO)?;
+    let translations = ResponseCache::translations(Duration::ZERO)?;
+
+    match action {
+        CacheAction::Stats => {
+            let stats = cache.stats()?;
+            println!("Poster cache: {} posters, {:.1} of {} MB, in {}", stats.files,
+                     stats.bytes as f64 / (1024.0 * 1024.0),
+                     config.cache.max_mb.unwrap_or(DEFAULT_MAX_MB), cache.dir().display());
+            println!("Movie cache: details of {} and translations of {} movies",
+                     details.count()?, translations.count()?);
+        },
+        CacheAction::Clear => {
+            println!("Removed {} posters from the cache", cache.clear()?);
+            println!("Removed the details of {} and translations of {} movies from the cache",
+                     details.clear()?, translations.clear()?);
+        },
+    }
+
+    Ok(())
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-struct UpComingMovieResponse {
-    page: u32,
-    results: Vec<Movie>,
-    dates: Dates,
-    total_pages: u32,
-    total_results: u32,
+fn change_group(action: GroupAction, config: &Config) -> Result<(), AppError> {
+    let paths = data_paths()?;
+    let (mut store, _) = load_state(&paths)?;
+    let mut errors = ErrorReport::default();
+
+    match action {
+        GroupAction::Add { name, ids } => {
+            let added = store.add_to_group(&name, &ids)?;
+            println!("Added {} movies to {}", added, name);
+            if added > 0 {
+                commit_state(&paths, config, &format!("Add {} movies to group {}", added, name),
+                             &mut errors);
+            }
+        },
+        GroupAction::Remove { name, ids } => {
+            let removed = store.remove_from_group(&name, &ids)?;
+            println!("Removed {} movies from {}", removed, name);
+            if removed > 0 {
+                commit_state(&paths, config,
+                             &format!("Remove {} movies from group {}", removed, name),
+                             &mut errors);
+            }
+        },
+        GroupAction::Show => {
+            for (name, ids) in store.groups()?.iter() {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                println!("{}: {}", name, ids.join(", "));
+            }
+        },
+    }
+
+    errors.finish()
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-struct Movie {
-    poster_path: Option<String>,
-    adult: bool,
-    overview: String,
-    release_date: String,
-    genre_ids: Vec<u32>,
-    id: u32,
-    title: String,
+fn show_weights() -> Result<(), AppError> {
+    let paths = data_paths()?;
+    let (store, _) = load_state(&paths)?;
+
+    let genre_weights = weights::genre_weights(&store)?;
+
+    if genre_weights.is_empty() {
+        println!("Nothing alerted yet, every genre is worth {:.2}", weights::PRIOR_WEIGHT);
+        return Ok(());
+    }
+
+    println!("{:<20} {:>8} {:>8} {:>8}", "Genre", "Alerted", "Opened", "Weight");
+    for weight in genre_weights.iter() {
+        println!("{:<20} {:>8} {:>8} {:>8.2}", weight.genre, weight.alerted, weight.opened,
+                 weight.weight());
+    }
+
+    Ok(())
+}
+
+fn show_suggestions(options: &RunOptions<'_>) -> Result<(), AppError> {
+    let paths = data_paths()?;
+    let (store, _) = load_state(&paths)?;
+
+    let watched = if options.genres.is_empty() {
+        vec![KnownGenre::Animation.name().to_owned()]
+    } else {
+        options.genres.clone()
+    };
+
+    let opened = store.user_opened_ids()?;
+    let movies: Vec<(String, bool)> = store.alerted_genres()?
+        .into_iter()
+        .map(|(id, genres)| (genres, opened.contains(&id)))
+        .collect();
+
+    let suggestions = suggest::suggest(&watched, &movies);
+
+    if suggestions.is_empty() {
+        println!("Nothing to suggest from {} alerted movies, {} of them opened", movies.len(),
+                 movies.iter().filter(|&&(_, opened)| opened).count());
+    }
+    for suggestion in suggestions.iter() {
+        println!("{}", suggestion);
+    }
+
+    Ok(())
+}
+
+fn mark_watched(id: u32, date: Option<&str>, rating: Option<u8>, config: &Config)
+                -> Result<(), AppError> {
+    let paths = data_paths()?;
+    let (store, _) = load_state(&paths)?;
+    let mut errors = ErrorReport::default();
+
+    let watched = store.record_watched(id, date, rating)?;
+    println!("Watched movie {} on {}", id, watched);
+
+    commit_state(&paths, config, &format!("Watch movie {} on {}", id, watched), &mut errors);
+
+    errors.finish()
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-struct Dates {
-    maximum: String,
-    minimum: String,
+fn show_recap(year: i32) -> Result<(), AppError> {
+    let paths = data_paths()?;
+    let (store, _) = load_state(&paths)?;
+
+    print!("{}", recap::render(year, &store.watched_in(year)?));
+
+    Ok(())
 }
 
+fn open_movie(id: u32, config: &Config, watchdog: &mut RunWatchdog) -> Result<(), AppError> {
+    let paths = data_paths()?;
+    let (store, _) = load_state(&paths)?;
+    let mut errors = ErrorReport::default();
+
+    watchdog.notification()?;
+    let opened = links::open_in_browser(&links::tmd_movie_url(id), config.browser.as_deref())
+        .map(|_| None);
+
+    if notifier::record_delivery(&store, &[id], "browser", opened, &mut errors)? {
+        store.record_alerted(id)?;
+        store.record_open(id)?;
+        commit_state(&paths, config, &format!("Open movie {}", id), &mut errors);
+    }
 
-#[derive(Debug)]
-enum AppError {
-    APIKeyError(std::env::VarError),
-    RestClientError(String, roadrunner::Error),
-    GenreIdNotFoundError(String),
-    HomeDirectoryError,
-    SerdeJsonSerializeError(serde_json::Error),
-    SerdeJsonDeserializeError(serde_json::Error),
-    IOError(std::io::Error),
-    EnvLogError(log::SetLoggerError),
-    ReactorInitializeError(std::io::Error),
+    errors.finish()
 }
 
-impl std::convert::From<std::io::Error> for AppError {
-    fn from(s: std::io::Error) -> Self {
-        AppError::IOError(s)
+fn print_version(build_info: bool) {
+    println!("movie_alert {}", env!("CARGO_PKG_VERSION"));
+
+    if build_info {
+        println!("git hash: {}", env!("MOVIE_ALERT_GIT_HASH"));
+        println!("target: {}", env!("MOVIE_ALERT_TARGET"));
+        println!("profile: {}", env!("MOVIE_ALERT_PROFILE"));
     }
 }
 
-impl AppError {
-    fn report_error(self) {
-        match self {
-            AppError::APIKeyError(cause) => {
-                error!("Error: TMD API key TMD_API_V3 is not set in env!");
-                error!("    TMD API key can be obtained at https://developers.themoviedb.org/3/getting-started");
-                error!("    {}", cause);
-            },
-            AppError::RestClientError(msg, cause) => {
-                error!("{}", msg);
-                error!("    {}", cause);
-            },
-            AppError::GenreIdNotFoundError(name) => {
-                error!("Error: id cannot be found for genre name: {}", name);
-            },
-            AppError::HomeDirectoryError => {
-                error!("Error: home directory cannot be located.")
-            },
-            AppError::SerdeJsonSerializeError(cause) => {
-                error!("Error: cannot save to data file");
-                error!("    {}", cause);
+fn print_stage(name: &str, started: Instant, detail: &str) {
+    let elapsed = started.elapsed();
+    let millis = elapsed.as_secs() * 1000 + elapsed.subsec_millis() as u64;
+
+    println!("  {:<18} {:>6} ms  ({})", name, millis, detail);
+}
+
+// a directory of the smoke test, removed again however the test ends
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn create(dir: PathBuf) -> Result<ScratchDir, AppError> {
+        fs::create_dir_all(&dir)?;
+
+        Ok(ScratchDir(dir))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.0) {
+            warn!("Cannot remove {}: {}", self.0.display(), e);
+        }
+    }
+}
+
+async fn smoke_test(options: &RunOptions<'_>, config: &Config, watchdog: &mut RunWatchdog)
+                    -> Result<(), AppError> {
+    let client = tmd_client_from_config(config)?;
+
+    println!("Smoke test:");
+
+    let started = Instant::now();
+    watchdog.api_call()?;
+    let genre_id_to_name = retrieve_genre_and_convert_to_map(client).await?;
+    print_stage("genres", started, &format!("{} genres", genre_id_to_name.len()));
+
+    let started = Instant::now();
+    let upcoming = retrieve_upcoming_movies_by_page(1, client.region(), client,
+                                                    watchdog).await?;
+    print_stage("upcoming page 1", started,
+                &format!("{} of {} movies", upcoming.results.len(), upcoming.total_results));
+
+    let started = Instant::now();
+    let wanted_genre_ids = resolve_genre_ids(&options.genres, &genre_id_to_name)?;
+    let matched_movies = get_upcoming_movies_by_filter(&wanted_genre_ids, options.genre_match,
+                                                       &upcoming.results);
+    print_stage("filter", started, &format!("{} matching movies", matched_movies.len()));
+
+    let started = Instant::now();
+    let state_dir = ScratchDir::create(
+        env::temp_dir().join(format!("movie_alert_smoke_{}", std::process::id())))?;
+
+    let store_path = state_dir.0.join(STORE_FILE_NAME);
+
+    {
+        let store = MovieStore::open(&store_path)?;
+        for movie in matched_movies.iter() {
+            let genre_names = get_genre_name_from_ids(&movie.genre_ids, &genre_id_to_name);
+            store.record_seen(movie.id, &movie.title, &movie.release_date, &genre_names)?;
+            store.record_alerted(movie.id)?;
+        }
+    }
+
+    let reloaded = MovieStore::open(&store_path)?.opened_ids()?;
+
+    drop(state_dir);
+    print_stage("state", started, &format!("{} ids saved and reloaded", reloaded.len()));
+
+    println!("OK");
+
+    Ok(())
+}
+
+// "CA 2025-07-25, US 2025-07-18", only for movies found in more than one
+// region
+fn region_release_dates_label(movie: &Movie) -> Option<String> {
+    if movie.region_release_dates.len() < 2 {
+        return None;
+    }
+
+    let labels: Vec<String> = movie.region_release_dates
+        .iter()
+        .map(|(region, date)| format!("{} {}", region, release_date_label(date)))
+        .collect();
+
+    Some(labels.join(", "))
+}
+
+// the cache of the responses, none when nothing is to be cached or the
+// cache directory cannot be had
+fn response_cache(open: fn(Duration) -> Result<ResponseCache, AppError>,
+                  options: &RunOptions<'_>) -> Option<ResponseCache> {
+    if options.details_ttl.is_zero() {
+        return None;
+    }
+
+    open(options.details_ttl)
+        .map_err(|e| warn!("Looking up movies without a cache: {}", e))
+        .ok()
+}
+
+// the responses about the movies by id, out of the cache where they are in
+// it, and else asked for with `retrieve` and kept in the cache
+async fn look_up<T, S>(ids: Vec<u32>, cache: Option<&ResponseCache>,
+                       retrieve: impl FnOnce(Vec<u32>) -> S)
+                       -> HashMap<u32, Result<T, AppError>>
+    where T: serde::Serialize + serde::de::DeserializeOwned,
+          S: Stream<Item = (u32, Result<T, AppError>)>,
+{
+    let mut found = HashMap::new();
+    let mut missing = Vec::new();
+
+    for id in ids {
+        match cache.and_then(|cache| cache.get(id)) {
+            Some(response) => {
+                debug!("Taking movie {} from the cache", id);
+                found.insert(id, Ok(response));
             },
-            AppError::SerdeJsonDeserializeError(cause) => {
-                error!("Error: cannot load from data file");
-                error!("    {}", cause);
+            None => missing.push(id),
+        }
+    }
+
+    let mut retrieved = pin!(retrieve(missing));
+    while let Some((id, response)) = retrieved.next().await {
+        if let (Some(cache), Ok(response)) = (cache, response.as_ref()) {
+            if let Err(e) = cache.put(id, response) {
+                warn!("Cannot cache movie {}: {}", id, e);
             }
-            AppError::IOError(cause) => {
-                error!("Error: IO error");
-                error!("    {}", cause);
-            },
-            AppError::EnvLogError(cause) => {
-                error!("Error: cannot initialize env log:");
-                error!("    {}", cause);
+        }
+
+        found.insert(id, response);
+    }
+
+    found
+}
+
+// notes on the movies whether they are translated into the api language,
+// a movie whose translations cannot be got is left as not known
+async fn add_translations<'m>(movies: impl Iterator<Item = &'m mut Movie>,
+                              options: &RunOptions<'_>, client: &TmdClient,
+                              watchdog: &mut RunWatchdog, errors: &mut ErrorReport)
+                              -> Result<(), AppError> {
+    let cache = response_cache(ResponseCache::translations, options);
+    let movies: Vec<&mut Movie> = movies.collect();
+    let ids = movies.iter().map(|movie| movie.id).collect();
+
+    let mut found = look_up(ids, cache.as_ref(), |ids| {
+        retrieve_translations(ids, options.lookup_concurrency, client, watchdog)
+    }).await;
+
+    for movie in movies {
+        match found.remove(&movie.id) {
+            Some(Ok(translations)) => {
+                movie.translated = Some(has_translation(&translations, client.language()));
             },
-            AppError::ReactorInitializeError(cause) => {
-                error!("Error: cannot initialize reactor Core:");
-                error!("    {}", cause);
+            Some(Err(e @ AppError::RestClientError(..))) => {
+                errors.add("getting the translations of a movie", e);
             },
+            Some(Err(e)) => return Err(e),
+            // the same movie twice
+            None => {},
         }
     }
+
+    Ok(())
 }
 
-fn main() {
+// with `--translation only` the movies not known to be untranslated, so
+// that one failed lookup does not hide a movie
+fn translated_only(movies: Vec<&Movie>, check: Option<TranslationCheck>) -> Vec<&Movie> {
+    if check != Some(TranslationCheck::Only) {
+        return movies;
+    }
 
-    ::std::process::exit(match process() {
-        Ok(_) => 0,
-        Err(e) => {
-            e.report_error();
-            1
-        },
-    });
+    movies.into_iter().filter(|movie| movie.translated != Some(false)).collect()
 }
 
-fn process() -> Result<(), AppError> {
-
-    let mut core = try!(Core::new().map_err(AppError::ReactorInitializeError));
+// whether --status or the exclusions need the details of every movie
+fn wants_details(options: &RunOptions<'_>) -> bool {
+    !options.statuses.is_empty() || !options.exclusions.is_empty()
+}
 
-    // make it possible to see logs by:
-    //          RUST_LOG="movie_alert=debug" cargo run
-    //          RUST_LOG="movie_alert" cargo run
-    env_logger::init()
-        .map_err(AppError::EnvLogError)
-        .and_then( |_| {
-            // need home directory to save the data file (to keep track of
-            // which movie is opened in browser).
-            env::home_dir()
-                .ok_or(AppError::HomeDirectoryError)
-        }).and_then(|home| {
-            // the movie database API key can be obtained from
-            // https://developers.themoviedb.org/3/getting-started
-            env::var(TMD_API_V3_ENV_KEY_NAME)
-                .map_err(AppError::APIKeyError)
-                .map(|key| (home, key))
-        }).and_then(|(home, key)| {
-            debug!("API key is found in env.");
+// notes on the movies their production status and whether a company,
+// keyword or person of theirs is excluded, without asking when nothing needs
+// them
+async fn add_details<'m>(movies: impl Iterator<Item = &'m mut Movie>,
+                         options: &RunOptions<'_>, client: &TmdClient,
+                         watchdog: &mut RunWatchdog, errors: &mut ErrorReport)
+                         -> Result<(), AppError> {
+    if !wants_details(options) {
+        return Ok(());
+    }
 
-            Ok((retrieve_genre_and_convert_to_map(&key, &mut core), key, home))
-        }).and_then(move |(genre_id_to_name, key, home)| {
+    let cache = response_cache(ResponseCache::details, options);
+    let movies: Vec<&mut Movie> = movies.collect();
+    let ids = movies.iter().map(|movie| movie.id).collect();
 
-            let genre_animation_id: u32 = try!(get_genre_id_by_name("Animation", &genre_id_to_name));
-            debug!("Animation genre id is: {}", genre_animation_id);
+    let mut found = look_up(ids, cache.as_ref(), |ids| {
+        retrieve_details(ids, options.lookup_concurrency, client, watchdog)
+    }).await;
 
-            let (upcoming_movies, min_date, max_date) = retrieve_all_upcoming_movies(&key, &mut core).unwrap();
+    for movie in movies {
+        match found.remove(&movie.id) {
+            Some(Ok(details)) => {
+                movie.excluded = Some(is_excluded(&details, &options.exclusions));
+                movie.status = details.status;
+                movie.runtime = details.runtime;
+            },
+            Some(Err(e @ AppError::RestClientError(..))) => {
+                errors.add("getting the details of a movie", e);
+            },
+            Some(Err(e)) => return Err(e),
+            None => {},
+        }
+    }
 
-            trace!("All upcoming movies: {:?}", upcoming_movies);
-            debug!("Total # of upcoming movies: {}", upcoming_movies.len());
+    Ok(())
+}
 
-            let animation_movies = get_upcoming_movies_by_genre_id(genre_animation_id,
-                    &upcoming_movies);
+// the movies known not to be excluded and in one of the --status statuses,
+// all of them when neither is asked for: one whose details could not be got
+// is left for a later run to alert on, as an alert cannot be taken back
+fn wanted_details<'m>(movies: Vec<&'m Movie>, options: &RunOptions<'_>) -> Vec<&'m Movie> {
+    let wanted = |movie: &Movie| {
+        (options.exclusions.is_empty() || movie.excluded == Some(false))
+            && (options.statuses.is_empty()
+                || movie.status.as_ref().is_some_and(|status| {
+                    options.statuses.iter().any(|wanted| wanted.eq_ignore_ascii_case(status))
+                }))
+    };
 
-            println!("Upcoming animation movies (from {} to {}): {}", min_date, max_date,
-                     animation_movies.len());
+    movies.into_iter().filter(|movie| wanted(movie)).collect()
+}
 
-            let mut data_path: PathBuf = PathBuf::from(home);
-            data_path.push(DATA_FILE_PATH);
-            let data_path = data_path;
-            debug!("Data file path is: {:?}", data_path);
+// remembers the production status of the movies that were looked up, and
+// tells which of them changed since
+fn record_statuses<'m>(movies: impl Iterator<Item = &'m Movie>, store: &MovieStore,
+                       options: &RunOptions<'_>) -> Result<(), AppError> {
+    if options.dry_run {
+        return Ok(());
+    }
 
-            let mut opened_movie_set: HashSet<u32> = try!(load_opened_movie_set(&data_path));
+    for movie in movies {
+        if let Some(ref status) = movie.status {
+            let previous = store.record_status(movie.id, status)?;
 
-            process_found_movies(&animation_movies, &genre_id_to_name, &mut opened_movie_set);
+            if let Some(previous) = previous {
+                if options.output == OutputFormat::Cards && !options.quiet {
+                    println!("Status of {} changed: {} -> {}", movie.title, previous, status);
+                }
+            }
+        }
+    }
 
-            let _ = try!(save_opened_movie_set(&opened_movie_set, &data_path));
+    Ok(())
+}
 
-            Ok(())
-        })
+// the command line values, the config file ones when there are none
+fn or_config<T: Clone>(values: Vec<T>, config_values: &[T]) -> Vec<T> {
+    if values.is_empty() {
+        config_values.to_vec()
+    } else {
+        values
+    }
 }
 
-fn load_opened_movie_set(path: &PathBuf) -> Result<HashSet<u32>, AppError> {
-    if path.is_file() && path.exists() {
-        let file = try!(File::open(path));
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+// "today", "1 day ago", "45 days ago", for a movie first seen in the
+// results `listed` seconds ago, or never before
+fn listed_label(listed: Option<i64>) -> String {
+    match listed.unwrap_or(0).max(0) / SECS_PER_DAY {
+        0 => "today".to_owned(),
+        1 => "1 day ago".to_owned(),
+        days => format!("{} days ago", days),
+    }
+}
 
-        debug!("Data file found, loading...");
+// the movies first seen in the results within `within`, all of them without
+// it.  A movie never seen before is as fresh as it gets.
+fn listed_within<'m>(movies: Vec<&'m Movie>, store: &MovieStore, within: Option<Duration>)
+                     -> Result<Vec<&'m Movie>, AppError> {
+    let within = match within {
+        Some(within) => within.as_secs() as i64,
+        None => return Ok(movies),
+    };
 
-        serde_json::from_reader::<_,HashSet<u32>>(file)
-            .map_err(|e| AppError::SerdeJsonDeserializeError(e))
-    } else {
-        debug!("Data file does not exist");
-        Ok(HashSet::new())
+    let mut fresh = Vec::new();
+    for movie in movies.into_iter() {
+        if store.listed_secs(movie.id)?.unwrap_or(0) <= within {
+            fresh.push(movie);
+        }
     }
+
+    Ok(fresh)
 }
 
-fn save_opened_movie_set(opened_set: &HashSet<u32>, path: &PathBuf) -> Result<(), AppError> {
-    let mut file = try!(File::create(path));
+fn print_movie(movie: &Movie, genre_map: &HashMap<u32, String>, is_new: bool,
+               listed: Option<i64>, output: OutputFormat, overview_length: OverviewLength,
+               language: &str) {
+    let genre_names = get_genre_name_from_ids(&movie.genre_ids, genre_map);
+
+    let url = links::tmd_movie_url(movie.id);
+
+    match output {
+        OutputFormat::Cards => {
+            println!("***");
+            println!("Title: {}", movie.title);
+            println!("Id: {}", movie.id);
+            println!("Genres: {}", genre_names);
+            println!("Release date: {}", release_date_label(&movie.release_date));
+            if let Some(dates) = region_release_dates_label(movie) {
+                println!("Release dates: {}", dates);
+            }
+            println!("URL: {}", url);
+            println!("Listed: {}", listed_label(listed));
+            if let Some(translated) = movie.translated {
+                println!("Translated: {}", if translated { "yes" } else { "no" });
+            }
+            if let Some(ref status) = movie.status {
+                println!("Status: {}", status);
+            }
+            if let Some(rating) = locale::format_rating(movie.vote_average, movie.vote_count,
+                                                        language) {
+                println!("Rating: {}", rating);
+            }
+            if let Some(runtime) = movie.runtime.filter(|&minutes| minutes > 0) {
+                println!("Runtime: {}", locale::format_runtime(runtime, language));
+            }
+
+            if let Some(overview) = overview::summarize(&movie.overview, overview_length) {
+                println!("Overview: {}", overview);
+            }
 
-    let _  = try!(serde_json::to_writer(&file, opened_set)
-        .map_err(|e| AppError::SerdeJsonSerializeError(e)));
+            if !is_new {
+                println!("URL was opened")
+            }
+        },
+        OutputFormat::Compact => {
+            let release_date = if calendar::is_date_tbd(&movie.release_date) {
+                "date TBD"
+            } else {
+                movie.release_date.as_str()
+            };
 
-    debug!("Saving data file");
+            let translated = match movie.translated {
+                Some(true) => "  [translated]",
+                Some(false) => "  [not translated]",
+                None => "",
+            };
+            let rated = match movie.vote_count {
+                0 => String::new(),
+                _ => format!("  rated {}", locale::format_decimal(movie.vote_average, language)),
+            };
 
-    file.flush().map_err(|e| AppError::IOError(e))
+            println!("\u{1F3AC} {}  {} ({})  {}  listed {}{}{}{}", release_date, movie.title,
+                     genre_names, url, listed_label(listed), rated, translated,
+                     if is_new { "  [NEW]" } else { "" });
+        },
+        // printed for all movies at once by print_alfred
+        OutputFormat::Alfred => {},
+    }
 }
 
-fn process_found_movies(movies: &[&Movie], genre_map: &HashMap<u32, String>,
-                        opened_movie_set: &mut HashSet<u32>) {
-    for movie in movies.iter() {
-        let genre_names = get_genre_name_from_ids(&movie.genre_ids, &genre_map);
+fn print_alfred(movies: &[&Movie], genre_map: &HashMap<u32, String>,
+                opened_movie_set: &HashSet<u32>) -> Result<(), AppError> {
+    let items: Vec<alfred::AlfredItem> = movies
+        .iter()
+        .map(|movie| {
+            let is_new = !opened_movie_set.contains(&movie.id);
+
+            alfred::AlfredItem {
+                uid: movie.id.to_string(),
+                title: movie.title.clone(),
+                subtitle: format!("{}  {}{}", release_date_label(&movie.release_date),
+                                  get_genre_name_from_ids(&movie.genre_ids, genre_map),
+                                  if is_new { "  [NEW]" } else { "" }),
+                arg: links::tmd_movie_url(movie.id),
+                icon: movie.poster_path
+                    .as_ref()
+                    .map(|p| alfred::AlfredIcon { path: links::tmd_poster_url(p) }),
+            }
+        })
+        .collect();
 
-        let url = TMD_MOVIE_URL_BASE.to_owned() + "/" + &movie.id.to_string();
+    println!("{}", alfred::render(&items).map_err(AppError::SerdeJsonSerializeError)?);
 
-        println!("***");
-        println!("Title: {}", movie.title);
-        println!("Genres: {}", genre_names);
-        println!("Release date: {}", movie.release_date);
-        println!("URL: {}", url);
+    Ok(())
+}
 
-        if opened_movie_set.contains(&movie.id) {
-            println!("URL was opened")
-        } else {
-            let _ = std::process::Command::new(BROWSER_OPEN_CMD)
-                .arg(url)
-                .stdout(std::process::Stdio::inherit())
-                .spawn();
+#[allow(clippy::too_many_arguments)]
+async fn process_found_movies(movies: &[&Movie], genre_map: &HashMap<u32, String>,
+                              opened_movie_set: &mut HashSet<u32>, store: &MovieStore,
+                              watchdog: &mut RunWatchdog, options: &RunOptions<'_>,
+                              dispatcher: &mut Dispatcher<'_>, errors: &mut ErrorReport)
+                              -> Result<(), AppError> {
+    let mut new_movies: Vec<&Movie> = Vec::new();
+
+    for movie in movies.iter() {
+        let is_new = !opened_movie_set.contains(&movie.id);
 
+        if !options.quiet {
+            print_movie(movie, genre_map, is_new, store.listed_secs(movie.id)?, options.output,
+                        options.overview, options.language);
+        }
+
+        if !options.dry_run {
+            let genre_names = get_genre_name_from_ids(&movie.genre_ids, genre_map);
+            store.record_seen(movie.id, &movie.title, &movie.release_date, &genre_names)?;
+        }
+
+        if is_new {
+            // only for this run, so a movie also added to a watched list is
+            // not alerted on twice
             opened_movie_set.insert(movie.id);
+            new_movies.push(movie);
         }
+    }
+
+    #[cfg(feature = "pick")]
+    if options.pick && !options.dry_run && !new_movies.is_empty() {
+        new_movies = pick_movies(new_movies, store)?;
+    }
+
+    // notifiers in a language of their own get titles in it; every run that
+    // alerts has set up the client, none of them is offline
+    let languages: Vec<String> = dispatcher.languages()
+        .into_iter()
+        .filter(|&language| language != options.language)
+        .map(str::to_owned)
+        .collect();
+    let mut translations = match TMD_CLIENT.get() {
+        Some(client) if !languages.is_empty() && !options.dry_run => {
+            let cache = response_cache(ResponseCache::translations, options);
+            let ids = new_movies.iter().map(|movie| movie.id).collect();
+
+            look_up(ids, cache.as_ref(), |ids| {
+                retrieve_translations(ids, options.lookup_concurrency, client, watchdog)
+            }).await
+        },
+        _ => HashMap::new(),
     };
-}
 
-fn get_upcoming_movies_by_genre_id(genre_id: u32, movies: &[Movie]) -> Vec<&Movie> {
-    movies
-        .iter()
-        .filter(move |movie| {
-            match movie.genre_ids.iter().find(|&&i| i == genre_id) {
-                Some(_) => true,
-                None => false,
+    for movie in new_movies.into_iter() {
+        watchdog.notification()?;
+        let url = links::tmd_movie_url(movie.id);
+
+        if options.dry_run {
+            if !options.quiet {
+                println!("Would alert ({}): {}", dispatcher.channels().join(", "), url);
             }
-        }).collect()
+
+            continue;
+        }
+
+        let mut digest = digest_movie(movie, genre_map, store, url, options.language)?;
+        if !languages.is_empty() {
+            // without them the titles stay in the api language
+            let found = match translations.remove(&movie.id) {
+                Some(Ok(found)) => found,
+                Some(Err(e @ AppError::RestClientError(..))) => {
+                    errors.add("getting the titles of a movie in other languages", e);
+                    Vec::new()
+                },
+                Some(Err(e)) => return Err(e),
+                None => Vec::new(),
+            };
+            localize(&mut digest, movie, genre_map, &found, &languages);
+        }
+
+        dispatcher.alert(digest, errors).await?;
+    }
+
+    Ok(())
 }
 
-fn get_genre_id_by_name(genre_name: &str, genre_map: &HashMap<u32, String>) -> Result<u32, AppError> {
-    genre_map
-        .iter()
-        .filter(|&(_, name) | name == genre_name)
-        .map(|(id, _)| id.clone())
-        .last()
-        .ok_or(AppError::GenreIdNotFoundError(genre_name.to_owned()))
+fn digest_movie(movie: &Movie, genre_map: &HashMap<u32, String>, store: &MovieStore,
+                url: String, language: &str) -> Result<DigestMovie, AppError> {
+    Ok(DigestMovie::new(movie, genre_map, store.groups_of(movie.id)?, url, language))
+}
+
+// the movie in every one of the `languages` as well, with the numbers
+// written the way the language writes them and titled in it where TMD has
+// a title
+fn localize(digest: &mut DigestMovie, movie: &Movie, genre_map: &HashMap<u32, String>,
+            translations: &[Translation], languages: &[String]) {
+    for language in languages.iter() {
+        let mut localized = DigestMovie::new(movie, genre_map, digest.groups.clone(),
+                                             digest.url.clone(), language);
+        if let Some(title) = translated_title(translations, language) {
+            localized.title = title.to_owned();
+        }
+
+        digest.localized.insert(language.clone(), localized);
+    }
 }
 
-fn retrieve_genre_and_convert_to_map(key: &str, core: &mut Core) -> HashMap<u32, String> {
-    let genre_response = RestClient::get(TMD_API_MOVIE_GENRES_URL)
-        .query_param(TMD_API_KEY_QUERY_PARAM_NAME, &key)
-        .query_param("language", "en-US")
-        .execute_on(core)
-        .unwrap();
+// asks which of the new movies to alert on, and what to do with the others
+#[cfg(feature = "pick")]
+fn pick_movies<'a>(movies: Vec<&'a Movie>, store: &MovieStore)
+                   -> Result<Vec<&'a Movie>, AppError> {
+    let items: Vec<String> = movies
+        .iter()
+        .map(|movie| format!("{} ({})", movie.title, release_date_label(&movie.release_date)))
+        .collect();
 
-    trace!("Got genre response: {:?}", genre_response);
+    let chosen = MultiSelect::new()
+        .with_prompt("New movies to alert on (space to choose, enter when done)")
+        .items(&items)
+        .interact()?;
 
-    let genre_response_typed: GenreReponse = genre_response
-        .content()
-        .as_typed::<GenreReponse>()
-        .unwrap();
-    trace!("Got typed genre response: {:?}", genre_response_typed);
+    let mut picked: Vec<&Movie> = Vec::new();
+    let mut others: Vec<&Movie> = Vec::new();
 
-    let mut genre_id_to_name: HashMap<u32, String> = HashMap::new();
+    for (i, movie) in movies.into_iter().enumerate() {
+        if chosen.contains(&i) {
+            picked.push(movie);
+        } else {
+            others.push(movie);
+        }
+    }
 
-    for g in genre_response_typed.genres.into_iter() {
-        genre_id_to_name.insert(g.id, g.name);
+    if !others.is_empty() {
+        let choice = Select::new()
+            .with_prompt(format!("The other {} movies", others.len()))
+            .items(PICK_CHOICES)
+            .default(0)
+            .interact()?;
+
+        for movie in others.iter() {
+            match choice {
+                1 => store.record_alerted(movie.id)?,
+                2 => store.snooze(movie.id, SNOOZE_SECS)?,
+                _ => {},
+            }
+        }
     }
 
-    let genre_id_to_name = genre_id_to_name;
+    Ok(picked)
+}
+
+fn print_deliveries(deliveries: &[Delivery], indent: &str) {
+    for delivery in deliveries.iter() {
+        let message_id = delivery.message_id.as_ref()
+            .map(|id| format!(" ({})", id))
+            .unwrap_or_default();
 
-    genre_id_to_name
+        println!("{}{} UTC  {:<7}  {}{}", indent, delivery.sent, delivery.channel,
+                 delivery.status, message_id);
+    }
 }
 
-fn get_genre_name_from_ids(ids: &[u32], genre_map: &HashMap<u32, String>) -> String {
-    ids.iter()
-        .map(|i| genre_map.get(&i) )
-        .fold((String::new(), true), |(mut result, is_first), r| {
-            match r {
-                Some(ref s) => {
-                    if !is_first {
-                        result.push_str(", ");
-                    }
+fn explain_movie(id: u32, snapshot: &UpcomingSnapshot, genre_ids: &[u32], genre_match: GenreMatch,
+                 opened_movie_set: &HashSet<u32>, deliveries: &[Delivery],
+                 genre_weights: &[GenreWeight]) {
+    let genre_map = &snapshot.genres;
+    let min_date = &snapshot.min_date;
+    let max_date = &snapshot.max_date;
 
-                    result.push_str(s);
-                },
+    let wanted_genres = describe_genres(genre_ids, genre_match, genre_map);
+
+    println!("Movie id: {}", id);
+
+    match snapshot.movies.iter().find(|movie| movie.id == id) {
+        Some(movie) => {
+            println!("Title: {}", movie.title);
+            println!("Release date: {}", release_date_label(&movie.release_date));
+            if let Some(dates) = region_release_dates_label(movie) {
+                println!("Release dates: {}", dates);
+            }
+            println!("In upcoming movies (from {} to {}): yes", min_date, max_date);
+
+            let genre_names = get_genre_name_from_ids(&movie.genre_ids, genre_map);
+            if is_genre_match(movie, genre_ids, genre_match) {
+                println!("Genre {}: matched (genres: {})", wanted_genres, genre_names);
+            } else {
+                println!("Genre {}: not matched (genres: {})", wanted_genres, genre_names);
+            }
+            println!("Score: {:.2}", weights::score(&genre_names, genre_weights));
+            match movie.excluded {
+                Some(true) => println!("Excluded: yes, by a company, keyword or person"),
+                Some(false) => println!("Excluded: no"),
                 None => {},
-            };
+            }
+        },
+        None => {
+            println!("In upcoming movies (from {} to {}): no", min_date, max_date);
+        },
+    }
 
-            (result, false)
+    if opened_movie_set.contains(&id) {
+        println!("Stored state: URL was opened");
+    } else {
+        println!("Stored state: not opened yet");
+    }
+
+    if deliveries.is_empty() {
+        println!("Alerts delivered: none");
+    } else {
+        println!("Alerts delivered:");
+        print_deliveries(deliveries, "    ");
+    }
+}
+
+fn show_outlook(from: &str, movies: &[&Movie], genre_map: &HashMap<u32, String>,
+                format: OutlookFormat) -> Result<(), AppError> {
+    let entries: Vec<outlook::OutlookEntry> = movies
+        .iter()
+        .map(|movie| {
+            outlook::OutlookEntry {
+                id: movie.id,
+                title: movie.title.clone(),
+                release_date: movie.release_date.clone(),
+                date_tbd: calendar::is_date_tbd(&movie.release_date),
+                genres: get_genre_name_from_ids(&movie.genre_ids, genre_map),
+                url: links::tmd_movie_url(movie.id),
+                source: "upcoming",
+            }
         })
-        .0
+        .collect();
+
+    let report = outlook::build(from, entries);
+
+    match format {
+        OutlookFormat::Terminal => print!("{}", outlook::render_terminal(&report)),
+        OutlookFormat::Html => print!("{}", outlook::render_html(&report)),
+        OutlookFormat::Json => {
+            println!("{}", outlook::render_json(&report)
+                .map_err(AppError::SerdeJsonSerializeError)?);
+        },
+    }
+
+    Ok(())
 }
 
-fn retrieve_all_upcoming_movies(key: &str, core: &mut Core)
-                                -> Result<(Vec<Movie>, String, String), AppError> {
+fn show_calendar(year: i32, month: u32, movies: &[&Movie], groups: &BTreeMap<String, Vec<u32>>) {
+    // a stand-in date would put the movie on a day it is not released on
+    let (undated, dated): (Vec<&&Movie>, Vec<&&Movie>) = movies
+        .iter()
+        .partition(|movie| calendar::is_date_tbd(&movie.release_date));
+
+    let mut days: Vec<(u32, &Movie)> = dated
+        .into_iter()
+        .filter_map(|movie| {
+            calendar::day_in_month(&movie.release_date, year, month).map(|day| (day, *movie))
+        })
+        .collect();
 
-    retrieve_upcoming_movies_by_page(1, key, core)
-        .and_then(|mut first_page_response| {
-            let total_pages = first_page_response.total_pages;
-            debug!("Total # of pages for upcoming movies: {}", total_pages);
+    // `(day, title on the grid, line below it)`
+    let mut entries: Vec<(u32, String, String)> = Vec::new();
 
-            let total_movies = first_page_response.total_results;
-            debug!("Total # of upcoming movies returned by page 1: {}", total_movies);
+    // two or more movies of a group in the month share one entry, on the day
+    // the first of them comes out
+    for (name, ids) in groups.iter() {
+        let members: Vec<&(u32, &Movie)> = days
+            .iter()
+            .filter(|&&(_, movie)| ids.contains(&movie.id))
+            .collect();
 
-            let min_date = first_page_response.dates.minimum;
-            let max_date = first_page_response.dates.maximum;
+        if members.len() < 2 {
+            continue;
+        }
 
-            let mut movies = Vec::new();
-            movies.append(&mut first_page_response.results);
+        let first_day = members.iter().map(|&&(day, _)| day).min().unwrap_or(1);
+        let member_titles: Vec<String> = members
+            .iter()
+            .map(|&&(day, movie)| format!("{} ({}-{:02})", movie.title, month, day))
+            .collect();
 
-            for p in 2..(total_pages + 1) {
-                let mut next_page_response = try!(retrieve_upcoming_movies_by_page(p, key, core));
-                movies.append(&mut next_page_response.results);
-            }
+        entries.push((first_day, format!("{} ({})", name, members.len()),
+                      format!("{}: {}", name, member_titles.join(", "))));
+        days.retain(|&(_, movie)| !ids.contains(&movie.id));
+    }
 
-            Ok((movies, min_date, max_date))
-        })
+    for (day, movie) in days.into_iter() {
+        entries.push((day, movie.title.clone(), movie.title.clone()));
+    }
+    entries.sort();
+
+    let titles: Vec<(u32, &str)> = entries
+        .iter()
+        .map(|&(day, ref title, _)| (day, title.as_str()))
+        .collect();
+
+    print!("{}", calendar::render_month(year, month, &titles));
+
+    for &(day, _, ref line) in entries.iter() {
+        println!("{}-{:02}-{:02}  {}", year, month, day, line);
+    }
+
+    if !undated.is_empty() {
+        println!("Not shown, release date TBD: {}", undated.len());
+    }
 }
 
-fn retrieve_upcoming_movies_by_page(page: u32, key: &str, core: &mut Core)
-                                    -> Result<UpComingMovieResponse, AppError> {
-    debug!("Getting upcoming movies, page={}", page);
-
-    RestClient::get(TMD_API_MOVIE_UPCOMING_URL)
-        .query_param(TMD_API_KEY_QUERY_PARAM_NAME, &key)
-        .query_param("language", "en-US")
-        .query_param("page", &page.to_string())
-        .query_param("region", "US")
-        .execute_on(core)
-        .map_err(|e| AppError::RestClientError(
-                        "Error: cannot get upcoming movies for page ".to_string() +
-                            &page.to_string(), e))
-        .and_then(|response| {
-            trace!("Got upcoming response: {:?}", response);
-
-            response
-                .content()
-                .as_typed::<UpComingMovieResponse>()
-                .map_err(|e| AppError::RestClientError(
-                            "Error: cannot parse upcoming movie response to json".to_string(),
-                            e))
-        })
+// TMD_API_BASE_URL wins over the api_base_urls of the config file
+fn new_tmd_client(key: String, config: &Config) -> TmdClient {
+    let base_urls = env::var(TMD_API_BASE_URL_ENV_KEY_NAME)
+        .ok()
+        .or_else(|| Some(config.api_base_urls.join(",")).filter(|urls| !urls.is_empty()));
+    if let Some(ref urls) = base_urls {
+        debug!("Using TMD api base urls: {}", urls);
+    }
 
+    TmdClient::new(key, base_urls.as_deref(),
+                   config.language.as_deref(),
+                   config.region.as_deref())
+        .with_page_concurrency(config.http.concurrency.unwrap_or(DEFAULT_PAGE_CONCURRENCY))
 }
+
@@ -0,0 +1,190 @@
+// Matches a title and year from elsewhere, an exported list say, against the
+// movies a TMD search finds, so a near miss is not taken for the movie.
+// Titles are compared normalized, without case, accents, punctuation and a
+// leading article, by how many letter pairs of their words they share; a
+// year one off still counts, a release date often differs by country.  Every
+// match has a confidence: a high one is taken, a medium one is for the user
+// to confirm, and a low one is no match.
+//
+//     "The Lord of the Rings: The Two Towers" -> "lord of the rings the two towers"
+
+/// How sure a match is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// The movie of a search best matching a title.
+#[derive(Debug, PartialEq)]
+pub struct TitleMatch {
+    pub id: u32,
+    pub title: String,
+    pub year: Option<i32>,
+    /// From 0 to 1, 1 for the same title of the same year.
+    pub score: f64,
+    pub confidence: Confidence,
+}
+
+// scores a match at least needs
+const HIGH_SCORE: f64 = 0.9;
+const MEDIUM_SCORE: f64 = 0.6;
+
+// what a year off takes from the score, none when there is no year to go by
+const YEAR_OFF_BY_ONE: f64 = 0.95;
+const YEAR_OFF: f64 = 0.7;
+
+const ARTICLES: &[&str] = &["the", "a", "an"];
+
+// the accented letters of the languages TMD titles are mostly in
+fn fold_accent(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' => 'c',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        _ => c,
+    }
+}
+
+/// The title to compare: lowercase, without accents and punctuation, words
+/// separated by a space and a leading article left out.
+pub fn normalize(title: &str) -> String {
+    let folded: String = title.to_lowercase()
+        .replace('&', " and ")
+        .chars()
+        .map(fold_accent)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut words: Vec<&str> = folded.split_whitespace().collect();
+
+    if words.len() > 1 && ARTICLES.contains(&words[0]) {
+        words.remove(0);
+    }
+
+    words.join(" ")
+}
+
+// the letter pairs of every word, a one letter word being a pair of its own
+fn letter_pairs(title: &str) -> Vec<String> {
+    title.split(' ')
+        .flat_map(|word| {
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() < 2 {
+                vec![word.to_owned()]
+            } else {
+                chars.windows(2).map(|pair| pair.iter().collect()).collect()
+            }
+        })
+        .collect()
+}
+
+/// How alike two titles are, from 0 to 1: the share of letter pairs they
+/// have in common, 1 when they normalize to the same.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 1.0;
+    }
+
+    let a_pairs = letter_pairs(&a);
+    let mut b_pairs = letter_pairs(&b);
+    let total = a_pairs.len() + b_pairs.len();
+    let mut shared = 0;
+
+    for pair in a_pairs.iter() {
+        if let Some(position) = b_pairs.iter().position(|other| other == pair) {
+            b_pairs.swap_remove(position);
+            shared += 1;
+        }
+    }
+
+    if total == 0 { 0.0 } else { 2.0 * shared as f64 / total as f64 }
+}
+
+fn year_factor(wanted: Option<i32>, found: Option<i32>) -> f64 {
+    match (wanted, found) {
+        (Some(wanted), Some(found)) if wanted == found => 1.0,
+        (Some(wanted), Some(found)) if (wanted - found).abs() == 1 => YEAR_OFF_BY_ONE,
+        (Some(_), Some(_)) => YEAR_OFF,
+        _ => 1.0,
+    }
+}
+
+fn confidence(score: f64) -> Confidence {
+    if score >= HIGH_SCORE {
+        Confidence::High
+    } else if score >= MEDIUM_SCORE {
+        Confidence::Medium
+    } else {
+        Confidence::Low
+    }
+}
+
+/// The best of the `candidates`, by id, title and year, for `title` of
+/// `year`; the first of them when several score the same.
+pub fn best_match(title: &str, year: Option<i32>, candidates: &[(u32, &str, Option<i32>)])
+                  -> Option<TitleMatch> {
+    let mut best: Option<TitleMatch> = None;
+
+    for &(id, found, found_year) in candidates.iter() {
+        let score = similarity(title, found) * year_factor(year, found_year);
+
+        if best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(TitleMatch {
+                id,
+                title: found.to_owned(),
+                year: found_year,
+                score,
+                confidence: confidence(score),
+            });
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_leaves_out_case_accents_punctuation_and_an_article() {
+        assert_eq!(normalize("The Lord of the Rings: The Two Towers"),
+                   "lord of the rings the two towers");
+        assert_eq!(normalize("Amélie"), "amelie");
+        assert_eq!(normalize("Fast & Furious"), "fast and furious");
+        assert_eq!(normalize("  Se7en  "), "se7en");
+        assert_eq!(normalize("The"), "the");
+    }
+
+    #[test]
+    fn similarity_is_one_for_the_same_title() {
+        assert_eq!(similarity("The Matrix", "matrix"), 1.0);
+        assert_eq!(similarity("Alien", "Heat"), 0.0);
+        let partly = similarity("Star Wars", "Star Wars: Episode IV");
+        assert!(partly > MEDIUM_SCORE && partly < HIGH_SCORE);
+        assert!(similarity("Harry Potter and the Philosopher's Stone",
+                           "Harry Potter and the Philosophers Stone") > HIGH_SCORE);
+    }
+
+    #[test]
+    fn best_match_tolerates_a_year_off_by_one() {
+        let candidates = [(1, "Dune", Some(1984)), (2, "Dune", Some(2021))];
+
+        let found = best_match("Dune", Some(2020), &candidates).unwrap();
+        assert_eq!((found.id, found.confidence), (2, Confidence::High));
+
+        let found = best_match("Dune", Some(1990), &candidates).unwrap();
+        assert_eq!(found.confidence, Confidence::Medium);
+
+        let found = best_match("Alien", Some(1979), &[(3, "Alien: Romulus", Some(2024))]);
+        assert_eq!(found.unwrap().confidence, Confidence::Low);
+        assert_eq!(best_match("Alien", None, &[]), None);
+    }
+}
@@ -0,0 +1,303 @@
+// Publishes to an MQTT broker for Home Assistant: every new movie as an event
+// of a "New movie" event entity, and after every check a "Movies releasing
+// this week" sensor, for a dashboard or smart display.  Both entities are
+// announced with retained discovery messages, so Home Assistant picks them up
+// without any yaml.  Only what this needs of MQTT 3.1.1 is spoken: a clean
+// session publishing at QoS 0, with a username and password if the broker
+// wants them, and no TLS.
+//
+//     movie_alert/new                  {"event_type": "new_movie", "id": 1,
+//                                       "title": "...", ...}
+//     movie_alert/releasing_this_week  {"count": 2, "movies": [...]}, retained
+
+use serde_json::{json, Value};
+use std::fs;
+use std::future::Future;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use crate::config::MqttConfig;
+use crate::digest::DigestMovie;
+use crate::AppError;
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_TOPIC: &str = "movie_alert";
+const DEFAULT_DISCOVERY_PREFIX: &str = "homeassistant";
+
+// of the topic
+const NEW_MOVIE_TOPIC: &str = "new";
+const RELEASING_TOPIC: &str = "releasing_this_week";
+
+// a whole session, from connecting to disconnecting
+const SESSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+const KEEP_ALIVE_SECS: u16 = 60;
+
+// packet types, in the high nibble of the first byte
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const DISCONNECT: u8 = 0xe0;
+
+const RETAIN: u8 = 0x01;
+
+// connect flags
+const CLEAN_SESSION: u8 = 0x02;
+const PASSWORD_FLAG: u8 = 0x40;
+const USERNAME_FLAG: u8 = 0x80;
+
+// "host:1883", the port defaults to 1883
+fn address(broker: &str) -> String {
+    match broker.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => broker.to_owned(),
+        _ => format!("{}:{}", broker, DEFAULT_PORT),
+    }
+}
+
+fn topic(settings: &MqttConfig, name: &str) -> String {
+    format!("{}/{}", settings.topic.as_deref().unwrap_or(DEFAULT_TOPIC), name)
+}
+
+// the remaining length of a packet, seven bits a byte
+fn encode_length(mut length: usize, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+
+        if length == 0 {
+            break;
+        }
+    }
+}
+
+// strings and the password go with their length in front
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn packet(first_byte: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![first_byte];
+    encode_length(body.len(), &mut packet);
+    packet.extend_from_slice(body);
+
+    packet
+}
+
+fn connect_packet(client_id: &str, username: Option<&str>, password: Option<&str>) -> Vec<u8> {
+    let mut flags = CLEAN_SESSION;
+    if username.is_some() {
+        flags |= USERNAME_FLAG;
+    }
+    if password.is_some() {
+        flags |= PASSWORD_FLAG;
+    }
+
+    let mut body = Vec::new();
+    encode_bytes(b"MQTT", &mut body);
+    body.push(4); // protocol level of 3.1.1
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+    encode_bytes(client_id.as_bytes(), &mut body);
+    for value in [username, password].iter().flatten() {
+        encode_bytes(value.as_bytes(), &mut body);
+    }
+
+    packet(CONNECT, &body)
+}
+
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    encode_bytes(topic.as_bytes(), &mut body);
+    body.extend_from_slice(payload);
+
+    packet(if retain { PUBLISH | RETAIN } else { PUBLISH }, &body)
+}
+
+// why the broker refused the connection, by the return code of the CONNACK
+fn refusal(code: u8) -> &'static str {
+    match code {
+        1 => "it does not speak MQTT 3.1.1",
+        2 => "it rejected the client id",
+        3 => "it is unavailable",
+        4 => "the username or password is wrong",
+        5 => "not authorized",
+        _ => "unknown return code",
+    }
+}
+
+struct Session {
+    stream: TcpStream,
+}
+
+impl Session {
+    async fn connect(settings: &MqttConfig) -> Result<Session, AppError> {
+        let password = match settings.password_file {
+            Some(ref path) => Some(fs::read_to_string(path)?.trim().to_owned()),
+            None => None,
+        };
+
+        debug!("Connecting to the MQTT broker at {}", settings.broker);
+
+        let mut stream = TcpStream::connect(address(&settings.broker)).await?;
+        let client_id = format!("movie_alert-{}", std::process::id());
+        stream.write_all(&connect_packet(&client_id, settings.username.as_deref(),
+                                         password.as_deref())).await?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack).await?;
+
+        if connack[0] != CONNACK {
+            return Err(AppError::NotificationError(
+                format!("the MQTT broker at {} did not answer with a CONNACK", settings.broker)));
+        }
+        if connack[3] != 0 {
+            return Err(AppError::NotificationError(
+                format!("the MQTT broker at {} refused the connection: {}", settings.broker,
+                        refusal(connack[3]))));
+        }
+
+        Ok(Session { stream })
+    }
+
+    async fn publish(&mut self, topic: &str, payload: &Value, retain: bool)
+                     -> Result<(), AppError> {
+        let payload = serde_json::to_vec(payload).map_err(AppError::SerdeJsonSerializeError)?;
+
+        debug!("Publishing {} bytes to {}", payload.len(), topic);
+
+        Ok(self.stream.write_all(&publish_packet(topic, &payload, retain)).await?)
+    }
+
+    async fn disconnect(mut self) -> Result<(), AppError> {
+        self.stream.write_all(&packet(DISCONNECT, &[])).await?;
+
+        Ok(self.stream.shutdown().await?)
+    }
+}
+
+// the discovery messages of both entities, retained so Home Assistant finds
+// them after a restart
+async fn announce(session: &mut Session, settings: &MqttConfig) -> Result<(), AppError> {
+    let prefix = settings.discovery_prefix.as_deref().unwrap_or(DEFAULT_DISCOVERY_PREFIX);
+    let device = json!({ "identifiers": ["movie_alert"], "name": "movie_alert" });
+    let releasing = topic(settings, RELEASING_TOPIC);
+
+    let event = json!({
+        "name": "New movie",
+        "unique_id": "movie_alert_new_movie",
+        "state_topic": topic(settings, NEW_MOVIE_TOPIC),
+        "event_types": ["new_movie"],
+        "icon": "mdi:movie-open-star",
+        "device": device,
+    });
+    let sensor = json!({
+        "name": "Movies releasing this week",
+        "unique_id": "movie_alert_releasing_this_week",
+        "state_topic": releasing,
+        "value_template": "{{ value_json.count }}",
+        "json_attributes_topic": releasing,
+        "json_attributes_template": "{{ {'movies': value_json.movies} | tojson }}",
+        "unit_of_measurement": "movies",
+        "icon": "mdi:movie-open",
+        "device": device,
+    });
+
+    session.publish(&format!("{}/event/movie_alert/new_movie/config", prefix), &event, true)
+        .await?;
+    session.publish(&format!("{}/sensor/movie_alert/releasing_this_week/config", prefix),
+                    &sensor, true).await
+}
+
+// fails the session when the broker hangs
+async fn in_time(settings: &MqttConfig,
+                 session: impl Future<Output = Result<(), AppError>>) -> Result<(), AppError> {
+    timeout(SESSION_TIMEOUT, session).await.unwrap_or_else(|_| {
+        Err(AppError::NotificationError(
+            format!("the MQTT broker at {} did not answer in time", settings.broker)))
+    })
+}
+
+/// Publishes every movie as an event of the "New movie" entity.
+pub async fn publish_movies(settings: &MqttConfig, movies: &[DigestMovie])
+                            -> Result<(), AppError> {
+    let topic = topic(settings, NEW_MOVIE_TOPIC);
+
+    in_time(settings, async {
+        let mut session = Session::connect(settings).await?;
+        announce(&mut session, settings).await?;
+
+        for movie in movies.iter() {
+            let mut event = serde_json::to_value(movie)
+                .map_err(AppError::SerdeJsonSerializeError)?;
+            event["event_type"] = json!("new_movie");
+
+            session.publish(&topic, &event, false).await?;
+        }
+
+        session.disconnect().await
+    }).await
+}
+
+/// Sets the "Movies releasing this week" sensor to the movies, retained so
+/// it holds until the next check.
+pub async fn publish_releasing(settings: &MqttConfig, movies: &[DigestMovie])
+                               -> Result<(), AppError> {
+    let state = json!({ "count": movies.len(), "movies": movies });
+
+    in_time(settings, async {
+        let mut session = Session::connect(settings).await?;
+        announce(&mut session, settings).await?;
+        session.publish(&topic(settings, RELEASING_TOPIC), &state, true).await?;
+        session.disconnect().await
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_defaults_to_the_mqtt_port() {
+        assert_eq!(address("mqtt.local"), "mqtt.local:1883");
+        assert_eq!(address("192.168.1.5:8883"), "192.168.1.5:8883");
+    }
+
+    #[test]
+    fn encode_length_takes_seven_bits_a_byte() {
+        let encoded = |length| {
+            let mut out = Vec::new();
+            encode_length(length, &mut out);
+            out
+        };
+
+        assert_eq!(encoded(0), vec![0x00]);
+        assert_eq!(encoded(127), vec![0x7f]);
+        assert_eq!(encoded(128), vec![0x80, 0x01]);
+        assert_eq!(encoded(16_383), vec![0xff, 0x7f]);
+        assert_eq!(encoded(2_097_152), vec![0x80, 0x80, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn connect_packet_carries_the_credentials() {
+        assert_eq!(connect_packet("c", None, None),
+                   vec![0x10, 13, 0, 4, b'M', b'Q', b'T', b'T', 4, 0x02, 0, 60, 0, 1, b'c']);
+
+        let packet = connect_packet("c", Some("me"), Some("pw"));
+        assert_eq!(packet[9], 0xc2);
+        assert!(packet.ends_with(&[0, 2, b'm', b'e', 0, 2, b'p', b'w']));
+    }
+
+    #[test]
+    fn publish_packet_is_retained_when_asked() {
+        assert_eq!(publish_packet("a/b", b"{}", true),
+                   vec![0x31, 7, 0, 3, b'a', b'/', b'b', b'{', b'}']);
+        assert_eq!(publish_packet("a/b", b"{}", false)[0], 0x30);
+    }
+}
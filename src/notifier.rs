@@ -0,0 +1,662 @@
+// Every way of alerting about new movies is a `Notifier`, and a run sends its
+// new movies to all of the configured ones.  Each delivery is recorded on
+// its own, so a notifier that fails only fails itself: the others still get
+// the movies, and next run the movie is only tried again with the notifiers
+// that failed.  A notifier that takes only so many messages a minute, like a
+// chat webhook, has its messages queued and spread out, so that the parts of
+// a long digest are not dropped.  A push to a phone that could not go out
+// is tried again on the next runs only until the movie is released, then it
+// is dropped as expired.  A notifier can be given a language of its own,
+// and gets the movies as localized into it instead of as found.
+
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{self, StreamExt};
+use std::collections::{BTreeSet, HashSet};
+use std::slice;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use crate::clock::Clock;
+use crate::config::{NotificationConfig, WebhookConfig};
+use crate::digest::{DIGEST_PART_SIZE, DigestMovie, DigestPart};
+use crate::store::{ANNOUNCED_ALERT, MovieStore, PLAYING_ALERT, UPCOMING_ALERT};
+use crate::{calendar, discord, links, slack, webhook};
+use crate::{AppError, ErrorReport};
+
+#[cfg(feature = "email")]
+use crate::{config::EmailConfig, email};
+#[cfg(feature = "desktop")]
+use crate::notify;
+#[cfg(feature = "mqtt")]
+use crate::{config::MqttConfig, mqtt};
+#[cfg(feature = "push")]
+use crate::{config::{GotifyConfig, NtfyConfig}, push};
+#[cfg(feature = "readlater")]
+use crate::{config::{InstapaperConfig, WallabagConfig}, http, readlater};
+#[cfg(feature = "readlater")]
+use tokio::sync::OnceCell;
+
+/// How many notifiers are sent to at a time unless [notifications]
+/// concurrency says otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// What a delivery went out with: the notification id or email Message-ID,
+/// when the channel has one.
+pub type Sent = Result<Option<String>, AppError>;
+
+pub trait Notifier {
+    /// Deliveries are recorded under this, e.g. "slack".
+    fn channel(&self) -> &'static str;
+
+    /// Whether all new movies of a run are sent together once it is over,
+    /// instead of one by one as they are found.
+    fn sends_digest(&self) -> bool {
+        false
+    }
+
+    /// The most movies in one message of the digest, a longer digest is sent
+    /// in parts.
+    fn digest_part_size(&self) -> usize {
+        DIGEST_PART_SIZE
+    }
+
+    /// The most messages a minute the channel takes, any number when none.
+    fn messages_per_minute(&self) -> Option<u32> {
+        None
+    }
+
+    /// Whether an alert about a movie coming up is dropped once the movie
+    /// is released instead of being tried again, for a phone that was off.
+    fn expires_on_release(&self) -> bool {
+        false
+    }
+
+    /// Alerts about the movies, which is a single one unless the notifier
+    /// sends a digest.  `part` is the part of the digest they are, if so.
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent>;
+}
+
+/// Prints a line per movie, e.g. for cron to mail.
+pub struct Stdout;
+
+impl Notifier for Stdout {
+    fn channel(&self) -> &'static str {
+        "stdout"
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        for movie in movies.iter() {
+            println!("New: {} ({})  {}", movie.title, movie.release_date, movie.url);
+        }
+
+        async { Ok(None) }.boxed()
+    }
+}
+
+/// Opens the movie pages, in `browser` when given.
+pub struct Browser<'a> {
+    pub browser: Option<&'a str>,
+}
+
+impl Notifier for Browser<'_> {
+    fn channel(&self) -> &'static str {
+        "browser"
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        let opened = movies
+            .iter()
+            .try_for_each(|movie| links::open_in_browser(&movie.url, self.browser))
+            .map(|_| None);
+
+        async { opened }.boxed()
+    }
+}
+
+/// Shows a notification per movie, a click opens it in `browser` where the
+/// platform allows, see notify.rs.
+#[cfg(feature = "desktop")]
+pub struct Desktop<'a> {
+    pub browser: Option<&'a str>,
+}
+
+#[cfg(feature = "desktop")]
+impl Notifier for Desktop<'_> {
+    fn channel(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        let mut shown = Ok(None);
+
+        for movie in movies.iter() {
+            shown = notify::show_desktop_notification(&movie.title, &movie.release_date,
+                                                      &movie.url, self.browser);
+            if shown.is_err() {
+                break;
+            }
+        }
+
+        async { shown }.boxed()
+    }
+}
+
+#[cfg(feature = "email")]
+pub struct Email<'a>(pub &'a EmailConfig);
+
+#[cfg(feature = "email")]
+impl Notifier for Email<'_> {
+    fn channel(&self) -> &'static str {
+        "email"
+    }
+
+    fn sends_digest(&self) -> bool {
+        true
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        let sent = email::send_digest(self.0, movies, part).map(Some);
+
+        async { sent }.boxed()
+    }
+}
+
+pub struct Webhook<'a>(pub &'a WebhookConfig);
+
+impl Notifier for Webhook<'_> {
+    fn channel(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn sends_digest(&self) -> bool {
+        true
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        webhook::post_movies(self.0, movies, part).map(|posted| posted.map(|_| None)).boxed()
+    }
+}
+
+/// Posts to the Slack incoming webhook at the url.
+pub struct Slack(pub String);
+
+impl Notifier for Slack {
+    fn channel(&self) -> &'static str {
+        "slack"
+    }
+
+    fn sends_digest(&self) -> bool {
+        true
+    }
+
+    fn digest_part_size(&self) -> usize {
+        slack::MOVIES_PER_MESSAGE
+    }
+
+    fn messages_per_minute(&self) -> Option<u32> {
+        Some(slack::MESSAGES_PER_MINUTE)
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        slack::post_movies(&self.0, movies, part).map(|posted| posted.map(|_| None)).boxed()
+    }
+}
+
+/// Posts to the Discord webhook at the url.
+pub struct Discord(pub String);
+
+impl Notifier for Discord {
+    fn channel(&self) -> &'static str {
+        "discord"
+    }
+
+    fn sends_digest(&self) -> bool {
+        true
+    }
+
+    fn digest_part_size(&self) -> usize {
+        discord::MOVIES_PER_MESSAGE
+    }
+
+    fn messages_per_minute(&self) -> Option<u32> {
+        Some(discord::MESSAGES_PER_MINUTE)
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        discord::post_movies(&self.0, movies, part).map(|posted| posted.map(|_| None)).boxed()
+    }
+}
+
+/// Publishes to the MQTT broker, once for the whole digest, see mqtt.rs.
+#[cfg(feature = "mqtt")]
+pub struct Mqtt<'a>(pub &'a MqttConfig);
+
+#[cfg(feature = "mqtt")]
+impl Notifier for Mqtt<'_> {
+    fn channel(&self) -> &'static str {
+        "mqtt"
+    }
+
+    fn sends_digest(&self) -> bool {
+        true
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        mqtt::publish_movies(self.0, movies).map(|published| published.map(|_| None)).boxed()
+    }
+}
+
+#[cfg(feature = "push")]
+pub struct Ntfy<'a>(pub &'a NtfyConfig);
+
+#[cfg(feature = "push")]
+impl Notifier for Ntfy<'_> {
+    fn channel(&self) -> &'static str {
+        "ntfy"
+    }
+
+    fn expires_on_release(&self) -> bool {
+        true
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        async move {
+            let mut pushed = None;
+            for movie in movies.iter() {
+                pushed = push::push_to_ntfy(self.0, movie).await?;
+            }
+
+            Ok(pushed)
+        }.boxed()
+    }
+}
+
+#[cfg(feature = "push")]
+pub struct Gotify<'a>(pub &'a GotifyConfig);
+
+#[cfg(feature = "push")]
+impl Notifier for Gotify<'_> {
+    fn channel(&self) -> &'static str {
+        "gotify"
+    }
+
+    fn expires_on_release(&self) -> bool {
+        true
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        async move {
+            let mut pushed = None;
+            for movie in movies.iter() {
+                pushed = push::push_to_gotify(self.0, movie).await?;
+            }
+
+            Ok(pushed)
+        }.boxed()
+    }
+}
+
+#[cfg(feature = "readlater")]
+pub struct Instapaper<'a>(pub &'a InstapaperConfig);
+
+#[cfg(feature = "readlater")]
+impl Notifier for Instapaper<'_> {
+    fn channel(&self) -> &'static str {
+        "instapaper"
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        async move {
+            for movie in movies.iter() {
+                readlater::save_to_instapaper(self.0, movie).await?;
+            }
+
+            Ok(None)
+        }.boxed()
+    }
+}
+
+/// Saves to a Wallabag server, logging in once for the whole run.
+#[cfg(feature = "readlater")]
+pub struct Wallabag<'a> {
+    settings: &'a WallabagConfig,
+    client: reqwest::Client,
+    token: OnceCell<String>,
+}
+
+#[cfg(feature = "readlater")]
+impl<'a> Wallabag<'a> {
+    pub fn new(settings: &'a WallabagConfig) -> Self {
+        Wallabag { settings, client: http::client(), token: OnceCell::new() }
+    }
+}
+
+#[cfg(feature = "readlater")]
+impl Notifier for Wallabag<'_> {
+    fn channel(&self) -> &'static str {
+        "wallabag"
+    }
+
+    fn notify<'a>(&'a self, movies: &'a [DigestMovie], _part: Option<&'a DigestPart>)
+                  -> BoxFuture<'a, Sent> {
+        async move {
+            // a failed login is tried again with the next movie
+            let token = self.token
+                .get_or_try_init(|| readlater::wallabag_token(&self.client, self.settings))
+                .await?;
+
+            let mut saved = None;
+            for movie in movies.iter() {
+                saved = readlater::save_to_wallabag(&self.client, self.settings, token,
+                                                    movie).await?;
+            }
+
+            Ok(saved)
+        }.boxed()
+    }
+}
+
+// unique enough to tell the digests of different runs apart
+fn new_digest_id(clock: &dyn Clock) -> String {
+    let nanos = clock.now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    format!("{:x}", nanos)
+}
+
+/// Adds the outcome of an alert about `ids` to their history, failed ones
+/// too, and returns whether it went out.  Its error is added to `errors`,
+/// only failing to update the store fails here.
+pub fn record_delivery(store: &MovieStore, ids: &[u32], channel: &str, result: Sent,
+                       errors: &mut ErrorReport) -> Result<bool, AppError> {
+    let (status, message_id) = match result {
+        Ok(ref message_id) => ("sent".to_owned(), message_id.as_deref()),
+        Err(ref e) => (format!("failed: {}", e), None),
+    };
+
+    for &id in ids.iter() {
+        store.record_delivery(id, channel, &status, message_id)?;
+    }
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(e) => {
+            errors.add(&format!("alerting by {}", channel), e);
+            Ok(false)
+        },
+    }
+}
+
+// a notifier with the time its next message may go out, its messages spread
+// out by `interval`
+struct Paced<'a> {
+    notifier: Box<dyn Notifier + 'a>,
+    interval: Option<Duration>,
+    next_send: Mutex<Instant>,
+    // the movies go out in this language instead of the api language
+    language: Option<String>,
+}
+
+impl<'a> Paced<'a> {
+    fn new(notifier: Box<dyn Notifier + 'a>, messages_per_minute: Option<u32>) -> Self {
+        let interval = messages_per_minute
+            .or_else(|| notifier.messages_per_minute())
+            .map(|rate| Duration::from_secs(60) / rate.max(1));
+
+        Paced { notifier, interval, next_send: Mutex::new(Instant::now()), language: None }
+    }
+
+    // waits for the turn of the message in the queue of the notifier
+    async fn notify(&self, movies: &[DigestMovie], part: Option<&DigestPart>) -> Sent {
+        if let Some(interval) = self.interval {
+            let wait = {
+                let mut next_send = self.next_send.lock().unwrap_or_else(|e| e.into_inner());
+
+                let now = Instant::now();
+                let turn = (*next_send).max(now);
+                *next_send = turn + interval;
+
+                turn - now
+            };
+
+            if !wait.is_zero() {
+                debug!("Waiting {} ms for the turn of {}", wait.as_millis(),
+                       self.notifier.channel());
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        self.notifier.notify(movies, part).await
+    }
+}
+
+/// Sends the new movies of a run to every notifier that did not get them
+/// yet, recording each delivery in the store.  A movie counts as alerted
+/// once every notifier got it.
+pub struct Dispatcher<'a> {
+    notifiers: Vec<Paced<'a>>,
+    store: &'a MovieStore,
+    // movies waiting for the notifiers that send a digest
+    digest: Vec<DigestMovie>,
+    // movies in the digest that a notifier already failed to get to
+    failed: HashSet<u32>,
+    // shared by all parts of the digest, see DigestPart
+    digest_id: String,
+    // UPCOMING_ALERT, or PLAYING_ALERT or ANNOUNCED_ALERT for movies in
+    // theaters or announced for a later year, which are remembered apart
+    // from the alert about them coming up
+    alert: &'static str,
+    // notifiers sent to at a time, each of them getting its movies in order
+    concurrency: usize,
+}
+
+impl<'a> Dispatcher<'a> {
+    pub fn new(notifiers: Vec<Box<dyn Notifier + 'a>>, store: &'a MovieStore) -> Self {
+        Dispatcher {
+            notifiers: notifiers.into_iter().map(|notifier| Paced::new(notifier, None)).collect(),
+            store,
+            digest: Vec::new(),
+            failed: HashSet::new(),
+            digest_id: new_digest_id(store.clock()),
+            alert: UPCOMING_ALERT,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// The same dispatcher sending to as many notifiers at a time as the
+    /// settings say, and as many messages a minute to each of them.
+    pub fn with_settings(self, settings: &NotificationConfig) -> Self {
+        let notifiers = self.notifiers
+            .into_iter()
+            .map(|paced| {
+                let channel = paced.notifier.channel();
+                let paced = match settings.messages_per_minute.get(channel) {
+                    Some(&rate) => Paced::new(paced.notifier, Some(rate)),
+                    None => paced,
+                };
+
+                Paced { language: settings.languages.get(channel).cloned(), ..paced }
+            })
+            .collect();
+        let concurrency = settings.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1);
+
+        Dispatcher { notifiers, concurrency, ..self }
+    }
+
+    /// Same as `new`, for alerts about movies now in theaters.
+    pub fn now_playing(notifiers: Vec<Box<dyn Notifier + 'a>>, store: &'a MovieStore) -> Self {
+        Dispatcher { alert: PLAYING_ALERT, ..Dispatcher::new(notifiers, store) }
+    }
+
+    /// Same as `new`, for alerts about movies announced for a later year.
+    pub fn announced(notifiers: Vec<Box<dyn Notifier + 'a>>, store: &'a MovieStore) -> Self {
+        Dispatcher { alert: ANNOUNCED_ALERT, ..Dispatcher::new(notifiers, store) }
+    }
+
+    /// Channels of all notifiers, e.g. ["browser", "slack"].
+    pub fn channels(&self) -> Vec<&'static str> {
+        self.notifiers.iter().map(|paced| paced.notifier.channel()).collect()
+    }
+
+    /// Languages notifiers want the movies in instead of the api language,
+    /// which `DigestMovie::localized` has them in.
+    pub fn languages(&self) -> BTreeSet<&str> {
+        self.notifiers.iter().filter_map(|paced| paced.language.as_deref()).collect()
+    }
+
+    /// Alerts the notifiers that take movies one by one about the movie
+    /// right away, and puts it in the digest of the others.  Notifiers that
+    /// got the movie on an earlier run are left out.
+    pub async fn alert(&mut self, movie: DigestMovie, errors: &mut ErrorReport)
+                       -> Result<(), AppError> {
+        let delivered_before = self.store.delivered_channels(movie.id, self.alert)?;
+        let released = self.alert == UPCOMING_ALERT && !calendar::is_date_tbd(&movie.release_date)
+            && movie.release_date < calendar::date_in_days(self.store.clock(), 0);
+        let (expired, owed): (Vec<&Paced>, Vec<&Paced>) = self.notifiers
+            .iter()
+            .filter(|paced| !delivered_before.contains(paced.notifier.channel()))
+            .partition(|paced| released && paced.notifier.expires_on_release());
+
+        for paced in expired.iter() {
+            self.expire(movie.id, paced.notifier.channel())?;
+        }
+
+        let mut delivered = true;
+
+        let movie_ref = &movie;
+        let mut sends = stream::iter(owed.iter().filter(|paced| !paced.notifier.sends_digest()))
+            .map(|paced| async move {
+                let single = slice::from_ref(movie_ref.in_language(paced.language.as_deref()));
+
+                (paced.notifier.channel(), paced.notify(single, None).await)
+            })
+            .buffered(self.concurrency);
+
+        while let Some((channel, sent)) = sends.next().await {
+            delivered &= self.record(&[movie.id], channel, sent, errors)?;
+        }
+        drop(sends);
+
+        if owed.iter().any(|paced| paced.notifier.sends_digest()) {
+            if !delivered {
+                self.failed.insert(movie.id);
+            }
+            self.digest.push(movie);
+        } else if delivered {
+            self.record_alerted(movie.id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the digest to the notifiers that take one, once the run is over,
+    /// each with the movies it did not get yet.  A digest too long for a
+    /// message of the notifier goes out in parts, each recorded on its own,
+    /// so only the movies of a part that failed are tried again.
+    pub async fn finish(&mut self, errors: &mut ErrorReport) -> Result<(), AppError> {
+        if self.digest.is_empty() {
+            return Ok(());
+        }
+
+        let mut delivered_before = Vec::new();
+        for movie in self.digest.iter() {
+            delivered_before.push(self.store.delivered_channels(movie.id, self.alert)?);
+        }
+
+        // every notifier with the parts of its digest, sent in order
+        let digesting = self.notifiers.iter().filter(|paced| paced.notifier.sends_digest());
+        let digests = digesting.map(|paced| {
+            let notifier = &paced.notifier;
+            let movies: Vec<DigestMovie> = self.digest
+                .iter()
+                .zip(delivered_before.iter())
+                .filter(|(_, channels)| !channels.contains(notifier.channel()))
+                .map(|(movie, _)| movie.in_language(paced.language.as_deref()).clone())
+                .collect();
+            let digest_id = &self.digest_id;
+
+            async move {
+                // never an empty part, even for a notifier that claims to
+                // take none
+                let part_size = notifier.digest_part_size().max(1);
+                let parts = movies.len().div_ceil(part_size);
+                let mut sent_parts = Vec::new();
+
+                for (index, chunk) in movies.chunks(part_size).enumerate() {
+                    let part = DigestPart { id: digest_id.clone(), part: index + 1, parts };
+
+                    let ids: Vec<u32> = chunk.iter().map(|movie| movie.id).collect();
+                    sent_parts.push((ids, paced.notify(chunk, Some(&part)).await));
+                }
+
+                (notifier.channel(), sent_parts)
+            }
+        });
+        let mut sends = stream::iter(digests).buffered(self.concurrency);
+
+        let mut failed = Vec::new();
+        while let Some((channel, sent_parts)) = sends.next().await {
+            for (ids, sent) in sent_parts {
+                if !self.record(&ids, channel, sent, errors)? {
+                    failed.extend(ids);
+                }
+            }
+        }
+        drop(sends);
+        self.failed.extend(failed);
+
+        for movie in self.digest.iter().filter(|movie| !self.failed.contains(&movie.id)) {
+            self.record_alerted(movie.id)?;
+        }
+
+        self.digest.clear();
+        self.failed.clear();
+
+        Ok(())
+    }
+
+    // records the delivery, and the channel as done with the movies when it
+    // went out
+    fn record(&self, ids: &[u32], channel: &str, sent: Sent, errors: &mut ErrorReport)
+              -> Result<bool, AppError> {
+        let delivered = record_delivery(self.store, ids, channel, sent, errors)?;
+
+        if delivered {
+            for &id in ids.iter() {
+                self.store.record_delivered(id, self.alert, channel)?;
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    // no longer owed the alert, which is kept in the history as expired
+    fn expire(&self, id: u32, channel: &str) -> Result<(), AppError> {
+        debug!("Dropping the {} alert about movie {}, it is released", channel, id);
+        self.store.record_delivery(id, channel, "expired", None)?;
+
+        self.store.record_delivered(id, self.alert, channel)
+    }
+
+    fn record_alerted(&self, id: u32) -> Result<(), AppError> {
+        match self.alert {
+            PLAYING_ALERT => self.store.record_playing(id),
+            ANNOUNCED_ALERT => self.store.record_announced(id),
+            _ => self.store.record_alerted(id),
+        }
+    }
+}
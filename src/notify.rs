@@ -0,0 +1,71 @@
+// Desktop notifications for new movies, a quieter alternative to opening a
+// browser tab for every one of them when dozens show up at once.
+
+use notify_rust::Notification;
+use crate::AppError;
+
+// Clicking the notification opens the movie page, where the notification
+// daemon tells which one was clicked (XDG).  The click is waited for on a
+// thread of its own, which ends with movie_alert: in watch mode clicks work
+// for as long as it keeps watching, after a single check only until it
+// exits.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn open_on_click(handle: notify_rust::NotificationHandle, url: &str, browser: Option<&str>)
+                 -> Option<String> {
+    let id = handle.id().to_string();
+
+    let page = url.to_owned();
+    let browser = browser.map(str::to_owned);
+
+    let waiting = std::thread::Builder::new()
+        .name("notification".to_owned())
+        .spawn(move || {
+            handle.wait_for_action(|action| {
+                if action != "default" {
+                    return;
+                }
+                if let Err(e) = crate::links::open_in_browser(&page, browser.as_deref()) {
+                    e.report_error();
+                }
+            })
+        });
+
+    if let Err(e) = waiting {
+        warn!("Clicking the notification of {} will not open it: {}", url, e);
+    }
+
+    Some(id)
+}
+
+// no notification id and nothing to wait for, the url in the body is all
+// there is
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn open_on_click<H>(_handle: H, _url: &str, _browser: Option<&str>) -> Option<String> {
+    None
+}
+
+/// Shows the notification, returns its id where the platform has one.
+/// Where clicks can be told apart, a click opens `url` with `browser`, the
+/// system default when it is not given.
+pub fn show_desktop_notification(title: &str, release_date: &str, url: &str,
+                                 browser: Option<&str>) -> Result<Option<String>, AppError> {
+    debug!("Showing desktop notification for: {}", title);
+
+    let mut notification = Notification::new();
+
+    // the url is in the body too, most notification daemons make it clickable
+    notification
+        .appname("movie_alert")
+        .summary(title)
+        .body(&format!("Release date: {}\n{}", release_date, url));
+
+    // elsewhere an action would only add a button that does nothing
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notification.action("default", "Open");
+
+    notification
+        .show()
+        .map(|handle| open_on_click(handle, url, browser))
+        .map_err(|e| AppError::NotificationError(
+            format!("the desktop notification failed: {}", e)))
+}
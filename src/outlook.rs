@@ -0,0 +1,227 @@
+// Year-ahead outlook: every known release in the next 12 months in date
+// order, grouped by quarter, rendered for the terminal, as html or as json.
+// Movies without a known release date are listed last, under "Date TBD".
+
+const DATE_TBD_SECTION_NAME: &str = "Date TBD";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutlookEntry {
+    pub id: u32,
+    pub title: String,
+    pub release_date: String,
+    // the release date is empty or only a stand-in
+    pub date_tbd: bool,
+    pub genres: String,
+    pub url: String,
+    // where the movie was found, e.g. "upcoming"
+    pub source: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Quarter {
+    pub name: String,
+    pub movies: Vec<OutlookEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Outlook {
+    pub from: String,
+    pub to: String,
+    pub quarters: Vec<Quarter>,
+}
+
+// "2025-07-18" -> "2025 Q3"
+fn quarter_of(release_date: &str) -> Option<String> {
+    let mut parts = release_date.splitn(3, '-');
+
+    let year = parts.next().and_then(|y| y.parse::<u32>().ok());
+    let month = parts.next().and_then(|m| m.parse::<u32>().ok());
+
+    match (year, month) {
+        (Some(y), Some(m)) if (1..=12).contains(&m) => Some(format!("{} Q{}", y, (m - 1) / 3 + 1)),
+        _ => None,
+    }
+}
+
+// same day one year later, dates compare fine as strings
+fn one_year_after(date: &str) -> String {
+    match date.find('-').and_then(|i| date[..i].parse::<u32>().ok().map(|y| (y, &date[i..]))) {
+        Some((year, rest)) => format!("{}{}", year + 1, rest),
+        None => date.to_owned(),
+    }
+}
+
+/// Builds the outlook for the 12 months starting at `from` (a date like
+/// "2025-07-01").  Entries without a usable date are left out.
+pub fn build(from: &str, mut entries: Vec<OutlookEntry>) -> Outlook {
+    let to = one_year_after(from);
+
+    // an empty date may well fall in the window, a stand-in one is kept to
+    // its year
+    entries.retain(|e| {
+        (e.date_tbd && e.release_date.is_empty()) ||
+            (e.release_date.as_str() >= from && e.release_date < to)
+    });
+    entries.sort_by(|a, b| {
+        a.date_tbd.cmp(&b.date_tbd)
+            .then(a.release_date.cmp(&b.release_date))
+            .then(a.id.cmp(&b.id))
+    });
+    entries.dedup_by(|a, b| a.id == b.id);
+
+    let mut quarters: Vec<Quarter> = Vec::new();
+
+    for entry in entries.into_iter() {
+        let name = if entry.date_tbd {
+            DATE_TBD_SECTION_NAME.to_owned()
+        } else {
+            match quarter_of(&entry.release_date) {
+                Some(name) => name,
+                None => continue,
+            }
+        };
+
+        let is_same_quarter = quarters.last().map(|q| q.name == name).unwrap_or(false);
+        if !is_same_quarter {
+            quarters.push(Quarter { name, movies: Vec::new() });
+        }
+
+        if let Some(quarter) = quarters.last_mut() {
+            quarter.movies.push(entry);
+        }
+    }
+
+    Outlook {
+        from: from.to_owned(),
+        to,
+        quarters,
+    }
+}
+
+pub fn render_terminal(outlook: &Outlook) -> String {
+    let mut out = format!("Outlook from {} to {}\n", outlook.from, outlook.to);
+
+    for quarter in outlook.quarters.iter() {
+        out.push_str(&format!("\n{} ({} movies)\n", quarter.name, quarter.movies.len()));
+
+        for movie in quarter.movies.iter() {
+            out.push_str(&format!("  {:<10}  {} ({})  [{}]  {}\n", display_date(movie),
+                                  movie.title, movie.genres, movie.source, movie.url));
+        }
+    }
+
+    out
+}
+
+fn display_date(movie: &OutlookEntry) -> &str {
+    if movie.date_tbd { "TBD" } else { &movie.release_date }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render_html(outlook: &Outlook) -> String {
+    let title = format!("Outlook from {} to {}", outlook.from, outlook.to);
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n</head>\n<body>\n", escape_html(&title)));
+    out.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+
+    for quarter in outlook.quarters.iter() {
+        out.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape_html(&quarter.name)));
+
+        for movie in quarter.movies.iter() {
+            out.push_str(&format!("<li>{} <a href=\"{}\">{}</a> ({}) [{}]</li>\n",
+                                  escape_html(display_date(movie)), escape_html(&movie.url),
+                                  escape_html(&movie.title), escape_html(&movie.genres),
+                                  movie.source));
+        }
+
+        out.push_str("</ul>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+pub fn render_json(outlook: &Outlook) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(outlook)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32, release_date: &str, date_tbd: bool) -> OutlookEntry {
+        OutlookEntry {
+            id,
+            title: format!("Movie {}", id),
+            release_date: release_date.to_owned(),
+            date_tbd,
+            genres: String::new(),
+            url: String::new(),
+            source: "upcoming",
+        }
+    }
+
+    fn ids(quarter: &Quarter) -> Vec<u32> {
+        quarter.movies.iter().map(|m| m.id).collect()
+    }
+
+    #[test]
+    fn build_groups_the_next_year_by_quarter() {
+        let outlook = build("2025-07-01", vec![
+            entry(1, "2025-08-10", false),
+            entry(2, "2025-07-01", false),
+            entry(3, "2025-06-30", false),
+            entry(4, "2026-07-01", false),
+            entry(7, "2026-01-05", false),
+        ]);
+
+        assert_eq!(outlook.from, "2025-07-01");
+        assert_eq!(outlook.to, "2026-07-01");
+
+        let names: Vec<&str> = outlook.quarters.iter().map(|q| q.name.as_str()).collect();
+        assert_eq!(names, vec!["2025 Q3", "2026 Q1"]);
+        assert_eq!(ids(&outlook.quarters[0]), vec![2, 1]);
+        assert_eq!(ids(&outlook.quarters[1]), vec![7]);
+    }
+
+    #[test]
+    fn build_lists_undated_movies_last() {
+        let outlook = build("2025-07-01", vec![
+            entry(6, "2025-12-31", true),
+            entry(5, "", true),
+            entry(8, "2027-12-31", true),
+            entry(1, "2025-08-10", false),
+        ]);
+
+        let last = outlook.quarters.last().unwrap();
+        assert_eq!(last.name, DATE_TBD_SECTION_NAME);
+        assert_eq!(ids(last), vec![5, 6]);
+        assert_eq!(outlook.quarters.len(), 2);
+    }
+
+    #[test]
+    fn build_keeps_a_movie_found_twice_once() {
+        let mut again = entry(2, "2025-07-01", false);
+        again.source = "company";
+
+        let outlook = build("2025-07-01", vec![entry(2, "2025-07-01", false), again]);
+
+        assert_eq!(outlook.quarters.len(), 1);
+        assert_eq!(outlook.quarters[0].movies.len(), 1);
+        assert_eq!(outlook.quarters[0].movies[0].source, "upcoming");
+    }
+
+    #[test]
+    fn one_year_after_keeps_the_day() {
+        assert_eq!(one_year_after("2025-07-01"), "2026-07-01");
+        assert_eq!(one_year_after("bad"), "bad");
+    }
+}
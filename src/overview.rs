@@ -0,0 +1,109 @@
+// How much of a movie overview to show, set by `--overview chars=200|full|none`.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverviewLength {
+    Chars(usize),
+    Full,
+    Hidden,
+}
+
+impl OverviewLength {
+    pub fn parse(s: &str) -> Option<OverviewLength> {
+        match s {
+            "full" => Some(OverviewLength::Full),
+            "none" => Some(OverviewLength::Hidden),
+            _ if s.starts_with("chars=") => {
+                s["chars=".len()..]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| if n > 0 { Some(OverviewLength::Chars(n)) } else { None })
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Returns the overview cut to the requested length, or None when it
+/// should not be shown at all.
+pub fn summarize(overview: &str, length: OverviewLength) -> Option<String> {
+    let overview = overview.trim();
+
+    if overview.is_empty() {
+        return None;
+    }
+
+    match length {
+        OverviewLength::Hidden => None,
+        OverviewLength::Full => Some(overview.to_owned()),
+        OverviewLength::Chars(max) => Some(truncate_at_sentence(overview, max)),
+    }
+}
+
+// Keep as many whole sentences as fit in `max` chars.  If not even the
+// first sentence fits, cut at a word boundary and mark the cut with "...".
+fn truncate_at_sentence(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_owned();
+    }
+
+    // byte offset just past the last char that still fits
+    let limit = text.char_indices().nth(max).map(|(i, _)| i).unwrap_or(text.len());
+    let head = &text[..limit];
+
+    let sentence_end = head
+        .char_indices()
+        .filter(|&(i, c)| {
+            (c == '.' || c == '!' || c == '?') &&
+                text[i + c.len_utf8()..].starts_with(char::is_whitespace)
+        })
+        .map(|(i, c)| i + c.len_utf8())
+        .next_back();
+
+    match sentence_end {
+        Some(end) => text[..end].to_owned(),
+        None => {
+            let cut = head.rfind(char::is_whitespace).unwrap_or(limit);
+            format!("{}...", head[..cut].trim_end())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_at_sentence_leaves_short_text_alone() {
+        assert_eq!(truncate_at_sentence("A heist. It goes wrong.", 23), "A heist. It goes wrong.");
+    }
+
+    #[test]
+    fn truncate_at_sentence_keeps_whole_sentences() {
+        assert_eq!(truncate_at_sentence("One. Two. Three.", 10), "One. Two.");
+        assert_eq!(truncate_at_sentence("Wow! Really? Yes indeed.", 13), "Wow! Really?");
+    }
+
+    #[test]
+    fn truncate_at_sentence_cuts_at_a_word_when_no_sentence_fits() {
+        assert_eq!(truncate_at_sentence("A very long sentence without end", 12),
+                   "A very long...");
+    }
+
+    #[test]
+    fn truncate_at_sentence_skips_dots_inside_words() {
+        assert_eq!(truncate_at_sentence("Rated 3.5 by critics. More to come.", 15),
+                   "Rated 3.5 by...");
+    }
+
+    #[test]
+    fn truncate_at_sentence_counts_chars_not_bytes() {
+        assert_eq!(truncate_at_sentence("Été. Ça va? Oui.", 6), "Été.");
+    }
+
+    #[test]
+    fn summarize_hides_empty_overviews() {
+        assert_eq!(summarize("  ", OverviewLength::Full), None);
+        assert_eq!(summarize("Plot.", OverviewLength::Hidden), None);
+        assert_eq!(summarize(" Plot. ", OverviewLength::Chars(100)), Some("Plot.".to_owned()));
+    }
+}
@@ -0,0 +1,96 @@
+// Poster thumbnails shown inline by `list --posters`, in terminals speaking
+// the inline images protocol of iTerm2, which WezTerm speaks as well.  TMD
+// posters are JPEGs, which those terminals take as they are; the kitty
+// protocol and sixel want PNGs or pixels, which would take an image decoder,
+// so other terminals, tmux and anything that is not a terminal get the
+// cards without posters.  A few thumbnails are downloaded at a time.  TMD
+// gives a new image a new path, so a cached thumbnail never needs asking for
+// again, and a thumbnail of some kilobytes is downloaded again rather than
+// resumed.
+
+use std::env;
+use std::io::IsTerminal;
+
+use crate::{http, links};
+use crate::AppError;
+
+/// Thumbnails downloaded at a time.
+pub const DOWNLOAD_CONCURRENCY: usize = 4;
+
+// lines of text a thumbnail takes, about a card's worth
+const THUMBNAIL_LINES: u32 = 6;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// terminals that set TERM_PROGRAM, or LC_TERMINAL which ssh passes on
+const INLINE_IMAGE_TERMINALS: &[&str] = &["iTerm.app", "iTerm2", "WezTerm"];
+
+/// Whether the terminal on stdout can show the thumbnails.
+pub fn terminal_shows_images() -> bool {
+    let named = |key: &str| {
+        env::var(key).is_ok_and(|name| INLINE_IMAGE_TERMINALS.contains(&name.as_str()))
+    };
+
+    std::io::stdout().is_terminal() && (named("TERM_PROGRAM") || named("LC_TERMINAL"))
+}
+
+/// The thumbnail of the poster, a small JPEG.
+pub async fn retrieve_thumbnail(poster_path: &str) -> Result<Vec<u8>, AppError> {
+    let url = links::tmd_thumbnail_url(poster_path);
+    let message = "Error: cannot get the poster ".to_string() + poster_path;
+
+    debug!("Getting the thumbnail {}", url);
+
+    http::check_response(http::client().get(&url).send().await, &message)
+        .await?
+        .bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| AppError::RestClientError(message, e, None))
+}
+
+// padded, as the terminals want it
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+/// The escape sequence showing the image on a line of its own.
+pub fn inline_image(image: &[u8]) -> String {
+    format!("\x1b]1337;File=inline=1;size={};height={};preserveAspectRatio=1:{}\x07",
+            image.len(), THUMBNAIL_LINES, base64(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_pads_the_last_chunk() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn inline_image_sends_the_file_base64_encoded() {
+        assert_eq!(inline_image(b"\xff\xd8\xff"),
+                   "\x1b]1337;File=inline=1;size=3;height=6;preserveAspectRatio=1:/9j/\x07");
+    }
+}
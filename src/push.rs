@@ -0,0 +1,101 @@
+// Pushes every new movie on its own to a phone through an ntfy topic or a
+// Gotify server, usually a self-hosted one, for when the check runs on a
+// machine nobody looks at.  Tapping the notification opens the movie page.
+
+use reqwest::RequestBuilder;
+use serde_json::{json, Value};
+use std::fs;
+
+use crate::config::{GotifyConfig, NtfyConfig};
+use crate::digest::DigestMovie;
+use crate::http;
+use crate::AppError;
+
+const NTFY_DEFAULT_SERVER: &str = "https://ntfy.sh";
+
+// the Android app only makes a sound from 4 up
+const GOTIFY_PRIORITY: u8 = 5;
+
+fn message(movie: &DigestMovie) -> String {
+    let mut message = format!("Release date: {}\n{}", movie.release_date, movie.genres);
+    if let Some(facts) = movie.facts() {
+        message.push('\n');
+        message.push_str(&facts);
+    }
+
+    message
+}
+
+// sends the push and returns the id the server gave the message
+async fn send(request: RequestBuilder, server: &str) -> Result<Option<String>, AppError> {
+    let message = "Error: cannot push new movie to ".to_string() + server;
+
+    let response: Value = http::check_post(request.send().await, &message)
+        .await?
+        .json()
+        .await
+        .map_err(|e| AppError::RestClientError(message, e.without_url(), None))?;
+
+    Ok(match response["id"] {
+        Value::String(ref id) => Some(id.clone()),
+        Value::Number(ref id) => Some(id.to_string()),
+        _ => None,
+    })
+}
+
+/// Publishes the movie to the ntfy topic, returns the message id.
+pub async fn push_to_ntfy(settings: &NtfyConfig, movie: &DigestMovie)
+                          -> Result<Option<String>, AppError> {
+    let server = settings.server.as_deref().unwrap_or(NTFY_DEFAULT_SERVER).trim_end_matches('/');
+
+    let mut body = json!({
+        "topic": settings.topic,
+        "title": movie.title,
+        "message": message(movie),
+        "click": movie.url,
+        "tags": ["movie_camera"],
+    });
+
+    if let Some(ref poster_url) = movie.poster_url {
+        body["attach"] = json!(poster_url);
+    }
+
+    let mut request = http::client().post(server).json(&body);
+
+    if let Some(ref path) = settings.token_file {
+        request = request.bearer_auth(fs::read_to_string(path)?.trim());
+    }
+
+    debug!("Pushing {} to ntfy topic {}", movie.id, settings.topic);
+
+    send(request, server).await
+}
+
+/// Sends the movie as a message of the Gotify application whose token is in
+/// the token file, returns the message id.
+pub async fn push_to_gotify(settings: &GotifyConfig, movie: &DigestMovie)
+                            -> Result<Option<String>, AppError> {
+    let server = settings.server.trim_end_matches('/');
+
+    let mut notification = json!({ "click": { "url": movie.url } });
+
+    if let Some(ref poster_url) = movie.poster_url {
+        notification["bigImageUrl"] = json!(poster_url);
+    }
+
+    let body = json!({
+        "title": movie.title,
+        "message": message(movie),
+        "priority": GOTIFY_PRIORITY,
+        "extras": { "client::notification": notification },
+    });
+
+    let request = http::client()
+        .post(format!("{}/message", server))
+        .header("X-Gotify-Key", fs::read_to_string(&settings.token_file)?.trim())
+        .json(&body);
+
+    debug!("Pushing {} to Gotify at {}", movie.id, server);
+
+    send(request, server).await
+}
@@ -0,0 +1,112 @@
+// Saves every new movie page on its own to a read-later service, Instapaper
+// or a Wallabag server, instead of opening it, so the new movies of a check
+// on a server can be read later on any device.  Instapaper takes the
+// account's username and password with every request, Wallabag wants an
+// OAuth token first, got with the password of the account and the client of
+// an api client created in its settings.
+
+use reqwest::Client;
+use serde_json::Value;
+use std::fs;
+
+use crate::config::{InstapaperConfig, WallabagConfig};
+use crate::digest::DigestMovie;
+use crate::http;
+use crate::AppError;
+
+const INSTAPAPER_ADD_URL: &str = "https://www.instapaper.com/api/add";
+
+// of the server url
+const WALLABAG_TOKEN_PATH: &str = "/oauth/v2/token";
+const WALLABAG_ENTRIES_PATH: &str = "/api/entries.json";
+
+// every movie saved to Wallabag gets it, to find them all
+const WALLABAG_TAG: &str = "movie_alert";
+
+#[derive(Deserialize)]
+struct WallabagToken {
+    access_token: String,
+}
+
+fn description(movie: &DigestMovie) -> String {
+    match movie.facts() {
+        Some(facts) => format!("Release date: {}, {}, {}", movie.release_date, movie.genres,
+                               facts),
+        None => format!("Release date: {}, {}", movie.release_date, movie.genres),
+    }
+}
+
+/// Saves the movie page to the Instapaper account.  Instapaper has no id
+/// for what it saved.
+pub async fn save_to_instapaper(settings: &InstapaperConfig, movie: &DigestMovie)
+                                -> Result<(), AppError> {
+    let password = match settings.password_file {
+        Some(ref path) => Some(fs::read_to_string(path)?.trim().to_owned()),
+        None => None,
+    };
+
+    let description = description(movie);
+    let request = http::client()
+        .post(INSTAPAPER_ADD_URL)
+        .basic_auth(&settings.username, password)
+        .form(&[("url", movie.url.as_str()), ("title", movie.title.as_str()),
+                ("selection", description.as_str())]);
+
+    debug!("Saving {} to Instapaper", movie.id);
+
+    http::check_response(request.send().await, "Error: cannot save new movie to Instapaper")
+        .await?;
+
+    Ok(())
+}
+
+/// The access token of the Wallabag account, good for an hour, which is
+/// longer than any run.
+pub async fn wallabag_token(client: &Client, settings: &WallabagConfig)
+                            -> Result<String, AppError> {
+    let server = settings.server.trim_end_matches('/');
+    let message = "Error: cannot log in to Wallabag at ".to_string() + server;
+
+    let client_secret = fs::read_to_string(&settings.client_secret_file)?;
+    let password = fs::read_to_string(&settings.password_file)?;
+
+    let request = client
+        .post(format!("{}{}", server, WALLABAG_TOKEN_PATH))
+        .form(&[("grant_type", "password"), ("client_id", settings.client_id.as_str()),
+                ("client_secret", client_secret.trim()),
+                ("username", settings.username.as_str()), ("password", password.trim())]);
+
+    http::check_response(request.send().await, &message)
+        .await?
+        .json::<WallabagToken>()
+        .await
+        .map(|token| token.access_token)
+        .map_err(|e| AppError::RestClientError(message, e, None))
+}
+
+/// Saves the movie page to Wallabag with the token, returns the id of the
+/// entry.
+pub async fn save_to_wallabag(client: &Client, settings: &WallabagConfig, token: &str,
+                              movie: &DigestMovie) -> Result<Option<String>, AppError> {
+    let server = settings.server.trim_end_matches('/');
+    let message = "Error: cannot save new movie to Wallabag at ".to_string() + server;
+
+    let request = client
+        .post(format!("{}{}", server, WALLABAG_ENTRIES_PATH))
+        .bearer_auth(token)
+        .form(&[("url", movie.url.as_str()), ("title", movie.title.as_str()),
+                ("tags", WALLABAG_TAG)]);
+
+    debug!("Saving {} to Wallabag at {}", movie.id, server);
+
+    let response: Value = http::check_response(request.send().await, &message)
+        .await?
+        .json()
+        .await
+        .map_err(|e| AppError::RestClientError(message, e, None))?;
+
+    Ok(match response["id"] {
+        Value::Number(ref id) => Some(id.to_string()),
+        _ => None,
+    })
+}
@@ -0,0 +1,132 @@
+// A year of movies watched, as remembered with `watched`: how many there
+// were, how many of them movie_alert had alerted on before, how they were
+// rated and which genres they were in, then every one of them by date.
+
+use std::collections::BTreeMap;
+
+use crate::store::Viewing;
+use crate::weights::split_genres;
+
+// genres named in the summary, the most watched first
+const TOP_GENRES: usize = 3;
+
+/// The recap of `year` for the terminal.
+pub fn render(year: i32, viewings: &[Viewing]) -> String {
+    if viewings.is_empty() {
+        return format!("Nothing watched in {}, mark movies with `watched <id>`\n", year);
+    }
+
+    let mut out = String::new();
+
+    let alerted = viewings.iter().filter(|viewing| viewing.alerted).count();
+    out.push_str(&format!("{}: {} watched, {} of them alerted on before\n", year,
+                          count(viewings.len()), alerted));
+
+    let ratings: Vec<u8> = viewings.iter().filter_map(|viewing| viewing.rating).collect();
+    if !ratings.is_empty() {
+        let average = ratings.iter().map(|&rating| rating as f64).sum::<f64>()
+            / ratings.len() as f64;
+        out.push_str(&format!("Average rating: {:.1} of {} rated\n", average, ratings.len()));
+    }
+
+    let genres = top_genres(viewings);
+    if !genres.is_empty() {
+        let genres: Vec<String> = genres
+            .iter()
+            .map(|(genre, watched)| format!("{} ({})", genre, watched))
+            .collect();
+        out.push_str(&format!("Top genres: {}\n", genres.join(", ")));
+    }
+
+    out.push('\n');
+
+    for viewing in viewings.iter() {
+        let title = if viewing.title.is_empty() {
+            format!("movie {}", viewing.id)
+        } else {
+            viewing.title.clone()
+        };
+        let rating = viewing.rating.map(|rating| format!("  {}/10", rating)).unwrap_or_default();
+
+        out.push_str(&format!("{}  {}{}{}\n", viewing.watched, title, rating,
+                              if viewing.alerted { "  [alerted]" } else { "" }));
+    }
+
+    out
+}
+
+// "1 movie", "12 movies"
+fn count(movies: usize) -> String {
+    if movies == 1 {
+        "1 movie".to_owned()
+    } else {
+        format!("{} movies", movies)
+    }
+}
+
+// the genres watched most, with how often, ties by name
+fn top_genres(viewings: &[Viewing]) -> Vec<(&str, usize)> {
+    let mut by_genre: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for viewing in viewings.iter() {
+        for genre in split_genres(&viewing.genres) {
+            *by_genre.entry(genre).or_default() += 1;
+        }
+    }
+
+    let mut genres: Vec<(&str, usize)> = by_genre.into_iter().collect();
+    genres.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    genres.truncate(TOP_GENRES);
+
+    genres
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewing(id: u32, title: &str, genres: &str, watched: &str, rating: Option<u8>,
+               alerted: bool) -> Viewing {
+        Viewing {
+            id,
+            title: title.to_owned(),
+            genres: genres.to_owned(),
+            watched: watched.to_owned(),
+            rating,
+            alerted,
+        }
+    }
+
+    #[test]
+    fn render_summarizes_the_year() {
+        let viewings = vec![
+            viewing(1, "Up", "Animation, Family", "2025-03-14", Some(9), true),
+            viewing(2, "Heat", "Crime", "2025-06-01", None, false),
+            viewing(3, "", "Animation", "2025-11-30", Some(6), true),
+        ];
+
+        assert_eq!(render(2025, &viewings),
+                   "2025: 3 movies watched, 2 of them alerted on before\n\
+                    Average rating: 7.5 of 2 rated\n\
+                    Top genres: Animation (2), Crime (1), Family (1)\n\
+                    \n\
+                    2025-03-14  Up  9/10  [alerted]\n\
+                    2025-06-01  Heat\n\
+                    2025-11-30  movie 3  6/10  [alerted]\n");
+    }
+
+    #[test]
+    fn render_leaves_out_what_is_not_known() {
+        let viewings = vec![viewing(7, "Heat", "", "2024-01-02", None, false)];
+
+        assert_eq!(render(2024, &viewings),
+                   "2024: 1 movie watched, 0 of them alerted on before\n\
+                    \n\
+                    2024-01-02  Heat\n");
+    }
+
+    #[test]
+    fn render_says_when_nothing_was_watched() {
+        assert!(render(2023, &[]).starts_with("Nothing watched in 2023"));
+    }
+}
@@ -0,0 +1,111 @@
+// Line delimited JSON-RPC 2.0 over stdin and stdout, one request or
+// response per line, so editors and launchers can keep one movie_alert
+// process around instead of starting a new one for every query.
+
+use serde_json::Value;
+use std::io::{self, Write};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::AppError;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// an AppError while handling the request
+const APP_ERROR: i64 = -32000;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    // without an id the request is a notification and gets no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    pub fn method_not_found(method: &str) -> Self {
+        RpcError { code: METHOD_NOT_FOUND, message: format!("unknown method: {}", method) }
+    }
+
+    pub fn invalid_params(message: &str) -> Self {
+        RpcError { code: INVALID_PARAMS, message: message.to_owned() }
+    }
+}
+
+impl ::std::convert::From<AppError> for RpcError {
+    fn from(e: AppError) -> Self {
+        RpcError { code: APP_ERROR, message: e.to_string() }
+    }
+}
+
+fn respond(id: Value, outcome: Result<Value, RpcError>) -> Response {
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+
+    Response { jsonrpc: "2.0", id, result, error }
+}
+
+/// Reads requests from stdin until it is closed, handing method name and
+/// params to `handle` and writing its outcome to stdout.
+pub async fn serve<F>(mut handle: F) -> Result<(), AppError>
+    where F: AsyncFnMut(&str, &Value) -> Result<Value, RpcError>
+{
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let stdout = io::stdout();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        trace!("Got rpc request: {}", line);
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(value) => match serde_json::from_value::<Request>(value) {
+                Ok(request) => {
+                    let outcome = handle(&request.method, &request.params).await;
+
+                    match request.id {
+                        Some(id) => respond(id, outcome),
+                        None => continue,
+                    }
+                },
+                Err(e) => {
+                    respond(Value::Null, Err(RpcError { code: INVALID_REQUEST,
+                                                        message: e.to_string() }))
+                },
+            },
+            Err(e) => respond(Value::Null, Err(RpcError { code: PARSE_ERROR,
+                                                          message: e.to_string() })),
+        };
+
+        let line = serde_json::to_string(&response)
+            .map_err(AppError::SerdeJsonSerializeError)?;
+
+        let mut out = stdout.lock();
+        writeln!(out, "{}", line)?;
+        out.flush()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+// A check run from code instead of the command line.  An `AlertRun` is built
+// with the genres, regions, least rating and notifiers it alerts with, which
+// are checked when it is built, and then run against a TMD client: the
+// upcoming movies of the genres are found, remembered in the store, and the
+// ones not alerted on or snoozed yet are sent to the notifiers like `check`
+// sends them.  What it found and alerted on comes back as a `RunReport`
+// instead of being printed.  Companies, watched lists, translations and the
+// other options of `check` stay with the command line.
+//
+//     let report = AlertRun::builder().genre("Animation").region("US").min_rating(6.0)
+//         .notifier(Stdout).build()?.run(&client, &mut watchdog).await?;
+
+use std::path::PathBuf;
+
+use crate::digest::DigestMovie;
+use crate::filter::{GenreMatch, get_genre_name_from_ids, get_upcoming_movies_by_filter,
+                    resolve_genre_ids};
+use crate::notifier::{Dispatcher, Notifier};
+use crate::state::{data_paths, load_state};
+use crate::store::MovieStore;
+use crate::tmdb::{Movie, TmdClient, retrieve_upcoming_snapshot};
+use crate::watchdog::RunWatchdog;
+use crate::{links, AppError, ErrorReport};
+
+/// What a run found and alerted on.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    /// Ids of the upcoming movies of the genres, in release order.
+    pub found: Vec<u32>,
+    /// Ids of the ones among them that were new and sent to the notifiers.
+    pub alerted: Vec<u32>,
+    /// What failed without failing the run, like one of the notifiers, whose
+    /// movies are tried again next run.
+    pub errors: ErrorReport,
+}
+
+/// Builds an `AlertRun`, see `AlertRun::builder`.
+pub struct AlertRunBuilder<'a> {
+    genres: Vec<String>,
+    genre_match: GenreMatch,
+    regions: Vec<String>,
+    min_rating: Option<f32>,
+    notifiers: Vec<Box<dyn Notifier + 'a>>,
+    store: Option<PathBuf>,
+}
+
+impl<'a> AlertRunBuilder<'a> {
+    /// Alerts on movies of the genre too, Animation when there is none.
+    pub fn genre(mut self, name: &str) -> Self {
+        self.genres.push(name.to_owned());
+
+        self
+    }
+
+    /// Alerts only on movies in every one of the genres, not in any.
+    pub fn all_genres(self) -> Self {
+        AlertRunBuilder { genre_match: GenreMatch::All, ..self }
+    }
+
+    /// Finds movies upcoming in the region too, e.g. "US", the region of
+    /// the client when there is none.
+    pub fn region(mut self, code: &str) -> Self {
+        self.regions.push(code.to_owned());
+
+        self
+    }
+
+    /// Alerts only on movies rated at least this, from 0 to 10.
+    pub fn min_rating(self, rating: f32) -> Self {
+        AlertRunBuilder { min_rating: Some(rating), ..self }
+    }
+
+    /// Alerts with the notifier too.
+    pub fn notifier(mut self, notifier: impl Notifier + 'a) -> Self {
+        self.notifiers.push(Box::new(notifier));
+
+        self
+    }
+
+    /// Keeps what was alerted on in this store instead of the one in the
+    /// data directory.
+    pub fn store(self, path: PathBuf) -> Self {
+        AlertRunBuilder { store: Some(path), ..self }
+    }
+
+    /// The run, unless the rating or a region is not one or there is no
+    /// notifier to alert with.
+    pub fn build(self) -> Result<AlertRun<'a>, AppError> {
+        if let Some(rating) = self.min_rating.filter(|rating| !(0.0..=10.0).contains(rating)) {
+            return Err(AppError::ArgumentError(
+                format!("the least rating is from 0 to 10, not {}", rating)));
+        }
+
+        if let Some(region) = self.regions.iter()
+            .find(|region| region.len() != 2 || !region.chars().all(|c| c.is_ascii_alphabetic())) {
+            return Err(AppError::ArgumentError(
+                format!("{} is not a region, which is two letters like US", region)));
+        }
+
+        if self.notifiers.is_empty() {
+            return Err(AppError::ArgumentError("an alert run needs a notifier".to_owned()));
+        }
+
+        let regions = self.regions.iter().map(|region| region.to_ascii_uppercase()).collect();
+
+        Ok(AlertRun { regions, builder: self })
+    }
+}
+
+/// A check, built with `AlertRun::builder`.
+pub struct AlertRun<'a> {
+    // uppercase
+    regions: Vec<String>,
+    builder: AlertRunBuilder<'a>,
+}
+
+impl<'a> AlertRun<'a> {
+    pub fn builder() -> AlertRunBuilder<'a> {
+        AlertRunBuilder {
+            genres: Vec::new(),
+            genre_match: GenreMatch::Any,
+            regions: Vec::new(),
+            min_rating: None,
+            notifiers: Vec::new(),
+            store: None,
+        }
+    }
+
+    /// Finds the upcoming movies and alerts on the new ones.  Failing to get
+    /// the movies or to keep them in the store fails the run, a notifier
+    /// that fails goes into the report.
+    pub async fn run(self, client: &TmdClient, watchdog: &mut RunWatchdog)
+                     -> Result<RunReport, AppError> {
+        let AlertRun { mut regions, builder } = self;
+        if regions.is_empty() {
+            regions.push(client.region().to_owned());
+        }
+
+        let snapshot = retrieve_upcoming_snapshot(client, &regions, 0, watchdog).await?;
+        let genre_ids = resolve_genre_ids(&builder.genres, &snapshot.genres)?;
+        let movies: Vec<&Movie> =
+            get_upcoming_movies_by_filter(&genre_ids, builder.genre_match, &snapshot.movies)
+                .into_iter()
+                .filter(|movie| builder.min_rating.is_none_or(|least| movie.vote_average >= least))
+                .collect();
+
+        let store = match builder.store {
+            Some(ref path) => MovieStore::open(path)?,
+            None => load_state(&data_paths()?)?.0,
+        };
+        let mut left_alone = store.opened_ids()?;
+        left_alone.extend(store.snoozed_ids()?);
+
+        let mut report = RunReport::default();
+        let mut dispatcher = Dispatcher::new(builder.notifiers, &store);
+
+        for movie in movies.into_iter() {
+            let genres = get_genre_name_from_ids(&movie.genre_ids, &snapshot.genres);
+            store.record_seen(movie.id, &movie.title, &movie.release_date, &genres)?;
+            report.found.push(movie.id);
+
+            if left_alone.insert(movie.id) {
+                watchdog.notification()?;
+
+                let digest = DigestMovie::new(movie, &snapshot.genres, store.groups_of(movie.id)?,
+                                              links::tmd_movie_url(movie.id), client.language());
+                dispatcher.alert(digest, &mut report.errors).await?;
+                report.alerted.push(movie.id);
+            }
+        }
+
+        // movies in the digest only count as alerted once it is sent
+        dispatcher.finish(&mut report.errors).await?;
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::Stdout;
+
+    #[test]
+    fn build_checks_the_rating_regions_and_notifiers() {
+        let run = AlertRun::builder().genre("Animation").region("us").min_rating(6.0)
+            .notifier(Stdout).build().unwrap();
+        assert_eq!(run.regions, vec!["US"]);
+
+        let failed = |builder: AlertRunBuilder| {
+            matches!(builder.build(), Err(AppError::ArgumentError(_)))
+        };
+        assert!(failed(AlertRun::builder().notifier(Stdout).min_rating(11.0)));
+        assert!(failed(AlertRun::builder().notifier(Stdout).region("USA")));
+        assert!(failed(AlertRun::builder().genre("Animation")));
+    }
+}
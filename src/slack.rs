@@ -0,0 +1,61 @@
+// Posts all new movies of a run to a Slack incoming webhook, a section block
+// per movie with its poster next to it.  Slack takes at most 50 blocks in a
+// message, so the digest of a long run comes in parts of MOVIES_PER_MESSAGE.
+
+use serde_json::{json, Value};
+
+use crate::digest::{DigestMovie, DigestPart};
+use crate::http;
+use crate::AppError;
+
+/// Movies in a message, every one is a section and a divider.
+pub const MOVIES_PER_MESSAGE: usize = 25;
+
+/// Messages a minute an incoming webhook takes, about one a second.
+pub const MESSAGES_PER_MINUTE: u32 = 60;
+
+fn movie_blocks(movie: &DigestMovie) -> Vec<Value> {
+    // <, > and & are the only characters Slack wants escaped
+    let title = movie.title.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let mut text = format!("*<{}|{}>*\n{}\nRelease date: {}", movie.url, title, movie.genres,
+                           movie.release_date);
+    if let Some(facts) = movie.facts() {
+        text.push('\n');
+        text.push_str(&facts);
+    }
+
+    let mut section = json!({
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": text },
+    });
+
+    if let Some(ref poster_url) = movie.poster_url {
+        section["accessory"] = json!({
+            "type": "image",
+            "image_url": poster_url,
+            "alt_text": movie.title,
+        });
+    }
+
+    vec![section, json!({ "type": "divider" })]
+}
+
+/// Posts the movies in one message, at most MOVIES_PER_MESSAGE of them.
+pub async fn post_movies(webhook_url: &str, movies: &[DigestMovie], part: Option<&DigestPart>)
+                         -> Result<(), AppError> {
+    let blocks: Vec<Value> = movies.iter().flat_map(movie_blocks).collect();
+
+    // the text is what notifications show
+    let message = json!({
+        "text": format!("{} new upcoming movies{}", movies.len(),
+                        part.map(DigestPart::suffix).unwrap_or_default()),
+        "blocks": blocks,
+    });
+
+    debug!("Posting {} movies to Slack", movies.len());
+
+    let sent = http::client().post(webhook_url).json(&message).send().await;
+    http::check_post(sent, "Error: cannot post new movies to Slack").await?;
+
+    Ok(())
+}
@@ -0,0 +1,262 @@
+// Where the state of movie_alert lives on disk and loading it: the store of
+// opened movies, the members of watched lists and the offline snapshot,
+// together with moving over the files of older versions.
+
+use directories::ProjectDirs;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::store::MovieStore;
+use crate::tmdb::{UpcomingSnapshot, release_order};
+use crate::AppError;
+
+// all in the data directory, e.g. ~/.local/share/movie_alert
+
+/// Opened movies and what was known about them, see store.rs.
+pub const STORE_FILE_NAME: &str = "movies.db";
+
+// last seen members of every watched TMD list, keyed by list id
+const LISTS_FILE_NAME: &str = "lists.json";
+
+// genres and upcoming movies of the last online run, used by --offline
+const SNAPSHOT_FILE_NAME: &str = "upcoming.json";
+
+// where older versions kept the files above, right in the home directory.
+// They are moved to the data directory on the first run.
+const LEGACY_STORE_FILE_PATH: &str = ".movie_alert.db";
+const LEGACY_LISTS_FILE_PATH: &str = ".movie_alert_lists";
+const LEGACY_SNAPSHOT_FILE_PATH: &str = ".movie_alert_upcoming";
+
+// the json data file and journal of opened movies of still older versions,
+// in the home directory too.  They are moved into the store on the first
+// run and the data file is kept next to them, renamed, as a backup.
+const DATA_FILE_PATH: &str = ".movie_alert";
+const JOURNAL_FILE_PATH: &str = ".movie_alert.journal";
+const MIGRATED_DATA_FILE_PATH: &str = ".movie_alert.migrated";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct JournalEntry {
+    opened: u32,
+}
+
+pub struct DataPaths {
+    pub dir: PathBuf,
+    pub store: PathBuf,
+    pub data: PathBuf,
+    pub journal: PathBuf,
+    pub migrated_data: PathBuf,
+    pub lists: PathBuf,
+    pub snapshot: PathBuf,
+    // false for --dry-run, which reads the files of older versions where
+    // they are and leaves them there
+    pub migrate: bool,
+}
+
+/// The platform's directories for movie_alert, $XDG_DATA_HOME/movie_alert
+/// and $XDG_CONFIG_HOME/movie_alert on Linux.
+pub fn project_dirs() -> Result<ProjectDirs, AppError> {
+    ProjectDirs::from("", "", "movie_alert").ok_or(AppError::HomeDirectoryError)
+}
+
+/// Paths of all state files, moving the ones of older versions into the
+/// data directory first.
+pub fn data_paths() -> Result<DataPaths, AppError> {
+    find_data_paths(true)
+}
+
+/// Same as `data_paths` for --dry-run, which moves nothing: the files of
+/// older versions are read where they are, until a real run moves them.
+pub fn dry_run_data_paths() -> Result<DataPaths, AppError> {
+    find_data_paths(false)
+}
+
+fn find_data_paths(migrate: bool) -> Result<DataPaths, AppError> {
+    let home = env::home_dir().ok_or(AppError::HomeDirectoryError)?;
+    let data_dir = project_dirs()?.data_dir().to_owned();
+
+    fs::create_dir_all(&data_dir)?;
+
+    let mut paths = DataPaths {
+        dir: data_dir.clone(),
+        store: data_dir.join(STORE_FILE_NAME),
+        data: home.join(DATA_FILE_PATH),
+        journal: home.join(JOURNAL_FILE_PATH),
+        migrated_data: home.join(MIGRATED_DATA_FILE_PATH),
+        lists: data_dir.join(LISTS_FILE_NAME),
+        snapshot: data_dir.join(SNAPSHOT_FILE_NAME),
+        migrate,
+    };
+
+    if migrate {
+        move_legacy_file(&home.join(LEGACY_STORE_FILE_PATH), &paths.store)?;
+        move_legacy_file(&home.join(LEGACY_LISTS_FILE_PATH), &paths.lists)?;
+        move_legacy_file(&home.join(LEGACY_SNAPSHOT_FILE_PATH), &paths.snapshot)?;
+    } else {
+        use_legacy_file(&home.join(LEGACY_STORE_FILE_PATH), &mut paths.store);
+        use_legacy_file(&home.join(LEGACY_LISTS_FILE_PATH), &mut paths.lists);
+        use_legacy_file(&home.join(LEGACY_SNAPSHOT_FILE_PATH), &mut paths.snapshot);
+    }
+    debug!("Store path is: {:?}", paths.store);
+
+    Ok(paths)
+}
+
+// the file of an older version in place of the one in the data directory,
+// as long as there is none there yet, which is the one `move_legacy_file`
+// would keep
+fn use_legacy_file(legacy: &Path, path: &mut PathBuf) {
+    if legacy.is_file() && !path.exists() {
+        info!("Reading {} where it is, --dry-run moves nothing", legacy.display());
+        *path = legacy.to_owned();
+    }
+}
+
+// a file of an older version found in the home directory, a file already in
+// the data directory is never replaced
+fn move_legacy_file(legacy: &Path, path: &Path) -> Result<(), AppError> {
+    if !legacy.is_file() {
+        return Ok(());
+    }
+
+    if path.exists() {
+        warn!("Ignoring {}, {} is used instead", legacy.display(), path.display());
+        return Ok(());
+    }
+
+    // a rename cannot cross file systems, e.g. to a separately mounted home
+    if fs::rename(legacy, path).is_err() {
+        fs::copy(legacy, path)?;
+        fs::remove_file(legacy)?;
+    }
+
+    info!("Moved {} to {}", legacy.display(), path.display());
+
+    Ok(())
+}
+
+/// The store and the ids of the opened movies in it.
+pub fn load_state(paths: &DataPaths) -> Result<(MovieStore, HashSet<u32>), AppError> {
+    let mut store = MovieStore::open(&paths.store)?;
+    let opened_movie_set = load_opened_movie_set(&mut store, paths)?;
+
+    Ok((store, opened_movie_set))
+}
+
+// moves the opened movies of an old json data file and its journal into the
+// store first, the first time this version runs.  A dry run only adds them
+// to the ones in the store.
+fn load_opened_movie_set(store: &mut MovieStore, paths: &DataPaths)
+                         -> Result<HashSet<u32>, AppError> {
+    if paths.data.is_file() || paths.journal.is_file() {
+        let mut legacy_set = load_legacy_data_file(&paths.data)?;
+        replay_journal(&paths.journal, &mut legacy_set)?;
+
+        if !paths.migrate {
+            let mut opened_movie_set = store.opened_ids()?;
+            opened_movie_set.extend(legacy_set);
+
+            return Ok(opened_movie_set);
+        }
+
+        store.import_opened(&legacy_set)?;
+        info!("Moved {} opened movies into {}", legacy_set.len(), paths.store.display());
+
+        if paths.data.is_file() {
+            fs::rename(&paths.data, &paths.migrated_data)?;
+        }
+        if paths.journal.is_file() {
+            fs::remove_file(&paths.journal)?;
+        }
+    }
+
+    store.opened_ids()
+}
+
+fn load_legacy_data_file(path: &PathBuf) -> Result<HashSet<u32>, AppError> {
+    if path.is_file() && path.exists() {
+        let file = File::open(path)?;
+
+        debug!("Data file found, loading...");
+
+        serde_json::from_reader::<_,HashSet<u32>>(file)
+            .map_err(AppError::SerdeJsonDeserializeError)
+    } else {
+        debug!("Data file does not exist");
+        Ok(HashSet::new())
+    }
+}
+
+// fold in movies opened by a previous run that did not finish
+fn replay_journal(path: &PathBuf, opened_set: &mut HashSet<u32>) -> Result<(), AppError> {
+    if !path.is_file() {
+        return Ok(());
+    }
+
+    debug!("Journal file found, replaying...");
+
+    let reader = BufReader::new(File::open(path)?);
+
+    for line in reader.lines() {
+        let line = line?;
+
+        match serde_json::from_str::<JournalEntry>(&line) {
+            Ok(entry) => {
+                opened_set.insert(entry.opened);
+            },
+            // the last line can be cut short by a crash
+            Err(e) => warn!("Skipping unreadable journal line {:?}: {}", line, e),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn load_snapshot(path: &PathBuf) -> Result<UpcomingSnapshot, AppError> {
+    if !path.is_file() {
+        return Err(AppError::OfflineError(
+            "nothing has been fetched yet, run once without --offline".to_owned()));
+    }
+
+    debug!("Loading upcoming movies from {:?}", path);
+
+    let file = File::open(path)?;
+
+    let mut snapshot = serde_json::from_reader::<_, UpcomingSnapshot>(file)
+        .map_err(AppError::SerdeJsonDeserializeError)?;
+
+    // snapshots of older versions left movies on the same date in api order
+    snapshot.movies.sort_by(release_order);
+
+    Ok(snapshot)
+}
+
+pub fn save_snapshot(snapshot: &UpcomingSnapshot, path: &PathBuf) -> Result<(), AppError> {
+    let mut file = File::create(path)?;
+
+    serde_json::to_writer(&file, snapshot).map_err(AppError::SerdeJsonSerializeError)?;
+
+    file.flush().map_err(AppError::IOError)
+}
+
+pub fn load_list_members(path: &PathBuf) -> Result<BTreeMap<u32, BTreeSet<u32>>, AppError> {
+    if path.is_file() {
+        let file = File::open(path)?;
+
+        serde_json::from_reader::<_, BTreeMap<u32, BTreeSet<u32>>>(file)
+            .map_err(AppError::SerdeJsonDeserializeError)
+    } else {
+        Ok(BTreeMap::new())
+    }
+}
+
+pub fn save_list_members(members: &BTreeMap<u32, BTreeSet<u32>>, path: &PathBuf)
+                         -> Result<(), AppError> {
+    let mut file = File::create(path)?;
+
+    serde_json::to_writer(&file, members).map_err(AppError::SerdeJsonSerializeError)?;
+
+    file.flush().map_err(AppError::IOError)
+}
@@ -0,0 +1,721 @@
+// Opened movies are kept in an SQLite database, one row for every movie
+// seen with what was known about it at the time, next to a history of every
+// alert delivered about it, the channels that already got the current alert
+// about it, the named groups it was put in, until when it is snoozed, every
+// time it was opened by hand, whether it was alerted on once announced for a
+// later year and once in theaters, its last production status and every time
+// it was watched, with its rating.  Every change is committed on its own, so
+// a run that is cut short loses nothing.  What `state forget`, `state reset`
+// and `group remove` take away is moved to a trash under the command, and
+// `undo` puts back what the last of them took; the last ten are kept.
+
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::clock::{Clock, SystemClock};
+use crate::AppError;
+
+// stored as user_version and bumped with every change of the schema.  Every
+// table is created if it does not exist, so a new table needs no migration;
+// a new or changed column of an existing table would need an ALTER TABLE
+// for stores of older versions, which none of the changes so far did.
+const SCHEMA_VERSION: i32 = 11;
+
+const CREATE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS movies (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL DEFAULT '',
+        release_date TEXT NOT NULL DEFAULT '',
+        genres TEXT NOT NULL DEFAULT '',
+        first_seen INTEGER NOT NULL,
+        alerted INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS deliveries (
+        movie_id INTEGER NOT NULL,
+        channel TEXT NOT NULL,
+        status TEXT NOT NULL,
+        message_id TEXT,
+        sent INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS deliveries_movie_id ON deliveries (movie_id);
+    CREATE TABLE IF NOT EXISTS groups (
+        name TEXT NOT NULL,
+        movie_id INTEGER NOT NULL,
+        PRIMARY KEY (name, movie_id)
+    );
+    CREATE TABLE IF NOT EXISTS snoozes (
+        movie_id INTEGER PRIMARY KEY,
+        until INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS opens (
+        movie_id INTEGER NOT NULL,
+        opened INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS playing (
+        movie_id INTEGER PRIMARY KEY,
+        alerted INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS announced (
+        movie_id INTEGER PRIMARY KEY,
+        alerted INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS delivered (
+        movie_id INTEGER NOT NULL,
+        alert TEXT NOT NULL,
+        channel TEXT NOT NULL,
+        PRIMARY KEY (movie_id, alert, channel)
+    );
+    CREATE TABLE IF NOT EXISTS watched (
+        movie_id INTEGER NOT NULL,
+        watched TEXT NOT NULL,
+        rating INTEGER,
+        PRIMARY KEY (movie_id, watched)
+    );
+    CREATE TABLE IF NOT EXISTS statuses (
+        movie_id INTEGER PRIMARY KEY,
+        status TEXT NOT NULL,
+        previous TEXT,
+        changed INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS trash (
+        id INTEGER PRIMARY KEY,
+        command TEXT NOT NULL,
+        trashed INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS trashed_alerts (
+        trash_id INTEGER NOT NULL,
+        movie_id INTEGER NOT NULL,
+        alerted INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS trashed_deliveries (
+        trash_id INTEGER NOT NULL,
+        movie_id INTEGER NOT NULL,
+        alert TEXT NOT NULL,
+        channel TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS trashed_groups (
+        trash_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        movie_id INTEGER NOT NULL
+    );";
+
+/// The alert about a movie coming up, see `MovieStore::delivered_channels`.
+pub const UPCOMING_ALERT: &str = "upcoming";
+/// The alert about a movie in theaters.
+pub const PLAYING_ALERT: &str = "playing";
+/// The alert about a movie announced for a later year.
+pub const ANNOUNCED_ALERT: &str = "announced";
+
+const INSERT_ALERTED: &str = "
+    INSERT INTO movies (id, first_seen, alerted) VALUES (?1, ?2, ?2)
+    ON CONFLICT (id) DO UPDATE SET alerted = COALESCE(alerted, excluded.alerted)";
+
+// destructive commands kept in the trash, older ones are emptied from it
+const TRASH_KEPT: i64 = 10;
+
+// tables of the rows in the trash, by the command that took them away
+const TRASHED_TABLES: &[&str] = &["trashed_alerts", "trashed_deliveries", "trashed_groups"];
+
+const TRASH_ALERTS: &str =
+    "INSERT INTO trashed_alerts (trash_id, movie_id, alerted)
+     SELECT ?1, id, alerted FROM movies WHERE alerted IS NOT NULL";
+const TRASH_DELIVERIES: &str =
+    "INSERT INTO trashed_deliveries (trash_id, movie_id, alert, channel)
+     SELECT ?1, movie_id, alert, channel FROM delivered WHERE alert = ?2";
+
+/// One alert about a movie, sent or not.
+pub struct Delivery {
+    // browser, desktop, email, webhook, slack, discord, ntfy, gotify,
+    // instapaper or wallabag
+    pub channel: String,
+    // "sent", or "failed: " and the error
+    pub status: String,
+    // notification id or email Message-ID, when the channel has one
+    pub message_id: Option<String>,
+    // UTC, like "2025-07-18 08:30:00"
+    pub sent: String,
+}
+
+/// One time a movie was watched, see `MovieStore::watched_in`.
+pub struct Viewing {
+    pub id: u32,
+    // empty for a movie never seen in the results
+    pub title: String,
+    // "Animation, Family"
+    pub genres: String,
+    // "2025-07-18"
+    pub watched: String,
+    // from 1 to 10
+    pub rating: Option<u8>,
+    // whether it was alerted on by then
+    pub alerted: bool,
+}
+
+/// The production status of a movie, see `MovieStore::record_status`.
+pub struct Status {
+    // "Post Production"
+    pub status: String,
+    // the one before, when it changed since it was first seen
+    pub previous: Option<String>,
+    // UTC, like "2025-07-18 08:30:00"
+    pub changed: String,
+}
+
+pub struct MovieStore {
+    conn: Connection,
+    clock: Arc<dyn Clock>,
+}
+
+// starts moving the rows a destructive command takes away to the trash,
+// returns the id they go under
+fn trash(transaction: &Transaction, command: &str, now: i64) -> Result<i64, AppError> {
+    transaction.execute("INSERT INTO trash (command, trashed) VALUES (?1, ?2)", (command, now))?;
+    let trash_id = transaction.last_insert_rowid();
+    empty_trash(transaction, "<= ?1", trash_id - TRASH_KEPT)?;
+
+    Ok(trash_id)
+}
+
+// removes the commands of the trash whose id is `condition` to `trash_id`,
+// with their rows
+fn empty_trash(transaction: &Transaction, condition: &str, trash_id: i64)
+               -> Result<(), AppError> {
+    for table in TRASHED_TABLES.iter() {
+        transaction.execute(&format!("DELETE FROM {} WHERE trash_id {}", table, condition),
+                            (trash_id,))?;
+    }
+    transaction.execute(&format!("DELETE FROM trash WHERE id {}", condition), (trash_id,))?;
+
+    Ok(())
+}
+
+impl MovieStore {
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        MovieStore::open_with_clock(path, Arc::new(SystemClock))
+    }
+
+    /// The store telling the time by `clock`, which every time it records or
+    /// compares with is taken from.
+    pub fn open_with_clock(path: &Path, clock: Arc<dyn Clock>) -> Result<Self, AppError> {
+        debug!("Opening store: {:?}", path);
+
+        let conn = Connection::open(path)?;
+        conn.execute_batch(CREATE_SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(MovieStore { conn, clock })
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    // unix seconds
+    fn now(&self) -> i64 {
+        self.clock.unix_secs()
+    }
+
+    /// Ids of every movie an alert was sent for.
+    pub fn opened_ids(&self) -> Result<HashSet<u32>, AppError> {
+        let mut statement = self.conn
+            .prepare("SELECT id FROM movies WHERE alerted IS NOT NULL")?;
+        let rows = statement.query_map([], |row| row.get::<_, u32>(0))?;
+
+        let mut ids = HashSet::new();
+        for id in rows {
+            ids.insert(id?);
+        }
+
+        Ok(ids)
+    }
+
+    /// Remembers a movie found in the results, keeping the time it was
+    /// first seen and refreshing everything else.
+    pub fn record_seen(&self, id: u32, title: &str, release_date: &str, genres: &str)
+                       -> Result<(), AppError> {
+        self.conn.execute(
+            "INSERT INTO movies (id, title, release_date, genres, first_seen)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (id) DO UPDATE SET title = excluded.title,
+                 release_date = excluded.release_date, genres = excluded.genres",
+            (id, title, release_date, genres, self.now()))?;
+
+        Ok(())
+    }
+
+    /// Seconds since the movie was first seen in the results, None when it
+    /// never was.
+    pub fn listed_secs(&self, id: u32) -> Result<Option<i64>, AppError> {
+        let listed = self.conn
+            .query_row("SELECT ?2 - first_seen FROM movies WHERE id = ?1", (id, self.now()),
+                       |row| row.get::<_, i64>(0))
+            .optional()?;
+
+        Ok(listed)
+    }
+
+    pub fn record_alerted(&self, id: u32) -> Result<(), AppError> {
+        self.conn.execute(INSERT_ALERTED, (id, self.now()))?;
+
+        Ok(())
+    }
+
+    pub fn record_delivery(&self, id: u32, channel: &str, status: &str,
+                           message_id: Option<&str>) -> Result<(), AppError> {
+        self.conn.execute(
+            "INSERT INTO deliveries (movie_id, channel, status, message_id, sent)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            (id, channel, status, message_id, self.now()))?;
+
+        Ok(())
+    }
+
+    /// Remembers that the user opened a movie, with `open` or from the tui.
+    pub fn record_open(&self, id: u32) -> Result<(), AppError> {
+        self.conn.execute("INSERT INTO opens (movie_id, opened) VALUES (?1, ?2)",
+                          (id, self.now()))?;
+
+        Ok(())
+    }
+
+    /// Ids of every movie the user opened.
+    pub fn user_opened_ids(&self) -> Result<HashSet<u32>, AppError> {
+        let mut statement = self.conn.prepare("SELECT DISTINCT movie_id FROM opens")?;
+        let rows = statement.query_map([], |row| row.get::<_, u32>(0))?;
+
+        let mut ids = HashSet::new();
+        for id in rows {
+            ids.insert(id?);
+        }
+
+        Ok(ids)
+    }
+
+    /// Id and genre names, e.g. "Animation, Family", of every movie an alert
+    /// was sent for.
+    pub fn alerted_genres(&self) -> Result<Vec<(u32, String)>, AppError> {
+        let mut statement = self.conn
+            .prepare("SELECT id, genres FROM movies WHERE alerted IS NOT NULL ORDER BY id")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, u32>(0)?,
+                                                      row.get::<_, String>(1)?)))?;
+
+        let mut movies = Vec::new();
+        for movie in rows {
+            movies.push(movie?);
+        }
+
+        Ok(movies)
+    }
+
+    /// Every delivery about a movie, oldest first.
+    pub fn deliveries(&self, id: u32) -> Result<Vec<Delivery>, AppError> {
+        let mut statement = self.conn.prepare(
+            "SELECT channel, status, message_id, datetime(sent, 'unixepoch') FROM deliveries
+             WHERE movie_id = ?1 ORDER BY sent, rowid")?;
+        let rows = statement.query_map((id,), |row| {
+            Ok(Delivery {
+                channel: row.get(0)?,
+                status: row.get(1)?,
+                message_id: row.get(2)?,
+                sent: row.get(3)?,
+            })
+        })?;
+
+        let mut deliveries = Vec::new();
+        for delivery in rows {
+            deliveries.push(delivery?);
+        }
+
+        Ok(deliveries)
+    }
+
+    /// Remembers that the alert about a movie went out through the channel,
+    /// so it is not sent there again while other channels are still tried.
+    pub fn record_delivered(&self, id: u32, alert: &str, channel: &str) -> Result<(), AppError> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO delivered (movie_id, alert, channel) VALUES (?1, ?2, ?3)",
+            (id, alert, channel))?;
+
+        Ok(())
+    }
+
+    /// Channels the alert about a movie went out through, since it was last
+    /// forgotten.
+    pub fn delivered_channels(&self, id: u32, alert: &str) -> Result<HashSet<String>, AppError> {
+        let mut statement = self.conn
+            .prepare("SELECT channel FROM delivered WHERE movie_id = ?1 AND alert = ?2")?;
+        let rows = statement.query_map((id, alert), |row| row.get::<_, String>(0))?;
+
+        let mut channels = HashSet::new();
+        for channel in rows {
+            channels.insert(channel?);
+        }
+
+        Ok(channels)
+    }
+
+    /// Puts the movies in the group, returns how many were not in it yet.
+    pub fn add_to_group(&mut self, name: &str, ids: &[u32]) -> Result<usize, AppError> {
+        let transaction = self.conn.transaction()?;
+        let mut added = 0;
+
+        for &id in ids.iter() {
+            added += transaction.execute(
+                "INSERT OR IGNORE INTO groups (name, movie_id) VALUES (?1, ?2)", (name, id))?;
+        }
+
+        transaction.commit()?;
+
+        Ok(added)
+    }
+
+    /// Takes the movies out of the group, returns how many were in it.
+    pub fn remove_from_group(&mut self, name: &str, ids: &[u32]) -> Result<usize, AppError> {
+        let now = self.now();
+        let transaction = self.conn.transaction()?;
+        let trash_id = trash(&transaction, &format!("group remove {}", name), now)?;
+        let mut removed = 0;
+
+        for &id in ids.iter() {
+            transaction.execute(
+                "INSERT INTO trashed_groups (trash_id, name, movie_id)
+                 SELECT ?1, name, movie_id FROM groups WHERE name = ?2 AND movie_id = ?3",
+                (trash_id, name, id))?;
+            removed += transaction.execute(
+                "DELETE FROM groups WHERE name = ?1 AND movie_id = ?2", (name, id))?;
+        }
+
+        if removed > 0 {
+            transaction.commit()?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Every group by name, with the ids of its movies.
+    pub fn groups(&self) -> Result<BTreeMap<String, Vec<u32>>, AppError> {
+        let mut statement = self.conn
+            .prepare("SELECT name, movie_id FROM groups ORDER BY name, movie_id")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?,
+                                                      row.get::<_, u32>(1)?)))?;
+
+        let mut groups: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for row in rows {
+            let (name, id) = row?;
+            groups.entry(name).or_default().push(id);
+        }
+
+        Ok(groups)
+    }
+
+    /// Names of the groups a movie is in.
+    pub fn groups_of(&self, id: u32) -> Result<Vec<String>, AppError> {
+        let mut statement = self.conn
+            .prepare("SELECT name FROM groups WHERE movie_id = ?1 ORDER BY name")?;
+        let rows = statement.query_map((id,), |row| row.get::<_, String>(0))?;
+
+        let mut names = Vec::new();
+        for name in rows {
+            names.push(name?);
+        }
+
+        Ok(names)
+    }
+
+    /// Remembers that an alert was sent for a movie now in theaters.
+    pub fn record_playing(&self, id: u32) -> Result<(), AppError> {
+        self.conn.execute("INSERT OR IGNORE INTO playing (movie_id, alerted) VALUES (?1, ?2)",
+                          (id, self.now()))?;
+
+        Ok(())
+    }
+
+    /// Ids of every movie an alert was sent for once it was in theaters.
+    pub fn playing_ids(&self) -> Result<HashSet<u32>, AppError> {
+        let mut statement = self.conn.prepare("SELECT movie_id FROM playing")?;
+        let rows = statement.query_map([], |row| row.get::<_, u32>(0))?;
+
+        let mut ids = HashSet::new();
+        for id in rows {
+            ids.insert(id?);
+        }
+
+        Ok(ids)
+    }
+
+    /// Remembers that an alert was sent for a movie announced for a later
+    /// year, which leaves the alert about it coming up to be sent.
+    pub fn record_announced(&self, id: u32) -> Result<(), AppError> {
+        self.conn.execute("INSERT OR IGNORE INTO announced (movie_id, alerted) VALUES (?1, ?2)",
+                          (id, self.now()))?;
+
+        Ok(())
+    }
+
+    /// Ids of every movie an alert was sent for as announced.
+    pub fn announced_ids(&self) -> Result<HashSet<u32>, AppError> {
+        let mut statement = self.conn.prepare("SELECT movie_id FROM announced")?;
+        let rows = statement.query_map([], |row| row.get::<_, u32>(0))?;
+
+        let mut ids = HashSet::new();
+        for id in rows {
+            ids.insert(id?);
+        }
+
+        Ok(ids)
+    }
+
+    /// Remembers the production status of the movie, returns the one before
+    /// when it changed.
+    pub fn record_status(&self, id: u32, status: &str) -> Result<Option<String>, AppError> {
+        let before = self.status_of(id)?.map(|before| before.status);
+        if before.as_deref() == Some(status) {
+            return Ok(None);
+        }
+
+        self.conn.execute(
+            "INSERT INTO statuses (movie_id, status, changed) VALUES (?1, ?2, ?3)
+             ON CONFLICT (movie_id) DO UPDATE SET previous = status, status = excluded.status,
+                 changed = excluded.changed",
+            (id, status, self.now()))?;
+
+        Ok(before)
+    }
+
+    pub fn status_of(&self, id: u32) -> Result<Option<Status>, AppError> {
+        let status = self.conn
+            .query_row(
+                "SELECT status, previous, datetime(changed, 'unixepoch') FROM statuses
+                 WHERE movie_id = ?1",
+                [id],
+                |row| Ok(Status { status: row.get(0)?, previous: row.get(1)?,
+                                  changed: row.get(2)? }))
+            .optional()?;
+
+        Ok(status)
+    }
+
+    /// Keeps a movie from being alerted on for the next `secs` seconds.
+    pub fn snooze(&self, id: u32, secs: i64) -> Result<(), AppError> {
+        self.conn.execute(
+            "INSERT INTO snoozes (movie_id, until) VALUES (?1, ?2)
+             ON CONFLICT (movie_id) DO UPDATE SET until = excluded.until",
+            (id, self.now() + secs))?;
+
+        Ok(())
+    }
+
+    /// Ids of every movie snoozed until some time still to come.
+    pub fn snoozed_ids(&self) -> Result<HashSet<u32>, AppError> {
+        let mut statement = self.conn
+            .prepare("SELECT movie_id FROM snoozes WHERE until > ?1")?;
+        let rows = statement.query_map((self.now(),), |row| row.get::<_, u32>(0))?;
+
+        let mut ids = HashSet::new();
+        for id in rows {
+            ids.insert(id?);
+        }
+
+        Ok(ids)
+    }
+
+    /// Remembers that the movie was watched on `date`, today when it is not
+    /// given, returns the date.  Watching it again on the same day only
+    /// changes the rating, if one is given.
+    pub fn record_watched(&self, id: u32, date: Option<&str>, rating: Option<u8>)
+                          -> Result<String, AppError> {
+        let watched = self.conn.query_row(
+            "INSERT INTO watched (movie_id, watched, rating)
+             VALUES (?1, COALESCE(?2, date('now', 'localtime')), ?3)
+             ON CONFLICT (movie_id, watched)
+             DO UPDATE SET rating = COALESCE(excluded.rating, rating)
+             RETURNING watched",
+            (id, date, rating), |row| row.get::<_, String>(0))?;
+
+        Ok(watched)
+    }
+
+    /// Every time a movie was watched in `year`, in order.
+    pub fn watched_in(&self, year: i32) -> Result<Vec<Viewing>, AppError> {
+        let mut statement = self.conn.prepare(
+            "SELECT w.movie_id, COALESCE(m.title, ''), COALESCE(m.genres, ''), w.watched,
+                    w.rating, COALESCE(date(m.alerted, 'unixepoch', 'localtime') <= w.watched, 0)
+             FROM watched w LEFT JOIN movies m ON m.id = w.movie_id
+             WHERE w.watched LIKE ?1 || '-%' ORDER BY w.watched, w.movie_id")?;
+        let rows = statement.query_map((format!("{:04}", year),), |row| {
+            Ok(Viewing {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                genres: row.get(2)?,
+                watched: row.get(3)?,
+                rating: row.get(4)?,
+                alerted: row.get(5)?,
+            })
+        })?;
+
+        let mut viewings = Vec::new();
+        for viewing in rows {
+            viewings.push(viewing?);
+        }
+
+        Ok(viewings)
+    }
+
+    /// Marks a movie as not alerted, returns whether it was.  Every channel
+    /// gets the next alert about it again.
+    pub fn forget(&mut self, id: u32) -> Result<bool, AppError> {
+        let now = self.now();
+        let transaction = self.conn.transaction()?;
+        let trash_id = trash(&transaction, &format!("state forget {}", id), now)?;
+
+        transaction.execute(&format!("{} AND id = ?2", TRASH_ALERTS), (trash_id, id))?;
+        let trashed = transaction.execute(&format!("{} AND movie_id = ?3", TRASH_DELIVERIES),
+                                          (trash_id, UPCOMING_ALERT, id))?;
+        let changed = transaction.execute(
+            "UPDATE movies SET alerted = NULL WHERE id = ?1 AND alerted IS NOT NULL", (id,))?;
+        transaction.execute("DELETE FROM delivered WHERE movie_id = ?1 AND alert = ?2",
+                            (id, UPCOMING_ALERT))?;
+
+        // nothing to undo, which rolls the trash back
+        if changed + trashed > 0 {
+            transaction.commit()?;
+        }
+
+        Ok(changed > 0)
+    }
+
+    /// Marks every movie as not alerted, returns how many were.
+    pub fn reset(&mut self) -> Result<usize, AppError> {
+        let now = self.now();
+        let transaction = self.conn.transaction()?;
+        let trash_id = trash(&transaction, "state reset", now)?;
+
+        transaction.execute(TRASH_ALERTS, (trash_id,))?;
+        let trashed = transaction.execute(TRASH_DELIVERIES, (trash_id, UPCOMING_ALERT))?;
+        let changed = transaction.execute(
+            "UPDATE movies SET alerted = NULL WHERE alerted IS NOT NULL", ())?;
+        transaction.execute("DELETE FROM delivered WHERE alert = ?1", (UPCOMING_ALERT,))?;
+
+        if changed + trashed > 0 {
+            transaction.commit()?;
+        }
+
+        Ok(changed)
+    }
+
+    /// Puts back what the last `state forget`, `state reset` or `group
+    /// remove` took away and empties it from the trash, returns the command,
+    /// None when there is none left.  A movie alerted on again since keeps
+    /// the time of its new alert.
+    pub fn undo(&mut self) -> Result<Option<String>, AppError> {
+        let transaction = self.conn.transaction()?;
+        let last = transaction
+            .query_row("SELECT id, command FROM trash ORDER BY id DESC LIMIT 1", [],
+                       |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+            .optional()?;
+
+        let (trash_id, command) = match last {
+            Some(last) => last,
+            None => return Ok(None),
+        };
+
+        transaction.execute(
+            "UPDATE movies SET alerted = (SELECT alerted FROM trashed_alerts
+                 WHERE trash_id = ?1 AND movie_id = movies.id)
+             WHERE alerted IS NULL
+                 AND id IN (SELECT movie_id FROM trashed_alerts WHERE trash_id = ?1)",
+            (trash_id,))?;
+        transaction.execute(
+            "INSERT OR IGNORE INTO delivered (movie_id, alert, channel)
+             SELECT movie_id, alert, channel FROM trashed_deliveries WHERE trash_id = ?1",
+            (trash_id,))?;
+        transaction.execute(
+            "INSERT OR IGNORE INTO groups (name, movie_id)
+             SELECT name, movie_id FROM trashed_groups WHERE trash_id = ?1",
+            (trash_id,))?;
+        empty_trash(&transaction, "= ?1", trash_id)?;
+
+        transaction.commit()?;
+
+        Ok(Some(command))
+    }
+
+    /// Records all of `ids` as alerted in one transaction, for taking over
+    /// the opened movies of the old json data file.
+    pub fn import_opened(&mut self, ids: &HashSet<u32>) -> Result<(), AppError> {
+        let timestamp = self.now();
+        let transaction = self.conn.transaction()?;
+
+        for &id in ids.iter() {
+            transaction.execute(INSERT_ALERTED, (id, timestamp))?;
+        }
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use std::time::Duration;
+
+    const DAY_SECS: u64 = 24 * 60 * 60;
+
+    fn store_at(clock: &Arc<SimulatedClock>) -> MovieStore {
+        MovieStore::open_with_clock(Path::new(":memory:"), clock.clone()).unwrap()
+    }
+
+    #[test]
+    fn snooze_runs_out_on_time() {
+        let clock = Arc::new(SimulatedClock::at_unix_secs(1_750_000_000));
+        let store = store_at(&clock);
+
+        store.snooze(1, 2 * DAY_SECS as i64).unwrap();
+        assert!(store.snoozed_ids().unwrap().contains(&1));
+
+        clock.advance(Duration::from_secs(2 * DAY_SECS - 1));
+        assert!(store.snoozed_ids().unwrap().contains(&1));
+        clock.advance(Duration::from_secs(1));
+        assert!(store.snoozed_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn listed_secs_counts_from_first_seen() {
+        let clock = Arc::new(SimulatedClock::at_unix_secs(1_750_000_000));
+        let store = store_at(&clock);
+
+        assert_eq!(store.listed_secs(1).unwrap(), None);
+        store.record_seen(1, "Heat", "1995-12-15", "Crime").unwrap();
+        clock.advance(Duration::from_secs(7 * DAY_SECS));
+        store.record_seen(1, "Heat", "1995-12-15", "Crime").unwrap();
+
+        assert_eq!(store.listed_secs(1).unwrap(), Some(7 * DAY_SECS as i64));
+    }
+
+    #[test]
+    fn undo_puts_back_the_last_destructive_command() {
+        let clock = Arc::new(SimulatedClock::at_unix_secs(1_750_000_000));
+        let mut store = store_at(&clock);
+        store.record_seen(1, "Heat", "1995-12-15", "Crime").unwrap();
+        store.record_alerted(1).unwrap();
+        store.record_delivered(1, UPCOMING_ALERT, "slack").unwrap();
+        store.add_to_group("Crime", &[1]).unwrap();
+
+        assert!(store.forget(1).unwrap());
+        assert_eq!(store.remove_from_group("Crime", &[1, 2]).unwrap(), 1);
+        assert!(!store.forget(1).unwrap());
+        assert!(store.opened_ids().unwrap().is_empty());
+
+        assert_eq!(store.undo().unwrap().as_deref(), Some("group remove Crime"));
+        assert_eq!(store.groups_of(1).unwrap(), vec!["Crime"]);
+        assert_eq!(store.undo().unwrap().as_deref(), Some("state forget 1"));
+        assert!(store.opened_ids().unwrap().contains(&1));
+        assert!(store.delivered_channels(1, UPCOMING_ALERT).unwrap().contains("slack"));
+        assert_eq!(store.undo().unwrap(), None);
+    }
+}
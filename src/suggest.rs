@@ -0,0 +1,84 @@
+// Changes to the watched genres worth making, read from which alerted movies
+// were opened by hand: a watched genre whose movies keep being left alone
+// when it is the only watched genre they are in is better dropped, or asked
+// for together with the others with --genre-match all.
+
+use crate::weights::split_genres;
+
+// alerts about a genre before anything is made of them
+const MIN_ALERTS: usize = 5;
+
+// opened share at which a genre is taken as not wanted
+const MAX_OPENED_SHARE: f64 = 0.1;
+
+/// Suggestions for the `watched` genres, from the genres of every alerted
+/// movie and whether it was opened by hand.  None when nothing stands out,
+/// or there is only one genre, which leaves nothing to drop.
+pub fn suggest(watched: &[String], movies: &[(String, bool)]) -> Vec<String> {
+    let mut suggestions = Vec::new();
+
+    if watched.len() < 2 {
+        return suggestions;
+    }
+
+    for genre in watched.iter() {
+        // the movies alerted on for this genre alone
+        let alone: Vec<bool> = movies
+            .iter()
+            .filter(|(genres, _)| {
+                let mut watched_in = split_genres(genres)
+                    .filter(|name| watched.iter().any(|w| w.eq_ignore_ascii_case(name)));
+
+                watched_in.next().is_some_and(|name| name.eq_ignore_ascii_case(genre))
+                    && watched_in.next().is_none()
+            })
+            .map(|&(_, opened)| opened)
+            .collect();
+
+        let opened = alone.iter().filter(|&&opened| opened).count();
+        if alone.len() < MIN_ALERTS || opened as f64 > alone.len() as f64 * MAX_OPENED_SHARE {
+            continue;
+        }
+
+        suggestions.push(format!(
+            "You opened {} of {} movies alerted on for {} alone, consider dropping --genre {} \
+             or asking for all genres with --genre-match all",
+            opened, alone.len(), genre, genre));
+    }
+
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movies(genres: &str, opened: usize, left: usize) -> Vec<(String, bool)> {
+        let mut movies = vec![(genres.to_owned(), true); opened];
+        movies.extend(vec![(genres.to_owned(), false); left]);
+        movies
+    }
+
+    #[test]
+    fn suggest_finds_a_genre_left_alone_when_it_is_the_only_one() {
+        let watched = vec!["Animation".to_owned(), "family".to_owned()];
+        let mut alerted = movies("Family", 0, 9);
+        alerted.extend(movies("Animation, Family", 4, 1));
+        alerted.extend(movies("Animation, Comedy", 3, 3));
+
+        assert_eq!(suggest(&watched, &alerted),
+                   vec!["You opened 0 of 9 movies alerted on for family alone, consider \
+                         dropping --genre family or asking for all genres with --genre-match \
+                         all"]);
+    }
+
+    #[test]
+    fn suggest_needs_enough_alerts_and_few_opened() {
+        let watched = vec!["Horror".to_owned(), "Thriller".to_owned()];
+
+        assert!(suggest(&watched, &movies("Horror", 0, 4)).is_empty());
+        assert!(suggest(&watched, &movies("Horror", 1, 5)).is_empty());
+        assert_eq!(suggest(&watched, &movies("Horror", 1, 9)).len(), 1);
+        assert!(suggest(&watched[..1], &movies("Horror", 0, 9)).is_empty());
+    }
+}
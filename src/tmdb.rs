@@ -0,0 +1,1321 @@
+// All TMD api requests go through `TmdClient`, which adds the api key, keeps
+// to a request rate the api is fine with and tries the configured api hosts
+// in order until one of them can be reached.  The api responses and the
+// calls that fetch the upcoming and now playing movies, genres and lists are
+// here too, and the details and translations of movies, asked for a few
+// movies at a time.
+
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use reqwest::{Client, Response, StatusCode};
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::calendar;
+use crate::filter::GenreMatch;
+use crate::genres;
+use crate::http;
+use crate::matching::{self, Confidence, TitleMatch};
+use crate::watchdog::RunWatchdog;
+use crate::AppError;
+
+const TMD_API_BASE_URL: &str = "https://api.themoviedb.org/3";
+const TMD_API_KEY_QUERY_PARAM_NAME: &str = "api_key";
+pub const TMD_API_DEFAULT_LANGUAGE: &str = "en-US";
+const TMD_API_DEFAULT_REGION: &str = "US";
+
+const TMD_API_MOVIE_GENRES_PATH: &str = "/genre/movie/list";
+const TMD_API_MOVIE_UPCOMING_PATH: &str = "/movie/upcoming";
+const TMD_API_MOVIE_NOW_PLAYING_PATH: &str = "/movie/now_playing";
+const TMD_API_LIST_PATH: &str = "/list";
+const TMD_API_DISCOVER_MOVIE_PATH: &str = "/discover/movie";
+const TMD_API_SEARCH_COMPANY_PATH: &str = "/search/company";
+const TMD_API_SEARCH_MOVIE_PATH: &str = "/search/movie";
+// followed by the movie id and this
+const TMD_API_MOVIE_PATH: &str = "/movie";
+const TMD_API_TRANSLATIONS_PATH: &str = "/translations";
+const TMD_API_ACCOUNT_PATH: &str = "/account";
+const TMD_API_WATCHLIST_PATH: &str = "/watchlist/movies";
+
+/// How many pages after the first one of upcoming and discover results are
+/// fetched at a time unless [http] concurrency says otherwise.
+pub const DEFAULT_PAGE_CONCURRENCY: usize = 6;
+
+/// How many movies are asked about at a time for their details or
+/// translations unless [enrichment] concurrency says otherwise.
+pub const DEFAULT_LOOKUP_CONCURRENCY: usize = 4;
+
+// a failed page is tried this many more times, waiting PAGE_RETRY_DELAY
+// before the first retry and twice as long before every further one
+const PAGE_RETRIES: u32 = 3;
+const PAGE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+// well under the api's limit of about 50 requests a second
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(25);
+
+// theatrical releases, limited or not, the same ones /movie/upcoming has
+const DISCOVER_RELEASE_TYPES: &str = "2|3";
+
+// the api answers no page past this one
+const TMD_API_MAX_PAGES: u32 = 500;
+
+// filters of the discover command can match thousands of movies, only the
+// ones on this many pages, the earliest releases, are taken in
+const DISCOVER_COMMAND_MAX_PAGES: u32 = 20;
+
+pub struct TmdClient {
+    http: Client,
+    key: String,
+    base_urls: Vec<String>,
+    language: String,
+    region: String,
+    // pages fetched at a time
+    page_concurrency: usize,
+    // earliest time the next request may be sent
+    next_request: Mutex<Instant>,
+    // genre names by id, fetched once for every run the client is used for
+    genres: OnceCell<HashMap<u32, String>>,
+}
+
+/// The upcoming movies of one region, see `TmdClient::upcoming`.
+pub struct Upcoming<'a> {
+    client: &'a TmdClient,
+    region: &'a str,
+}
+
+/// The movies in theaters in one region, see `TmdClient::now_playing`.
+pub struct NowPlaying<'a> {
+    client: &'a TmdClient,
+    region: &'a str,
+}
+
+/// What the discover command asks the discover api for.  Empty filters are
+/// left out, the movies of several companies or keywords match any of them.
+#[derive(Debug)]
+pub struct DiscoverFilter {
+    pub genre_ids: Vec<u32>,
+    pub genre_match: GenreMatch,
+    pub company_ids: Vec<u32>,
+    pub keyword_ids: Vec<u32>,
+    // first release dates, "2025-03-01"
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub year: Option<i32>,
+    pub min_vote: Option<f32>,
+    // "ja"
+    pub original_language: Option<String>,
+    // in the certification system of the region, e.g. "PG-13" in the US
+    pub certification: Option<String>,
+    pub region: String,
+}
+
+// responses that come in pages
+trait Paged {
+    fn total_pages(&self) -> u32;
+}
+
+impl TmdClient {
+    /// `base_urls` is a comma separated list of api base urls (proxies,
+    /// caching gateways, mirrors), tried in order.  The public api host is
+    /// used when none is given.  `language` and `region` default to en-US
+    /// and US.
+    pub fn new(key: String, base_urls: Option<&str>, language: Option<&str>,
+               region: Option<&str>) -> Self {
+        let mut urls: Vec<String> = base_urls
+            .unwrap_or("")
+            .split(',')
+            .map(|url| url.trim().trim_end_matches('/').to_owned())
+            .filter(|url| !url.is_empty())
+            .collect();
+
+        if urls.is_empty() {
+            urls.push(TMD_API_BASE_URL.to_owned());
+        }
+
+        TmdClient {
+            http: http::client(),
+            key,
+            base_urls: urls,
+            language: language.unwrap_or(TMD_API_DEFAULT_LANGUAGE).to_owned(),
+            region: region.unwrap_or(TMD_API_DEFAULT_REGION).to_owned(),
+            page_concurrency: DEFAULT_PAGE_CONCURRENCY,
+            next_request: Mutex::new(Instant::now()),
+            genres: OnceCell::new(),
+        }
+    }
+
+    /// The same client fetching `concurrency` pages at a time.
+    pub fn with_page_concurrency(self, concurrency: usize) -> Self {
+        TmdClient { page_concurrency: concurrency.max(1), ..self }
+    }
+
+    /// The upcoming movies of `region`, to be fetched page by page.
+    pub fn upcoming<'a>(&'a self, region: &'a str) -> Upcoming<'a> {
+        Upcoming { client: self, region }
+    }
+
+    /// The movies in theaters in `region`, to be fetched page by page.
+    pub fn now_playing<'a>(&'a self, region: &'a str) -> NowPlaying<'a> {
+        NowPlaying { client: self, region }
+    }
+
+    pub fn base_urls(&self) -> &[String] {
+        &self.base_urls
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    /// GET `path` (e.g. "/movie/upcoming") with the given query params.
+    ///
+    /// Only failures to get any response move on to the next host, an error
+    /// status from the api is returned as is.
+    pub async fn get(&self, path: &str, params: &[(&str, &str)])
+                     -> Result<Response, reqwest::Error> {
+        let mut last_error = None;
+
+        for base_url in self.base_urls.iter() {
+            self.wait_for_turn().await;
+
+            let request = self.http
+                .get(base_url.to_owned() + path)
+                .query(&[(TMD_API_KEY_QUERY_PARAM_NAME, self.key.as_str())])
+                .query(params);
+
+            match request.send().await {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    let e = http::redact_url(e);
+                    warn!("Request to {}{} failed: {}", base_url, path, e);
+                    last_error = Some(e);
+                },
+            }
+        }
+
+        // base_urls is never empty, so there is always an error here
+        Err(last_error.unwrap())
+    }
+
+    // requests in flight together are spread out by MIN_REQUEST_INTERVAL
+    async fn wait_for_turn(&self) {
+        let wait = {
+            let mut next_request = self.next_request.lock().unwrap_or_else(|e| e.into_inner());
+
+            let now = Instant::now();
+            let turn = (*next_request).max(now);
+            *next_request = turn + MIN_REQUEST_INTERVAL;
+
+            turn - now
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl<'a> Upcoming<'a> {
+    /// Every page in order, fetched a few at a time and each
+    /// tried again a few times when it fails.  Pages are only fetched, and
+    /// counted by the watchdog, as far as the stream is read.
+    pub fn pages(self, watchdog: &'a mut RunWatchdog)
+                 -> impl Stream<Item = Result<UpComingMovieResponse, AppError>> + 'a {
+        let Upcoming { client, region } = self;
+
+        page_stream(watchdog, TMD_API_MAX_PAGES, client.page_concurrency,
+                    move |page| fetch_upcoming_page(page, region, client))
+    }
+}
+
+impl<'a> NowPlaying<'a> {
+    /// Every page in order, the same way as `Upcoming::pages`.
+    pub fn pages(self, watchdog: &'a mut RunWatchdog)
+                 -> impl Stream<Item = Result<UpComingMovieResponse, AppError>> + 'a {
+        let NowPlaying { client, region } = self;
+
+        page_stream(watchdog, TMD_API_MAX_PAGES, client.page_concurrency,
+                    move |page| fetch_now_playing_page(page, region, client))
+    }
+}
+
+impl Paged for UpComingMovieResponse {
+    fn total_pages(&self) -> u32 {
+        self.total_pages
+    }
+}
+
+impl Paged for DiscoverMovieResponse {
+    fn total_pages(&self) -> u32 {
+        self.total_pages
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenreReponse {
+    pub genres: Vec<Genre>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Genre {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UpComingMovieResponse {
+    pub page: u32,
+    pub results: Vec<Movie>,
+    pub dates: Dates,
+    pub total_pages: u32,
+    pub total_results: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiscoverMovieResponse {
+    pub page: u32,
+    pub results: Vec<Movie>,
+    pub total_pages: u32,
+    pub total_results: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Movie {
+    pub poster_path: Option<String>,
+    pub adult: bool,
+    pub overview: String,
+    pub release_date: String,
+    pub genre_ids: Vec<u32>,
+    pub id: u32,
+    pub title: String,
+    #[serde(default)]
+    pub vote_average: f32,
+    #[serde(default)]
+    pub vote_count: u32,
+    // release date in every region the movie was found in, filled in when
+    // the regions are merged
+    #[serde(default)]
+    pub region_release_dates: BTreeMap<String, String>,
+    // whether there is a translation into the api language, filled in when
+    // asked for with --translation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated: Option<bool>,
+    // whether a company, keyword or person of the movie is excluded, filled
+    // in when there are exclusions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excluded: Option<bool>,
+    // production status, e.g. "Post Production", filled in when asked for
+    // with --status or when there are exclusions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    // minutes, filled in with the status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Dates {
+    pub maximum: String,
+    pub minimum: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompanySearchResponse {
+    pub results: Vec<Company>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovieSearchResponse {
+    pub results: Vec<FoundMovie>,
+}
+
+/// A movie of the search results, of which only the id, title and release
+/// date are needed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct FoundMovie {
+    pub id: u32,
+    pub title: String,
+    #[serde(default)]
+    pub release_date: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Company {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TranslationsResponse {
+    pub translations: Vec<Translation>,
+}
+
+/// A language the title and overview of a movie are translated into.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Translation {
+    // "de"
+    pub iso_639_1: String,
+    // "DE"
+    pub iso_3166_1: String,
+    #[serde(default)]
+    pub data: TranslationData,
+}
+
+/// What a translation translates.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranslationData {
+    // empty when only the overview is translated
+    #[serde(default)]
+    pub title: String,
+}
+
+/// The production status, companies, keywords and people of a movie, which
+/// the upcoming and discover results leave out.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MovieDetails {
+    // "Rumored", "Planned", "In Production", "Post Production", "Released"
+    // or "Canceled"
+    #[serde(default)]
+    pub status: Option<String>,
+    // minutes, 0 when not known yet
+    #[serde(default)]
+    pub runtime: Option<u32>,
+    #[serde(default)]
+    pub production_companies: Vec<Entry>,
+    #[serde(default)]
+    pub keywords: Keywords,
+    #[serde(default)]
+    pub credits: Credits,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Keywords {
+    pub keywords: Vec<Entry>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Credits {
+    pub cast: Vec<Entry>,
+    pub crew: Vec<Entry>,
+}
+
+/// A company, keyword or person, of which only the id is needed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u32,
+}
+
+/// A page of the movies on the watchlist of an account.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct WatchlistResponse {
+    pub page: u32,
+    pub results: Vec<Entry>,
+    pub total_pages: u32,
+}
+
+/// TMD ids of the companies, keywords and people whose movies are never
+/// alerted on.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Exclusions {
+    pub company_ids: Vec<u32>,
+    pub keyword_ids: Vec<u32>,
+    pub person_ids: Vec<u32>,
+}
+
+impl Exclusions {
+    pub fn is_empty(&self) -> bool {
+        self.company_ids.is_empty() && self.keyword_ids.is_empty() && self.person_ids.is_empty()
+    }
+}
+
+/// Items can be movies or tv shows, only movies are picked out of them.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ListResponse {
+    pub name: String,
+    pub items: Vec<serde_json::Value>,
+    pub total_pages: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UpcomingSnapshot {
+    // of titles, overviews and genre names, snapshots from before it was
+    // kept are en-US
+    #[serde(default = "default_snapshot_language")]
+    pub language: String,
+    #[serde(serialize_with = "serialize_sorted")]
+    pub genres: HashMap<u32, String>,
+    pub movies: Vec<Movie>,
+    pub min_date: String,
+    pub max_date: String,
+}
+
+// by id, so the snapshot file is the same from run to run
+fn serialize_sorted<S: Serializer>(genres: &HashMap<u32, String>, serializer: S)
+                                   -> Result<S::Ok, S::Error> {
+    genres.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Order movies are listed and alerted on in, whatever order the api
+/// returned them in: by release date, movies without a real date last, then
+/// by id.
+pub fn release_order(a: &Movie, b: &Movie) -> Ordering {
+    calendar::is_date_tbd(&a.release_date).cmp(&calendar::is_date_tbd(&b.release_date))
+        .then(a.release_date.cmp(&b.release_date))
+        .then(a.id.cmp(&b.id))
+}
+
+fn default_snapshot_language() -> String {
+    "en-US".to_owned()
+}
+
+/// The upcoming movies of every one of `regions`, merged.  With
+/// `extend_months`, movies releasing that many months past the end of a
+/// region's upcoming window are discovered and added to it.
+pub async fn retrieve_upcoming_snapshot(client: &TmdClient, regions: &[String], extend_months: u32,
+                                        watchdog: &mut RunWatchdog)
+                                        -> Result<UpcomingSnapshot, AppError> {
+    let genres = retrieve_genres(client, watchdog).await?;
+
+    let mut movies: Vec<Movie> = Vec::new();
+    let mut window: Option<(String, String)> = None;
+
+    for region in regions.iter() {
+        let (upcoming, region_min_date, mut region_max_date) =
+            retrieve_all_upcoming_movies(client, region, watchdog).await?;
+        merge_region_movies(&mut movies, upcoming, region);
+
+        if extend_months > 0 {
+            let extended_max_date = calendar::add_months(&region_max_date, extend_months)
+                .ok_or(AppError::ArgumentError(
+                    format!("cannot extend upcoming window ending: {}", region_max_date)))?;
+
+            let discovered = retrieve_discover_movies(&region_max_date, &extended_max_date,
+                                                      region, &[], client, watchdog).await?;
+            debug!("Discovered {} movies in {} up to {}", discovered.len(), region,
+                   extended_max_date);
+
+            merge_region_movies(&mut movies, discovered, region);
+
+            region_max_date = extended_max_date;
+        }
+
+        // dates compare fine as strings
+        window = Some(match window {
+            Some((min_date, max_date)) => {
+                (min_date.min(region_min_date), max_date.max(region_max_date))
+            },
+            None => (region_min_date, region_max_date),
+        });
+    }
+
+    // regions is never empty, but an empty window is no reason to fail
+    let (min_date, max_date) = window.unwrap_or_default();
+
+    // the api orders by popularity, which shifts from run to run
+    movies.sort_by(release_order);
+
+    trace!("All upcoming movies: {:?}", movies);
+    debug!("Total # of upcoming movies: {}", movies.len());
+
+    Ok(UpcomingSnapshot {
+        language: client.language().to_owned(),
+        genres,
+        movies,
+        min_date,
+        max_date,
+    })
+}
+
+/// Genre names by id, fetched only the first time for a client, or the
+/// built in ones when the api does not answer: genres hardly ever change, a
+/// blip fetching them is no reason to give up on the run.  The built in ones
+/// are not kept, the next run tries the api again.
+pub async fn retrieve_genres(client: &TmdClient, watchdog: &mut RunWatchdog)
+                             -> Result<HashMap<u32, String>, AppError> {
+    let fetched = client.genres
+        .get_or_try_init(|| async {
+            watchdog.api_call()?;
+            retrieve_genre_and_convert_to_map(client).await
+        })
+        .await;
+
+    match fetched {
+        Ok(genres) => Ok(genres.clone()),
+        Err(AppError::RestClientError(_, cause, _)) => {
+            warn!("Cannot get movie genres, using the built in list: {}", cause);
+            Ok(genres::known_genre_map())
+        },
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn retrieve_genre_and_convert_to_map(client: &TmdClient)
+                                               -> Result<HashMap<u32, String>, AppError> {
+    let sent = client.get(TMD_API_MOVIE_GENRES_PATH, &[("language", client.language())]).await;
+    let genre_response = http::check_response(sent, "Error: cannot get movie genres").await?;
+
+    trace!("Got genre response: {:?}", genre_response);
+
+    let genre_response_typed: GenreReponse = genre_response
+        .json::<GenreReponse>()
+        .await
+        .map_err(|e| AppError::RestClientError(
+                    "Error: cannot parse genre response to json".to_string(), e, None))?;
+    trace!("Got typed genre response: {:?}", genre_response_typed);
+
+    let mut genre_id_to_name: HashMap<u32, String> = HashMap::new();
+
+    for g in genre_response_typed.genres.into_iter() {
+        genre_id_to_name.insert(g.id, g.name);
+    }
+
+    Ok(genre_id_to_name)
+}
+
+// adds the movies found for `region`, one already found elsewhere only gets
+// the release date of this region added
+fn merge_region_movies(movies: &mut Vec<Movie>, region_movies: Vec<Movie>, region: &str) {
+    let mut positions: HashMap<u32, usize> = movies
+        .iter()
+        .enumerate()
+        .map(|(position, movie)| (movie.id, position))
+        .collect();
+
+    for mut movie in region_movies.into_iter() {
+        match positions.get(&movie.id) {
+            Some(&position) => {
+                movies[position].region_release_dates.insert(region.to_owned(),
+                                                             movie.release_date);
+            },
+            None => {
+                movie.region_release_dates.insert(region.to_owned(), movie.release_date.clone());
+                positions.insert(movie.id, movies.len());
+                movies.push(movie);
+            },
+        }
+    }
+}
+
+pub async fn retrieve_all_upcoming_movies(client: &TmdClient, region: &str,
+                                          watchdog: &mut RunWatchdog)
+                                          -> Result<(Vec<Movie>, String, String), AppError> {
+    let mut pages = pin!(client.upcoming(region).pages(watchdog));
+
+    let mut movies = Vec::new();
+    let mut dates = None;
+
+    while let Some(page) = pages.try_next().await? {
+        if page.page == 1 {
+            debug!("Total # of pages for upcoming movies in {}: {}", region, page.total_pages);
+            debug!("Total # of upcoming movies returned by page 1: {}", page.total_results);
+        }
+
+        dates.get_or_insert(page.dates);
+        movies.extend(page.results);
+    }
+
+    // there is always a first page, or an error above
+    let (min_date, max_date) = dates.map(|d| (d.minimum, d.maximum)).unwrap_or_default();
+
+    Ok((movies, min_date, max_date))
+}
+
+/// Movies in theaters in any of the regions, in release order.
+pub async fn retrieve_now_playing_movies(client: &TmdClient, regions: &[String],
+                                         watchdog: &mut RunWatchdog)
+                                         -> Result<Vec<Movie>, AppError> {
+    let mut movies: Vec<Movie> = Vec::new();
+
+    for region in regions.iter() {
+        let mut pages = pin!(client.now_playing(region).pages(watchdog));
+        let mut region_movies = Vec::new();
+
+        while let Some(page) = pages.try_next().await? {
+            if page.page == 1 {
+                debug!("Total # of pages for now playing movies in {}: {}", region,
+                       page.total_pages);
+            }
+
+            region_movies.extend(page.results);
+        }
+
+        merge_region_movies(&mut movies, region_movies, region);
+    }
+
+    movies.sort_by(release_order);
+    debug!("Total # of now playing movies: {}", movies.len());
+
+    Ok(movies)
+}
+
+// the first page and then the rest of them up to `max_pages`,
+// `concurrency` in flight at a time, every page counted by the watchdog as
+// it is asked for
+fn page_stream<'a, T, F, Fut>(watchdog: &'a mut RunWatchdog, max_pages: u32, concurrency: usize,
+                              fetch_page: F) -> impl Stream<Item = Result<T, AppError>> + 'a
+    where T: Paged + 'a,
+          F: Fn(u32) -> Fut + Clone + 'a,
+          Fut: Future<Output = Result<T, AppError>> + 'a
+{
+    stream::once(async move {
+        watchdog.api_call()?;
+        let first_page = with_retries(|| fetch_page(1)).await?;
+
+        // buffered hands the pages back in page order however they complete
+        let next_pages = stream::iter(2..(first_page.total_pages().min(max_pages) + 1))
+            .map(move |page| {
+                let counted = watchdog.api_call();
+                let fetch_page = fetch_page.clone();
+
+                async move {
+                    counted?;
+                    with_retries(|| fetch_page(page)).await
+                }
+            })
+            .buffered(concurrency);
+
+        Ok::<_, AppError>(stream::iter([Ok(first_page)]).chain(next_pages))
+    }).try_flatten()
+}
+
+// a page that could not be fetched, or came back with too many requests or
+// a server error, is tried again after a while
+async fn with_retries<T, F, Fut>(fetch_page: F) -> Result<T, AppError>
+    where F: Fn() -> Fut,
+          Fut: Future<Output = Result<T, AppError>>
+{
+    let mut delay = PAGE_RETRY_DELAY;
+
+    for _ in 0..PAGE_RETRIES {
+        match fetch_page().await {
+            Err(AppError::RestClientError(msg, cause, _)) if is_retryable(&cause) => {
+                warn!("{}, trying again in {} seconds: {}", msg, delay.as_secs(), cause);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            },
+            result => return result,
+        }
+    }
+
+    fetch_page().await
+}
+
+fn is_retryable(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+        // a response that cannot be parsed will not get any better
+        None => !error.is_decode(),
+    }
+}
+
+/// Movies from the discover api released in the region between the two
+/// dates, only the ones of any of `company_ids` unless it is empty.
+pub async fn retrieve_discover_movies(from: &str, to: &str, region: &str, company_ids: &[u32],
+                                      client: &TmdClient, watchdog: &mut RunWatchdog)
+                                      -> Result<Vec<Movie>, AppError> {
+    let companies = company_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join("|");
+
+    let mut params = vec![("region", region), ("release_date.gte", from),
+                          ("release_date.lte", to),
+                          ("with_release_type", DISCOVER_RELEASE_TYPES),
+                          ("sort_by", "release_date.asc")];
+    if !companies.is_empty() {
+        params.push(("with_companies", &companies));
+    }
+
+    let mut pages = pin!(page_stream(watchdog, TMD_API_MAX_PAGES, client.page_concurrency,
+                                     |page| fetch_discover_page(page, &params, client)));
+
+    let mut movies = Vec::new();
+
+    while let Some(page) = pages.try_next().await? {
+        if page.page == 1 {
+            debug!("Total # of pages for discovered movies: {}", page.total_pages);
+        }
+
+        movies.extend(page.results);
+    }
+
+    Ok(movies)
+}
+
+/// Movies of any of the companies releasing in theaters in one of the
+/// regions between the two dates, whatever their genres, in release order.
+pub async fn retrieve_company_movies(company_ids: &[u32], from: &str, to: &str,
+                                     regions: &[String], client: &TmdClient,
+                                     watchdog: &mut RunWatchdog)
+                                     -> Result<Vec<Movie>, AppError> {
+    let mut movies: Vec<Movie> = Vec::new();
+
+    for region in regions.iter() {
+        let region_movies = retrieve_discover_movies(from, to, region, company_ids, client,
+                                                     watchdog).await?;
+        merge_region_movies(&mut movies, region_movies, region);
+    }
+
+    movies.sort_by(release_order);
+    debug!("Total # of movies of watched companies: {}", movies.len());
+
+    Ok(movies)
+}
+
+/// The company called `name`, or else the one the api finds most relevant
+/// for it, None if it finds none.
+pub async fn search_company(name: &str, client: &TmdClient, watchdog: &mut RunWatchdog)
+                            -> Result<Option<Company>, AppError> {
+    debug!("Searching for company {}", name);
+
+    watchdog.api_call()?;
+
+    let sent = client.get(TMD_API_SEARCH_COMPANY_PATH, &[("query", name), ("page", "1")]).await;
+    let response = http::check_response(
+        sent, &("Error: cannot search for company ".to_string() + name)).await?;
+
+    let mut companies = response
+        .json::<CompanySearchResponse>()
+        .await
+        .map_err(|e| AppError::RestClientError(
+                    "Error: cannot parse company search response to json".to_string(), e,
+                    None))?
+        .results;
+
+    let position = companies
+        .iter()
+        .position(|company| company.name.to_lowercase() == name.to_lowercase())
+        .unwrap_or(0);
+
+    Ok(if companies.is_empty() { None } else { Some(companies.swap_remove(position)) })
+}
+
+// the movies a search for the title finds, of the year when one is given
+async fn search_movies(title: &str, year: Option<&str>, client: &TmdClient,
+                       watchdog: &mut RunWatchdog) -> Result<Vec<FoundMovie>, AppError> {
+    debug!("Searching for movie {} ({:?})", title, year);
+
+    watchdog.api_call()?;
+
+    let mut params = vec![("query", title), ("page", "1")];
+    if let Some(year) = year {
+        params.push(("primary_release_year", year));
+    }
+
+    let sent = client.get(TMD_API_SEARCH_MOVIE_PATH, &params).await;
+    let response = http::check_response(
+        sent, &("Error: cannot search for movie ".to_string() + title)).await?;
+
+    Ok(response
+        .json::<MovieSearchResponse>()
+        .await
+        .map_err(|e| AppError::RestClientError(
+                    "Error: cannot parse movie search response to json".to_string(), e,
+                    None))?
+        .results)
+}
+
+/// The movie best matching `title` of `year`, see `matching`.  Movies of
+/// that year are searched first, and all of them when none of those is a
+/// sure match, as the year may be off by one.
+pub async fn search_movie(title: &str, year: Option<i32>, client: &TmdClient,
+                          watchdog: &mut RunWatchdog) -> Result<Option<TitleMatch>, AppError> {
+    let best = |movies: &[FoundMovie]| {
+        let candidates: Vec<(u32, &str, Option<i32>)> = movies.iter()
+            .map(|movie| (movie.id, movie.title.as_str(),
+                          movie.release_date.as_deref().and_then(|date| date.get(..4))
+                              .and_then(|year| year.parse().ok())))
+            .collect();
+
+        matching::best_match(title, year, &candidates)
+    };
+
+    let year_text = year.map(|year| year.to_string());
+    let found = best(&search_movies(title, year_text.as_deref(), client, watchdog).await?);
+    if year.is_none() || found.as_ref().is_some_and(|found| found.confidence == Confidence::High) {
+        return Ok(found);
+    }
+
+    let any_year = best(&search_movies(title, None, client, watchdog).await?);
+
+    Ok(match (found, any_year) {
+        (Some(found), Some(other)) => Some(if other.score > found.score { other } else { found }),
+        (found, other) => found.or(other),
+    })
+}
+
+/// Movies the discover api finds with the filter, the earliest first
+/// releases up to DISCOVER_COMMAND_MAX_PAGES pages of them, in release
+/// order.
+pub async fn retrieve_filtered_discover_movies(filter: &DiscoverFilter, client: &TmdClient,
+                                               watchdog: &mut RunWatchdog)
+                                               -> Result<Vec<Movie>, AppError> {
+    let join = |ids: &[u32], separator| ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(separator);
+
+    let genre_separator = match filter.genre_match {
+        GenreMatch::Any => "|",
+        GenreMatch::All => ",",
+    };
+
+    let values = [
+        ("with_genres", Some(join(&filter.genre_ids, genre_separator))),
+        ("with_companies", Some(join(&filter.company_ids, "|"))),
+        ("with_keywords", Some(join(&filter.keyword_ids, "|"))),
+        ("primary_release_date.gte", filter.from.clone()),
+        ("primary_release_date.lte", filter.to.clone()),
+        ("primary_release_year", filter.year.map(|year| year.to_string())),
+        ("vote_average.gte", filter.min_vote.map(|vote| vote.to_string())),
+        ("with_original_language", filter.original_language.clone()),
+        ("certification", filter.certification.clone()),
+    ];
+
+    let mut params: Vec<(&str, &str)> = values
+        .iter()
+        .filter_map(|(name, value)| value.as_deref().filter(|v| !v.is_empty()).map(|v| (*name, v)))
+        .collect();
+
+    params.push(("region", &filter.region));
+    if filter.certification.is_some() {
+        params.push(("certification_country", &filter.region));
+    }
+    params.push(("sort_by", "primary_release_date.asc"));
+
+    let mut pages = pin!(page_stream(watchdog, DISCOVER_COMMAND_MAX_PAGES,
+                                     client.page_concurrency,
+                                     |page| fetch_discover_page(page, &params, client)));
+
+    let mut movies = Vec::new();
+
+    while let Some(page) = pages.try_next().await? {
+        if page.page == 1 {
+            debug!("Total # of pages for discovered movies: {}", page.total_pages);
+
+            if page.total_pages > DISCOVER_COMMAND_MAX_PAGES {
+                warn!("Only taking in the first {} of {} pages of discovered movies, narrow \
+                       down the filters to get all of them", DISCOVER_COMMAND_MAX_PAGES,
+                      page.total_pages);
+            }
+        }
+
+        movies.extend(page.results);
+    }
+
+    movies.sort_by(release_order);
+    debug!("Total # of discovered movies: {}", movies.len());
+
+    Ok(movies)
+}
+
+// one page of discovered movies, the api call is counted by the caller
+async fn fetch_discover_page(page: u32, params: &[(&str, &str)], client: &TmdClient)
+                             -> Result<DiscoverMovieResponse, AppError> {
+    debug!("Discovering movies with {:?}, page={}", params, page);
+
+    let page_param = page.to_string();
+
+    let mut query = vec![("language", client.language()), ("page", page_param.as_str())];
+    query.extend_from_slice(params);
+
+    let sent = client.get(TMD_API_DISCOVER_MOVIE_PATH, &query).await;
+    let response = http::check_response(
+        sent, &("Error: cannot discover movies for page ".to_string() + &page.to_string())).await?;
+
+    trace!("Got discover response: {:?}", response);
+
+    response
+        .json::<DiscoverMovieResponse>()
+        .await
+        .map_err(|e| AppError::RestClientError(
+                    "Error: cannot parse discover movie response to json".to_string(), e,
+                    None))
+}
+
+pub async fn retrieve_upcoming_movies_by_page(page: u32, region: &str, client: &TmdClient,
+                                              watchdog: &mut RunWatchdog)
+                                              -> Result<UpComingMovieResponse, AppError> {
+    watchdog.api_call()?;
+
+    fetch_upcoming_page(page, region, client).await
+}
+
+// one page of upcoming movies, the api call is counted by the caller
+async fn fetch_upcoming_page(page: u32, region: &str, client: &TmdClient)
+                             -> Result<UpComingMovieResponse, AppError> {
+    fetch_dated_page(TMD_API_MOVIE_UPCOMING_PATH, "upcoming", page, region, client).await
+}
+
+// one page of now playing movies, the api call is counted by the caller
+async fn fetch_now_playing_page(page: u32, region: &str, client: &TmdClient)
+                                -> Result<UpComingMovieResponse, AppError> {
+    fetch_dated_page(TMD_API_MOVIE_NOW_PLAYING_PATH, "now playing", page, region, client).await
+}
+
+// upcoming and now playing answer the same way, with the dates they cover
+async fn fetch_dated_page(path: &str, what: &str, page: u32, region: &str, client: &TmdClient)
+                          -> Result<UpComingMovieResponse, AppError> {
+    debug!("Getting {} movies in {}, page={}", what, region, page);
+
+    let page_param = page.to_string();
+
+    let sent = client
+        .get(path,
+             &[("language", client.language()), ("page", page_param.as_str()),
+               ("region", region)])
+        .await;
+    let response = http::check_response(
+        sent, &format!("Error: cannot get {} movies for page {}", what, page)).await?;
+
+    trace!("Got {} response: {:?}", what, response);
+
+    response
+        .json::<UpComingMovieResponse>()
+        .await
+        .map_err(|e| AppError::RestClientError(
+                    format!("Error: cannot parse {} movie response to json", what), e, None))
+}
+
+// the languages the movie is translated into, in the api's order
+async fn fetch_translations(id: u32, client: &TmdClient) -> Result<Vec<Translation>, AppError> {
+    debug!("Getting translations of movie {}", id);
+
+    let path = format!("{}/{}{}", TMD_API_MOVIE_PATH, id, TMD_API_TRANSLATIONS_PATH);
+
+    let sent = client.get(&path, &[]).await;
+    let response = http::check_response(
+        sent, &format!("Error: cannot get translations of movie {}", id)).await?;
+
+    response
+        .json::<TranslationsResponse>()
+        .await
+        .map(|response| response.translations)
+        .map_err(|e| AppError::RestClientError(
+            "Error: cannot parse translations response to json".to_string(), e, None))
+}
+
+/// The translations of every movie, see `retrieve_details`.
+pub fn retrieve_translations<'a>(ids: Vec<u32>, concurrency: usize, client: &'a TmdClient,
+                                 watchdog: &'a mut RunWatchdog)
+                                 -> impl Stream<Item = PerMovie<Vec<Translation>>> + 'a {
+    per_movie(ids, concurrency, watchdog, move |id| fetch_translations(id, client))
+}
+
+// "de" is any German translation, "de-DE" only the one for Germany, since
+// "pt-BR" and "pt-PT" are not the same thing
+fn is_into(translation: &Translation, language: &str) -> bool {
+    let mut parts = language.splitn(2, '-');
+    let wanted_language = parts.next().unwrap_or("");
+    let wanted_country = parts.next();
+
+    translation.iso_639_1 == wanted_language
+        && wanted_country.is_none_or(|country| translation.iso_3166_1 == country)
+}
+
+/// Whether one of the translations is into `language`, "de" or "de-DE".
+pub fn has_translation(translations: &[Translation], language: &str) -> bool {
+    translations.iter().any(|translation| is_into(translation, language))
+}
+
+/// The title translated into `language`, none when no translation into it
+/// has one.
+pub fn translated_title<'a>(translations: &'a [Translation], language: &str)
+                            -> Option<&'a str> {
+    translations.iter()
+        .filter(|translation| is_into(translation, language))
+        .map(|translation| translation.data.title.as_str())
+        .find(|title| !title.is_empty())
+}
+
+// the production status, companies, keywords and people of the movie, with
+// one request
+async fn fetch_details(id: u32, client: &TmdClient) -> Result<MovieDetails, AppError> {
+    debug!("Getting details of movie {}", id);
+
+    let path = format!("{}/{}", TMD_API_MOVIE_PATH, id);
+
+    let sent = client.get(&path, &[("append_to_response", "keywords,credits")]).await;
+    let response = http::check_response(
+        sent, &format!("Error: cannot get details of movie {}", id)).await?;
+
+    response
+        .json::<MovieDetails>()
+        .await
+        .map_err(|e| AppError::RestClientError(
+            "Error: cannot parse movie details response to json".to_string(), e, None))
+}
+
+/// What was got about a movie, with its id.
+pub type PerMovie<T> = (u32, Result<T, AppError>);
+
+/// The details of every movie by its id, in the order of `ids`,
+/// `concurrency` of them asked for at a time.  Every request is counted by
+/// the watchdog as it is made, and one that fails fails only its movie.
+pub fn retrieve_details<'a>(ids: Vec<u32>, concurrency: usize, client: &'a TmdClient,
+                            watchdog: &'a mut RunWatchdog)
+                            -> impl Stream<Item = PerMovie<MovieDetails>> + 'a {
+    per_movie(ids, concurrency, watchdog, move |id| fetch_details(id, client))
+}
+
+// what `fetch` gets about every movie, with its id
+fn per_movie<'a, T, F, Fut>(ids: Vec<u32>, concurrency: usize, watchdog: &'a mut RunWatchdog,
+                            fetch: F) -> impl Stream<Item = PerMovie<T>> + 'a
+    where T: 'a,
+          F: Fn(u32) -> Fut + 'a,
+          Fut: Future<Output = Result<T, AppError>> + 'a
+{
+    stream::iter(ids)
+        .map(move |id| {
+            let counted = watchdog.api_call();
+            let fetched = fetch(id);
+
+            async move {
+                match counted {
+                    Ok(()) => (id, fetched.await),
+                    Err(e) => (id, Err(e)),
+                }
+            }
+        })
+        .buffered(concurrency.max(1))
+}
+
+/// Whether a production company, keyword, cast or crew member of the movie
+/// is excluded.
+pub fn is_excluded(details: &MovieDetails, exclusions: &Exclusions) -> bool {
+    let any_of = |entries: &[Entry], ids: &[u32]| entries.iter().any(|e| ids.contains(&e.id));
+
+    any_of(&details.production_companies, &exclusions.company_ids)
+        || any_of(&details.keywords.keywords, &exclusions.keyword_ids)
+        || any_of(&details.credits.cast, &exclusions.person_ids)
+        || any_of(&details.credits.crew, &exclusions.person_ids)
+}
+
+/// Ids of the movies on the watchlist of the account the session is of,
+/// which takes the id of the account first.
+pub async fn retrieve_watchlist(session_id: &str, client: &TmdClient, watchdog: &mut RunWatchdog)
+                                -> Result<HashSet<u32>, AppError> {
+    debug!("Getting the account of the session");
+
+    watchdog.api_call()?;
+
+    let sent = client.get(TMD_API_ACCOUNT_PATH, &[("session_id", session_id)]).await;
+    let account = http::check_response(sent, "Error: cannot get the TMD account").await?
+        .json::<Entry>()
+        .await
+        .map_err(|e| AppError::RestClientError(
+            "Error: cannot parse account response to json".to_string(), e, None))?;
+
+    let path = format!("{}/{}{}", TMD_API_ACCOUNT_PATH, account.id, TMD_API_WATCHLIST_PATH);
+    let mut ids = HashSet::new();
+    let mut page = 1;
+
+    loop {
+        debug!("Getting the watchlist of account {}, page={}", account.id, page);
+
+        watchdog.api_call()?;
+
+        let page_param = page.to_string();
+
+        let sent = client
+            .get(&path, &[("session_id", session_id), ("page", page_param.as_str())])
+            .await;
+        let watchlist = http::check_response(sent, "Error: cannot get the TMD watchlist").await?
+            .json::<WatchlistResponse>()
+            .await
+            .map_err(|e| AppError::RestClientError(
+                "Error: cannot parse watchlist response to json".to_string(), e, None))?;
+
+        ids.extend(watchlist.results.iter().map(|movie| movie.id));
+
+        if page >= watchlist.total_pages.min(TMD_API_MAX_PAGES) {
+            break;
+        }
+
+        page += 1;
+    }
+
+    Ok(ids)
+}
+
+pub async fn retrieve_list(list_id: u32, client: &TmdClient, watchdog: &mut RunWatchdog)
+                           -> Result<(String, Vec<Movie>), AppError> {
+    let path = TMD_API_LIST_PATH.to_owned() + "/" + &list_id.to_string();
+
+    let mut name;
+    let mut movies = Vec::new();
+    let mut page = 1;
+
+    loop {
+        debug!("Getting list {}, page={}", list_id, page);
+
+        watchdog.api_call()?;
+
+        let page_param = page.to_string();
+
+        let sent = client
+            .get(&path, &[("language", client.language()), ("page", page_param.as_str())])
+            .await;
+        let response = http::check_response(
+            sent, &("Error: cannot get TMD list ".to_string() + &list_id.to_string())).await?;
+
+        trace!("Got list response: {:?}", response);
+
+        let list_response = response
+            .json::<ListResponse>()
+            .await
+            .map_err(|e| AppError::RestClientError(
+                "Error: cannot parse TMD list ".to_string() + &list_id.to_string(), e, None))?;
+
+        name = list_response.name;
+
+        for item in list_response.items.into_iter() {
+            if item.get("media_type").and_then(|t| t.as_str()).unwrap_or("movie") != "movie" {
+                continue;
+            }
+
+            match serde_json::from_value::<Movie>(item) {
+                Ok(movie) => movies.push(movie),
+                Err(e) => warn!("Skipping unreadable item on list {}: {}", list_id, e),
+            }
+        }
+
+        if page >= list_response.total_pages.unwrap_or(1) {
+            break;
+        }
+
+        page += 1;
+    }
+
+    Ok((name, movies))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movie(id: u32, release_date: &str) -> Movie {
+        Movie {
+            poster_path: None,
+            adult: false,
+            overview: String::new(),
+            release_date: release_date.to_owned(),
+            genre_ids: Vec::new(),
+            id,
+            title: format!("Movie {}", id),
+            vote_average: 0.0,
+            vote_count: 0,
+            region_release_dates: BTreeMap::new(),
+            translated: None,
+            excluded: None,
+            status: None,
+            runtime: None,
+        }
+    }
+
+    #[test]
+    fn merge_region_movies_adds_the_dates_of_every_region() {
+        let mut movies = Vec::new();
+
+        merge_region_movies(&mut movies, vec![movie(1, "2025-07-01"), movie(2, "2025-07-04")],
+                            "US");
+        merge_region_movies(&mut movies, vec![movie(2, "2025-07-10"), movie(3, "2025-07-12")],
+                            "GB");
+
+        let ids: Vec<u32> = movies.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        // the date of the region it was found in first stays the main one
+        assert_eq!(movies[1].release_date, "2025-07-04");
+        assert_eq!(movies[1].region_release_dates.get("US").map(String::as_str),
+                   Some("2025-07-04"));
+        assert_eq!(movies[1].region_release_dates.get("GB").map(String::as_str),
+                   Some("2025-07-10"));
+
+        assert_eq!(movies[0].region_release_dates.keys().collect::<Vec<_>>(), vec!["US"]);
+        assert_eq!(movies[2].region_release_dates.keys().collect::<Vec<_>>(), vec!["GB"]);
+    }
+
+    #[test]
+    fn merge_region_movies_keeps_a_movie_listed_twice_once() {
+        let mut movies = Vec::new();
+
+        merge_region_movies(&mut movies, vec![movie(1, "2025-07-01"), movie(1, "2025-07-01")],
+                            "US");
+
+        assert_eq!(movies.len(), 1);
+        assert_eq!(movies[0].region_release_dates.len(), 1);
+    }
+
+    fn translation(language: &str, country: &str) -> Translation {
+        Translation { iso_639_1: language.to_owned(), iso_3166_1: country.to_owned(),
+                      data: TranslationData::default() }
+    }
+
+    #[test]
+    fn has_translation_matches_the_language_alone() {
+        let translations = vec![translation("en", "US"), translation("de", "AT")];
+
+        assert!(has_translation(&translations, "de"));
+        assert!(!has_translation(&translations, "fr"));
+    }
+
+    #[test]
+    fn has_translation_matches_the_country_when_given() {
+        let translations = vec![translation("pt", "PT"), translation("de", "DE")];
+
+        assert!(has_translation(&translations, "de-DE"));
+        assert!(!has_translation(&translations, "pt-BR"));
+        assert!(!has_translation(&[], "en-US"));
+    }
+
+    #[test]
+    fn translated_title_skips_translations_without_one() {
+        let titled = |language, country, title: &str| Translation {
+            data: TranslationData { title: title.to_owned() },
+            ..translation(language, country)
+        };
+        let translations = vec![titled("fr", "CA", ""), titled("fr", "FR", "Le Voyage"),
+                                titled("de", "DE", "Die Reise")];
+
+        assert_eq!(translated_title(&translations, "fr"), Some("Le Voyage"));
+        assert_eq!(translated_title(&translations, "fr-CA"), None);
+        assert_eq!(translated_title(&translations, "de-DE"), Some("Die Reise"));
+    }
+
+    #[test]
+    fn is_excluded_looks_at_companies_keywords_and_people() {
+        let entries = |ids: &[u32]| ids.iter().map(|&id| Entry { id }).collect::<Vec<Entry>>();
+        let details = MovieDetails {
+            status: None,
+            runtime: None,
+            production_companies: entries(&[3]),
+            keywords: Keywords { keywords: entries(&[9715]) },
+            credits: Credits { cast: entries(&[7]), crew: entries(&[8]) },
+        };
+        let excluding = |company_ids: &[u32], keyword_ids: &[u32], person_ids: &[u32]| {
+            Exclusions {
+                company_ids: company_ids.to_vec(),
+                keyword_ids: keyword_ids.to_vec(),
+                person_ids: person_ids.to_vec(),
+            }
+        };
+
+        assert!(is_excluded(&details, &excluding(&[3], &[], &[])));
+        assert!(is_excluded(&details, &excluding(&[], &[9715], &[])));
+        assert!(is_excluded(&details, &excluding(&[], &[], &[8])));
+        assert!(!is_excluded(&details, &excluding(&[4], &[1], &[2])));
+        assert!(!is_excluded(&details, &Exclusions::default()));
+    }
+}
@@ -0,0 +1,269 @@
+// Full screen browser for the upcoming movies of the watched genres: a list
+// to move through with the chosen movie's details and overview next to it,
+// narrowed down by genre and keyword, and movies opened or marked as seen
+// right from it, in the same store as every other command.
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{HashMap, HashSet};
+
+use crate::calendar;
+use crate::filter::get_genre_name_from_ids;
+use crate::links;
+use crate::store::MovieStore;
+use crate::tmdb::Movie;
+use crate::AppError;
+
+const HELP: &str =
+    "up/down move  pgup/pgdn scroll  / keyword  g genre  enter open  m mark seen  q quit";
+
+// lines PageUp and PageDown scroll the overview by
+const SCROLL_LINES: u16 = 5;
+
+struct App<'a> {
+    movies: &'a [&'a Movie],
+    genre_map: &'a HashMap<u32, String>,
+    opened: &'a mut HashSet<u32>,
+    // every genre of the movies by name, in the order `g` goes through them
+    genres: Vec<(&'a str, u32)>,
+    // index into `genres`, all of them when not set
+    genre: Option<usize>,
+    keyword: String,
+    // the keyword is being typed
+    typing: bool,
+    // indexes into `movies` of the ones that match
+    shown: Vec<usize>,
+    list: ListState,
+    scroll: u16,
+    // outcome of the last key pressed, shown instead of the help
+    status: String,
+}
+
+/// Runs until the user quits.  Movies opened or marked as seen are added to
+/// `opened` and the store.
+pub fn browse(movies: &[&Movie], genre_map: &HashMap<u32, String>, store: &MovieStore,
+              opened: &mut HashSet<u32>, browser: Option<&str>) -> Result<(), AppError> {
+    let mut genre_ids: Vec<u32> = movies.iter().flat_map(|m| m.genre_ids.iter().cloned()).collect();
+    genre_ids.sort();
+    genre_ids.dedup();
+
+    let mut genres: Vec<(&str, u32)> = genre_ids
+        .into_iter()
+        .filter_map(|id| genre_map.get(&id).map(|name| (name.as_str(), id)))
+        .collect();
+    genres.sort();
+
+    let mut app = App {
+        movies,
+        genre_map,
+        opened,
+        genres,
+        genre: None,
+        keyword: String::new(),
+        typing: false,
+        shown: Vec::new(),
+        list: ListState::default(),
+        scroll: 0,
+        status: String::new(),
+    };
+    app.refilter();
+
+    let mut terminal = ratatui::try_init()?;
+    let result = run_app(&mut terminal, &mut app, store, browser);
+    ratatui::restore();
+
+    result
+}
+
+fn run_app(terminal: &mut DefaultTerminal, app: &mut App<'_>, store: &MovieStore,
+           browser: Option<&str>) -> Result<(), AppError> {
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        app.status.clear();
+
+        if app.typing {
+            match key.code {
+                KeyCode::Enter | KeyCode::Esc => app.typing = false,
+                KeyCode::Backspace => {
+                    app.keyword.pop();
+                    app.refilter();
+                },
+                KeyCode::Char(c) => {
+                    app.keyword.push(c);
+                    app.refilter();
+                },
+                _ => {},
+            }
+
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+            KeyCode::PageDown => app.scroll = app.scroll.saturating_add(SCROLL_LINES),
+            KeyCode::PageUp => app.scroll = app.scroll.saturating_sub(SCROLL_LINES),
+            KeyCode::Char('/') => app.typing = true,
+            KeyCode::Char('g') => app.next_genre(),
+            KeyCode::Enter => app.open(store, browser)?,
+            KeyCode::Char('m') => app.mark_seen(store)?,
+            _ => {},
+        }
+    }
+}
+
+impl<'a> App<'a> {
+    fn selected(&self) -> Option<&'a Movie> {
+        self.list.selected().and_then(|i| self.shown.get(i)).map(|&i| self.movies[i])
+    }
+
+    fn genre_name(&self) -> &str {
+        self.genre.map(|i| self.genres[i].0).unwrap_or("all")
+    }
+
+    fn is_match(&self, movie: &Movie) -> bool {
+        let genre_names = get_genre_name_from_ids(&movie.genre_ids, self.genre_map);
+        let keyword = self.keyword.to_lowercase();
+
+        let is_genre = match self.genre {
+            Some(i) => movie.genre_ids.contains(&self.genres[i].1),
+            None => true,
+        };
+        let is_keyword = [&movie.title, &movie.overview, &genre_names]
+            .iter()
+            .any(|text| text.to_lowercase().contains(&keyword));
+
+        is_genre && is_keyword
+    }
+
+    fn refilter(&mut self) {
+        self.shown = (0..self.movies.len()).filter(|&i| self.is_match(self.movies[i])).collect();
+
+        self.list.select(if self.shown.is_empty() { None } else { Some(0) });
+        self.scroll = 0;
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        if let Some(i) = self.list.selected() {
+            let last = self.shown.len().saturating_sub(1);
+            self.list.select(Some(i.saturating_add_signed(by).min(last)));
+            self.scroll = 0;
+        }
+    }
+
+    // all genres, then every genre in turn
+    fn next_genre(&mut self) {
+        self.genre = match self.genre {
+            None if !self.genres.is_empty() => Some(0),
+            Some(i) if i + 1 < self.genres.len() => Some(i + 1),
+            _ => None,
+        };
+
+        self.refilter();
+    }
+
+    fn open(&mut self, store: &MovieStore, browser: Option<&str>) -> Result<(), AppError> {
+        let movie = match self.selected() {
+            Some(movie) => movie,
+            None => return Ok(()),
+        };
+        let url = links::tmd_movie_url(movie.id);
+
+        match links::open_in_browser(&url, browser) {
+            Ok(()) => {
+                store.record_delivery(movie.id, "browser", "sent", None)?;
+                store.record_alerted(movie.id)?;
+                store.record_open(movie.id)?;
+                self.opened.insert(movie.id);
+                self.status = format!("Opened {}", movie.title);
+            },
+            Err(e) => {
+                store.record_delivery(movie.id, "browser", &format!("failed: {}", e), None)?;
+                self.status = format!("Cannot open {}: {}", movie.title, e);
+            },
+        }
+
+        Ok(())
+    }
+
+    fn mark_seen(&mut self, store: &MovieStore) -> Result<(), AppError> {
+        if let Some(movie) = self.selected() {
+            store.record_alerted(movie.id)?;
+            self.opened.insert(movie.id);
+            self.status = format!("Marked {} as seen", movie.title);
+        }
+
+        Ok(())
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App<'_>) {
+    let [main_area, footer_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+        .areas(frame.area());
+    let [list_area, detail_area] = Layout::horizontal([Constraint::Percentage(45),
+                                                       Constraint::Percentage(55)])
+        .areas(main_area);
+
+    // new movies are starred, like [NEW] in the list command
+    let items: Vec<ListItem> = app.shown
+        .iter()
+        .map(|&i| {
+            let movie = app.movies[i];
+            let marker = if app.opened.contains(&movie.id) { " " } else { "*" };
+            ListItem::new(format!("{} {:<10}  {}", marker, release_date_label(movie),
+                                  movie.title))
+        })
+        .collect();
+
+    let title = format!(" {} of {} movies, genre: {}, keyword: {} ", app.shown.len(),
+                        app.movies.len(), app.genre_name(),
+                        if app.keyword.is_empty() { "none" } else { &app.keyword });
+    let list = List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, list_area, &mut app.list);
+
+    let bold = Style::new().add_modifier(Modifier::BOLD);
+
+    let details = match app.selected() {
+        Some(movie) => vec![
+            Line::from(Span::styled(movie.title.clone(), bold)),
+            Line::from(format!("Release date: {}", release_date_label(movie))),
+            Line::from(format!("Genres: {}", get_genre_name_from_ids(&movie.genre_ids,
+                                                                     app.genre_map))),
+            Line::from(links::tmd_movie_url(movie.id)),
+            Line::from(""),
+            Line::from(movie.overview.clone()),
+        ],
+        None => vec![Line::from("No movies match")],
+    };
+    let details = Paragraph::new(details)
+        .block(Block::bordered())
+        .wrap(Wrap { trim: false })
+        .scroll((app.scroll, 0));
+    frame.render_widget(details, detail_area);
+
+    let footer = if app.typing {
+        format!("Keyword: {}_", app.keyword)
+    } else if !app.status.is_empty() {
+        app.status.clone()
+    } else {
+        HELP.to_owned()
+    };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+}
+
+fn release_date_label(movie: &Movie) -> &str {
+    if calendar::is_date_tbd(&movie.release_date) { "TBD" } else { &movie.release_date }
+}
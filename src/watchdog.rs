@@ -0,0 +1,80 @@
+// Hard caps on what a single run may do.  Every api call and every opened
+// movie goes through the watchdog first, and the run is aborted as soon as
+// one of the caps is exceeded.  A run that is stuck waiting, say on a host
+// that does not answer, is cut off by `capped` once it runs out of time.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+use crate::AppError;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RunLimits {
+    pub max_api_calls: Option<u32>,
+    pub max_notifications: Option<u32>,
+    pub max_run_time: Option<Duration>,
+}
+
+pub struct RunWatchdog {
+    limits: RunLimits,
+    started: Instant,
+    api_calls: u32,
+    notifications: u32,
+}
+
+impl RunWatchdog {
+    pub fn start(limits: RunLimits) -> Self {
+        RunWatchdog {
+            limits,
+            started: Instant::now(),
+            api_calls: 0,
+            notifications: 0,
+        }
+    }
+
+    pub fn api_call(&mut self) -> Result<(), AppError> {
+        self.check_run_time()?;
+
+        self.api_calls += 1;
+
+        match self.limits.max_api_calls {
+            Some(max) if self.api_calls > max => {
+                Err(AppError::RunLimitExceededError(format!("more than {} api calls", max)))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    pub fn notification(&mut self) -> Result<(), AppError> {
+        self.check_run_time()?;
+
+        self.notifications += 1;
+
+        match self.limits.max_notifications {
+            Some(max) if self.notifications > max => {
+                Err(AppError::RunLimitExceededError(format!("more than {} notifications", max)))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn check_run_time(&self) -> Result<(), AppError> {
+        match self.limits.max_run_time {
+            Some(max) if self.started.elapsed() > max => Err(run_time_exceeded(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `run`, given up on once it takes longer than the max run time of
+/// `limits`, if there is one.
+pub async fn capped<T>(limits: RunLimits, run: impl Future<Output = Result<T, AppError>>)
+                       -> Result<T, AppError> {
+    match limits.max_run_time {
+        Some(max) => tokio::time::timeout(max, run).await.unwrap_or(Err(run_time_exceeded(max))),
+        None => run.await,
+    }
+}
+
+fn run_time_exceeded(max: Duration) -> AppError {
+    AppError::RunLimitExceededError(format!("run took longer than {} seconds", max.as_secs()))
+}
@@ -0,0 +1,46 @@
+// Posts all new movies of a run as json to a url of the user's choosing, for
+// wiring alerts into Home Assistant, n8n and such:
+//
+//     {"movies": [{"id": 1, "title": "...", "genres": "Animation, Family",
+//                  "release_date": "2025-07-18", "poster_url": "...",
+//                  "url": "https://www.themoviedb.org/movie/1",
+//                  "groups": [], "rating": "7.5 of 1,234 votes",
+//                  "runtime": "1h 42m"}],
+//      "digest": {"id": "18a2f0c3b1d6e000", "part": 1, "parts": 1}}
+//
+// A digest of more than 25 movies is posted in parts with the same id.
+
+use std::fs;
+
+use crate::config::WebhookConfig;
+use crate::digest::{DigestMovie, DigestPart};
+use crate::http;
+use crate::AppError;
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    movies: &'a [DigestMovie],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digest: Option<&'a DigestPart>,
+}
+
+pub async fn post_movies(settings: &WebhookConfig, movies: &[DigestMovie],
+                         part: Option<&DigestPart>) -> Result<(), AppError> {
+    let mut request = http::client()
+        .post(&settings.url)
+        .json(&Payload { movies, digest: part });
+
+    if let Some(ref path) = settings.token_file {
+        request = request.bearer_auth(fs::read_to_string(path)?.trim());
+    }
+
+    for (name, value) in settings.headers.iter() {
+        request = request.header(name, value);
+    }
+
+    debug!("Posting {} movies to {}", movies.len(), settings.url);
+
+    http::check_post(request.send().await, "Error: cannot post new movies to the webhook").await?;
+
+    Ok(())
+}
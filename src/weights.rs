@@ -0,0 +1,79 @@
+// How much an alert in each genre is worth, learned from which of the
+// alerted movies were then opened by hand, with `open` or from the tui.  A
+// genre starts out at 0.5 and moves towards the share of its alerted movies
+// that were opened as alerts about it pile up.
+
+use std::collections::BTreeMap;
+
+use crate::store::MovieStore;
+use crate::AppError;
+
+// weight of a genre nothing was alerted about yet
+pub const PRIOR_WEIGHT: f64 = 0.5;
+
+pub struct GenreWeight {
+    pub genre: String,
+    // alerted movies in the genre
+    pub alerted: u32,
+    // of those, the ones opened by hand
+    pub opened: u32,
+}
+
+impl GenreWeight {
+    // one opened and one unopened movie taken as given, so a single alert
+    // does not swing it all the way
+    pub fn weight(&self) -> f64 {
+        (self.opened as f64 + 1.0) / (self.alerted as f64 + 2.0)
+    }
+}
+
+// genre names as the store keeps them, "Animation, Family"
+pub(crate) fn split_genres(genres: &str) -> impl Iterator<Item = &str> {
+    genres.split(',').map(|name| name.trim()).filter(|name| !name.is_empty())
+}
+
+/// Weights of every genre alerted about, the highest first.
+pub fn genre_weights(store: &MovieStore) -> Result<Vec<GenreWeight>, AppError> {
+    let opened = store.user_opened_ids()?;
+
+    let mut by_genre: BTreeMap<&str, GenreWeight> = BTreeMap::new();
+    let movies = store.alerted_genres()?;
+
+    for (id, genres) in movies.iter() {
+        for name in split_genres(genres) {
+            let weight = by_genre.entry(name).or_insert_with(|| {
+                GenreWeight { genre: name.to_owned(), alerted: 0, opened: 0 }
+            });
+
+            weight.alerted += 1;
+            if opened.contains(id) {
+                weight.opened += 1;
+            }
+        }
+    }
+
+    let mut weights: Vec<GenreWeight> = by_genre.into_values().collect();
+    weights.sort_by(|a, b| b.weight().total_cmp(&a.weight()).then(a.genre.cmp(&b.genre)));
+
+    Ok(weights)
+}
+
+/// Score of a movie in `genres` (e.g. "Animation, Family"), the mean weight
+/// of its genres.
+pub fn score(genres: &str, weights: &[GenreWeight]) -> f64 {
+    let genre_weights: Vec<f64> = split_genres(genres)
+        .map(|name| {
+            weights
+                .iter()
+                .find(|w| w.genre == name)
+                .map(|w| w.weight())
+                .unwrap_or(PRIOR_WEIGHT)
+        })
+        .collect();
+
+    if genre_weights.is_empty() {
+        return PRIOR_WEIGHT;
+    }
+
+    genre_weights.iter().sum::<f64>() / genre_weights.len() as f64
+}